@@ -1,34 +1,19 @@
-use std::fs;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
 use std::thread;
+use std::time::Duration;
 
+use serde::Serialize;
 use serde_json::{json, Value};
 use tauri::{AppHandle, Emitter};
 
-fn data_path() -> std::path::PathBuf {
-    let home = dirs::home_dir().expect("Could not find home directory");
-    home.join(".visor")
-}
-
-fn load_json() -> Value {
-    let path = data_path().join("data.json");
-    if path.exists() {
-        let raw = fs::read_to_string(&path).unwrap_or_else(|_| "{}".to_string());
-        serde_json::from_str(&raw).unwrap_or(json!({}))
-    } else {
-        json!({})
-    }
-}
-
-fn save_json(data: &Value) {
-    let dir = data_path();
-    let _ = fs::create_dir_all(&dir);
-    let _ = fs::write(dir.join("data.json"), serde_json::to_string_pretty(data).unwrap_or_default());
-}
+use crate::storage::{self, load_json, update_json};
+use crate::{calendar, export, history, import, journal, report, search, settings, snapshots, sync, tasks, templates};
 
 fn cors_headers() -> Vec<tiny_http::Header> {
     vec![
         "Access-Control-Allow-Origin: *".parse().unwrap(),
-        "Access-Control-Allow-Methods: GET, POST, PUT, DELETE, OPTIONS".parse().unwrap(),
+        "Access-Control-Allow-Methods: GET, POST, PUT, PATCH, DELETE, OPTIONS".parse().unwrap(),
         "Access-Control-Allow-Headers: Content-Type".parse().unwrap(),
         "Content-Type: application/json".parse().unwrap(),
     ]
@@ -40,30 +25,109 @@ fn respond_json(request: tiny_http::Request, status: u16, body: &Value) {
     let response = tiny_http::Response::from_string(body_str)
         .with_status_code(status_code)
         .with_header("Access-Control-Allow-Origin: *".parse::<tiny_http::Header>().unwrap())
-        .with_header("Access-Control-Allow-Methods: GET, POST, PUT, DELETE, OPTIONS".parse::<tiny_http::Header>().unwrap())
+        .with_header("Access-Control-Allow-Methods: GET, POST, PUT, PATCH, DELETE, OPTIONS".parse::<tiny_http::Header>().unwrap())
         .with_header("Access-Control-Allow-Headers: Content-Type".parse::<tiny_http::Header>().unwrap())
         .with_header("Content-Type: application/json".parse::<tiny_http::Header>().unwrap());
     let _ = request.respond(response);
 }
 
+fn respond_text(request: tiny_http::Request, content_type: &str, body: String) {
+    let mut response = tiny_http::Response::from_string(body);
+    for header in cors_headers() {
+        if !header.field.equiv("Content-Type") {
+            response = response.with_header(header);
+        }
+    }
+    let content_type = format!("Content-Type: {}", content_type);
+    let _ = request.respond(response.with_header(content_type.parse::<tiny_http::Header>().unwrap()));
+}
+
+/// Decode an `application/x-www-form-urlencoded` query value (`+` and `%XX`).
+fn decode_param(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 2;
+            }
+            (b'+', _) => out.push(b' '),
+            (b, _) => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// The decoded value of `key` in `query`, if it's given.
+fn query_param(query: &str, key: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|s| s.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| decode_param(v))
+}
+
 fn read_body(request: &mut tiny_http::Request) -> Value {
     let mut body = String::new();
     let _ = request.as_reader().read_to_string(&mut body);
     serde_json::from_str(&body).unwrap_or(json!({}))
 }
 
-pub fn start_api_server(app_handle: AppHandle) {
-    thread::spawn(move || {
-        let server = match tiny_http::Server::http("127.0.0.1:8745") {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("Failed to start API server: {}", e);
-                return;
+/// Runtime controls for the API server, managed as Tauri state (`Arc<ApiState>`).
+#[derive(Default)]
+pub struct ApiState {
+    paused: AtomicBool,
+    /// Focus timers started headless, so only the latest one reports.
+    focus_sessions: AtomicU64,
+}
+
+impl ApiState {
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// Run a focus timer without the webview, which normally keeps it. There
+    /// is nowhere to show a notification, so the end is printed to stderr.
+    /// Starting another replaces it.
+    fn start_focus(self: &Arc<Self>, minutes: u64) {
+        let session = self.focus_sessions.fetch_add(1, Ordering::Relaxed) + 1;
+        let state = Arc::clone(self);
+        thread::spawn(move || {
+            thread::sleep(Duration::from_secs(minutes * 60));
+            if state.focus_sessions.load(Ordering::Relaxed) == session {
+                eprintln!("Focus session complete ({} min)", minutes);
             }
-        };
+        });
+    }
+}
 
-        println!("Visor API server listening on http://127.0.0.1:8745");
+/// Notify the webview, if there is one. Headless mode has no app to tell.
+fn emit<S: Serialize + Clone>(app_handle: &Option<AppHandle>, event: &str, payload: S) {
+    if let Some(app) = app_handle {
+        let _ = app.emit(event, payload);
+    }
+}
 
+/// Bind the API port (`settings.api.port`) and serve it on a background
+/// thread. `app_handle` is `None` in headless mode, where nothing listens for
+/// `data-changed`.
+pub fn start_api_server(
+    state: Arc<ApiState>,
+    app_handle: Option<AppHandle>,
+) -> Result<thread::JoinHandle<()>, String> {
+    let addr = settings::load().api_addr();
+    let server = tiny_http::Server::http(&addr).map_err(|e| e.to_string())?;
+    println!("Visor API server listening on http://{}", addr);
+
+    Ok(thread::spawn(move || {
         for mut request in server.incoming_requests() {
             let method = request.method().to_string();
             let url = request.url().to_string();
@@ -80,6 +144,18 @@ pub fn start_api_server(app_handle: AppHandle) {
                 continue;
             }
 
+            // Paused from the tray: keep the port, refuse the work
+            if state.is_paused() {
+                respond_json(request, 503, &json!({"error": "api paused"}));
+                continue;
+            }
+
+            // Encrypted data waiting for its passphrase
+            if let Err(e) = storage::ensure_unlocked() {
+                respond_json(request, 423, &json!({"error": e}));
+                continue;
+            }
+
             // Parse path and query
             let (path, query_string) = if let Some(idx) = url.find('?') {
                 (&url[..idx], &url[idx + 1..])
@@ -91,23 +167,7 @@ pub fn start_api_server(app_handle: AppHandle) {
                 // GET /api/status
                 ("GET", "/api/status") => {
                     let data = load_json();
-                    let tasks = data.get("tasks").cloned().unwrap_or(json!({}));
-                    let projects = data.get("projects").cloned().unwrap_or(json!({}));
-
-                    let task_count = tasks.as_object().map_or(0, |t| t.len());
-                    let project_count = projects.as_object().map_or(0, |p| p.len());
-                    let pending = tasks.as_object().map_or(0, |t| {
-                        t.values().filter(|v| {
-                            !v.get("archived").and_then(|a| a.as_bool()).unwrap_or(false)
-                                && !v.get("completed").and_then(|c| c.as_bool()).unwrap_or(false)
-                        }).count()
-                    });
-
-                    respond_json(request, 200, &json!({
-                        "tasks": task_count,
-                        "projects": project_count,
-                        "pending": pending
-                    }));
+                    respond_json(request, 200, &tasks::status_summary(&data));
                 }
 
                 // GET /api/projects
@@ -131,7 +191,6 @@ pub fn start_api_server(app_handle: AppHandle) {
                         continue;
                     }
 
-                    let mut data = load_json();
                     let id = uuid::Uuid::new_v4().to_string();
                     let project = json!({
                         "id": id,
@@ -143,19 +202,25 @@ pub fn start_api_server(app_handle: AppHandle) {
                         "isInbox": false
                     });
 
-                    if data.get("projects").is_none() {
-                        data["projects"] = json!({});
+                    let saved = update_json(|data| {
+                        if data.get("projects").is_none() {
+                            data["projects"] = json!({});
+                        }
+                        data["projects"][&id] = project.clone();
+                        Ok(())
+                    });
+                    match saved {
+                        Ok(()) => {
+                            emit(&app_handle, "data-changed", ());
+                            respond_json(request, 201, &project);
+                        }
+                        Err(e) => respond_json(request, 500, &json!({"error": e})),
                     }
-                    data["projects"][&id] = project.clone();
-                    save_json(&data);
-                    let _ = app_handle.emit("data-changed", ());
-                    respond_json(request, 201, &project);
                 }
 
                 // GET /api/tasks
                 ("GET", "/api/tasks") => {
                     let data = load_json();
-                    let tasks = data.get("tasks").cloned().unwrap_or(json!({}));
 
                     // Parse query params
                     let params: Vec<(&str, &str)> = query_string.split('&')
@@ -166,48 +231,7 @@ pub fn start_api_server(app_handle: AppHandle) {
                     let filter_project = params.iter().find(|(k, _)| *k == "project").map(|(_, v)| *v);
                     let filter_status = params.iter().find(|(k, _)| *k == "status").map(|(_, v)| *v);
 
-                    let projects = data.get("projects").cloned().unwrap_or(json!({}));
-
-                    let list: Vec<Value> = tasks.as_object()
-                        .map(|t| {
-                            t.values()
-                                .filter(|task| {
-                                    // Filter by project slug
-                                    if let Some(slug) = filter_project {
-                                        let project_id = task.get("projectId").and_then(|p| p.as_str()).unwrap_or("");
-                                        let project_slug = projects.get(project_id)
-                                            .and_then(|p| p.get("slug"))
-                                            .and_then(|s| s.as_str())
-                                            .unwrap_or("");
-                                        if project_slug != slug {
-                                            return false;
-                                        }
-                                    }
-                                    // Filter by status
-                                    if let Some(status) = filter_status {
-                                        match status {
-                                            "pending" => {
-                                                let archived = task.get("archived").and_then(|a| a.as_bool()).unwrap_or(false);
-                                                let completed = task.get("completed").and_then(|c| c.as_bool()).unwrap_or(false);
-                                                if archived || completed {
-                                                    return false;
-                                                }
-                                            }
-                                            other => {
-                                                let task_status = task.get("status").and_then(|s| s.as_str()).unwrap_or("TODO");
-                                                if task_status != other.to_uppercase() {
-                                                    return false;
-                                                }
-                                            }
-                                        }
-                                    }
-                                    true
-                                })
-                                .cloned()
-                                .collect()
-                        })
-                        .unwrap_or_default();
-
+                    let list = tasks::list_tasks(&data, filter_project, filter_status);
                     respond_json(request, 200, &json!(list));
                 }
 
@@ -222,137 +246,405 @@ pub fn start_api_server(app_handle: AppHandle) {
                         continue;
                     }
 
-                    let mut data = load_json();
-                    let projects = data.get("projects").cloned().unwrap_or(json!({}));
-
-                    // Find project by slug
-                    let (project_id, _) = projects.as_object()
-                        .and_then(|p| {
-                            p.iter().find(|(_, v)| {
-                                v.get("slug").and_then(|s| s.as_str()) == Some(&project_slug)
-                            })
-                        })
-                        .map(|(k, v)| (k.clone(), v.clone()))
-                        .unwrap_or_else(|| ("inbox".to_string(), json!({})));
-
-                    let task_id = uuid::Uuid::new_v4().to_string();
-                    let now = chrono::Utc::now().timestamp_millis();
-                    let task = json!({
-                        "id": task_id,
-                        "content": content,
-                        "completed": false,
-                        "status": "TODO",
-                        "archived": false,
-                        "projectId": project_id,
-                        "parentId": null,
-                        "indent": 0,
-                        "createdAt": now,
-                        "completedAt": null,
-                        "dueAt": null,
-                        "scheduled": null,
-                        "notes": null,
-                        "recurrence": null
-                    });
-
-                    if data.get("tasks").is_none() {
-                        data["tasks"] = json!({});
-                    }
-                    data["tasks"][&task_id] = task.clone();
-
-                    // Add to project taskOrder
-                    if let Some(proj) = data["projects"].get_mut(&project_id) {
-                        if let Some(order) = proj.get_mut("taskOrder") {
-                            if let Some(arr) = order.as_array_mut() {
-                                arr.push(json!(task_id));
-                            }
+                    match update_json(|data| tasks::create_task(data, &content, &project_slug)) {
+                        Ok(task) => {
+                            emit(&app_handle, "data-changed", ());
+                            respond_json(request, 201, &task);
                         }
+                        Err(e) => respond_json(request, 400, &json!({"error": e})),
                     }
-
-                    save_json(&data);
-                    let _ = app_handle.emit("data-changed", ());
-                    respond_json(request, 201, &task);
                 }
 
                 // PUT /api/tasks/:id/complete
                 _ if method == "PUT" && path.starts_with("/api/tasks/") && path.ends_with("/complete") => {
                     let task_id = path.trim_start_matches("/api/tasks/").trim_end_matches("/complete");
-                    let mut data = load_json();
-
-                    if let Some(task) = data.get_mut("tasks").and_then(|t| t.get_mut(task_id)) {
-                        task["status"] = json!("DONE");
-                        task["completed"] = json!(true);
-                        task["completedAt"] = json!(chrono::Utc::now().timestamp_millis());
-                        let updated = task.clone();
-                        save_json(&data);
-                        let _ = app_handle.emit("data-changed", ());
-                        respond_json(request, 200, &updated);
-                    } else {
-                        respond_json(request, 404, &json!({"error": "task not found"}));
+
+                    match update_json(|data| Ok(tasks::complete_task(data, task_id))) {
+                        Ok(Some(updated)) => {
+                            emit(&app_handle, "data-changed", ());
+                            respond_json(request, 200, &updated);
+                        }
+                        Ok(None) => respond_json(request, 404, &json!({"error": "task not found"})),
+                        Err(e) => respond_json(request, 500, &json!({"error": e})),
                     }
                 }
 
                 // PUT /api/tasks/:id/archive
                 _ if method == "PUT" && path.starts_with("/api/tasks/") && path.ends_with("/archive") => {
                     let task_id = path.trim_start_matches("/api/tasks/").trim_end_matches("/archive");
-                    let mut data = load_json();
-
-                    if let Some(task) = data.get_mut("tasks").and_then(|t| t.get_mut(task_id)) {
-                        task["archived"] = json!(true);
-                        let updated = task.clone();
-                        save_json(&data);
-                        let _ = app_handle.emit("data-changed", ());
-                        respond_json(request, 200, &updated);
-                    } else {
-                        respond_json(request, 404, &json!({"error": "task not found"}));
+
+                    match update_json(|data| Ok(tasks::archive_task(data, task_id))) {
+                        Ok(Some(updated)) => {
+                            emit(&app_handle, "data-changed", ());
+                            respond_json(request, 200, &updated);
+                        }
+                        Ok(None) => respond_json(request, 404, &json!({"error": "task not found"})),
+                        Err(e) => respond_json(request, 500, &json!({"error": e})),
                     }
                 }
 
                 // GET /api/log
                 ("GET", "/api/log") => {
-                    let data = load_json();
-                    let log = data.get("logEntries").cloned().unwrap_or(json!([]));
-                    respond_json(request, 200, &log);
+                    let param = |key: &str| query_param(query_string, key);
+
+                    let from = param("from").map(|v| journal::parse_bound(&v, false).ok_or(v)).transpose();
+                    let to = param("to").map(|v| journal::parse_bound(&v, true).ok_or(v)).transpose();
+                    let (from, to) = match (from, to) {
+                        (Ok(from), Ok(to)) => (from, to),
+                        (Err(v), _) | (_, Err(v)) => {
+                            respond_json(request, 400, &json!({"error": format!("invalid date: {} (expected YYYY-MM-DD or ms)", v)}));
+                            continue;
+                        }
+                    };
+                    let query = journal::LogQuery {
+                        project: param("project"),
+                        task_id: param("task"),
+                        from,
+                        to,
+                        text: param("q").filter(|q| !q.is_empty()),
+                        before: param("before"),
+                        limit: param("limit").and_then(|l| l.parse().ok()),
+                    };
+
+                    match journal::list_entries(&load_json(), &query) {
+                        Ok(entries) => respond_json(request, 200, &entries),
+                        Err(e) => respond_json(request, 404, &json!({"error": e})),
+                    }
                 }
 
                 // POST /api/log
                 ("POST", "/api/log") => {
                     let body = read_body(&mut request);
                     let content = body.get("content").and_then(|c| c.as_str()).unwrap_or("").to_string();
-                    let project_slug = body.get("project").and_then(|p| p.as_str()).unwrap_or("inbox").to_string();
+                    let project_slug = body.get("project").and_then(|p| p.as_str());
+                    let task_id = body.get("taskId").and_then(|t| t.as_str());
 
-                    if content.is_empty() {
-                        respond_json(request, 400, &json!({"error": "content required"}));
-                        continue;
+                    match update_json(|data| journal::add_entry(data, &content, project_slug, task_id)) {
+                        Ok(entry) => {
+                            emit(&app_handle, "data-changed", ());
+                            respond_json(request, 201, &entry);
+                        }
+                        Err(e) => respond_json(request, 400, &json!({"error": e})),
                     }
+                }
 
-                    let mut data = load_json();
-                    let projects = data.get("projects").cloned().unwrap_or(json!({}));
-                    let project_id = projects.as_object()
-                        .and_then(|p| {
-                            p.iter().find(|(_, v)| {
-                                v.get("slug").and_then(|s| s.as_str()) == Some(&project_slug)
-                            })
-                        })
-                        .map(|(k, _)| k.clone())
-                        .unwrap_or_else(|| "inbox".to_string());
+                // PATCH /api/log/:id
+                _ if method == "PATCH" && path.starts_with("/api/log/") => {
+                    let entry_id = path.trim_start_matches("/api/log/").to_string();
+                    let body = read_body(&mut request);
 
-                    let entry = json!({
-                        "id": uuid::Uuid::new_v4().to_string(),
-                        "content": content,
-                        "createdAt": chrono::Utc::now().timestamp_millis(),
-                        "projectId": project_id
+                    match update_json(|data| journal::update_entry(data, &entry_id, &body)) {
+                        Ok(Some(updated)) => {
+                            emit(&app_handle, "data-changed", ());
+                            respond_json(request, 200, &updated);
+                        }
+                        Ok(None) => respond_json(request, 404, &json!({"error": "entry not found"})),
+                        Err(e) => respond_json(request, 400, &json!({"error": e})),
+                    }
+                }
+
+                // DELETE /api/log/:id
+                _ if method == "DELETE" && path.starts_with("/api/log/") => {
+                    let entry_id = path.trim_start_matches("/api/log/");
+
+                    match update_json(|data| Ok(journal::delete_entry(data, entry_id))) {
+                        Ok(Some(deleted)) => {
+                            emit(&app_handle, "data-changed", ());
+                            respond_json(request, 200, &deleted);
+                        }
+                        Ok(None) => respond_json(request, 404, &json!({"error": "entry not found"})),
+                        Err(e) => respond_json(request, 500, &json!({"error": e})),
+                    }
+                }
+
+                // GET /api/templates
+                ("GET", "/api/templates") => {
+                    respond_json(request, 200, &templates::list_templates(&load_json()));
+                }
+
+                // POST /api/templates
+                ("POST", "/api/templates") => {
+                    let body = read_body(&mut request);
+                    let new_template: templates::NewTemplate = match serde_json::from_value(body) {
+                        Ok(t) => t,
+                        Err(e) => {
+                            respond_json(request, 400, &json!({"error": e.to_string()}));
+                            continue;
+                        }
+                    };
+
+                    match update_json(|data| templates::create_template(data, &new_template)) {
+                        Ok(template) => {
+                            emit(&app_handle, "data-changed", ());
+                            respond_json(request, 201, &template);
+                        }
+                        Err(e) => respond_json(request, 400, &json!({"error": e})),
+                    }
+                }
+
+                // POST /api/templates/:id/apply
+                _ if method == "POST" && path.starts_with("/api/templates/") && path.ends_with("/apply") => {
+                    let template_id = path.trim_start_matches("/api/templates/").trim_end_matches("/apply");
+                    let project = query_param(query_string, "project");
+                    let params: templates::TemplateParams = match serde_json::from_value(read_body(&mut request)) {
+                        Ok(p) => p,
+                        Err(e) => {
+                            respond_json(request, 400, &json!({"error": e.to_string()}));
+                            continue;
+                        }
+                    };
+                    // Kept aside so the response can tell the kinds of failure apart
+                    let mut failure = None;
+                    let applied = update_json(|data| {
+                        templates::apply_template(data, template_id, project.as_deref(), &params).map_err(|e| {
+                            let message = e.to_string();
+                            failure = Some(e);
+                            message
+                        })
                     });
 
-                    if data.get("logEntries").is_none() {
-                        data["logEntries"] = json!([]);
+                    match (applied, failure) {
+                        (Ok(created), _) => {
+                            emit(&app_handle, "data-changed", ());
+                            respond_json(request, 201, &json!(created));
+                        }
+                        (Err(_), Some(e)) => {
+                            let mut body = json!({"error": e.to_string()});
+                            if let templates::ApplyError::MissingVariables(missing) = &e {
+                                body["missing"] = json!(missing);
+                            }
+                            let status = if matches!(e, templates::ApplyError::NotFound(_)) { 404 } else { 400 };
+                            respond_json(request, status, &body);
+                        }
+                        (Err(e), None) => respond_json(request, 500, &json!({"error": e})),
+                    }
+                }
+
+                // DELETE /api/templates/:id
+                _ if method == "DELETE" && path.starts_with("/api/templates/") => {
+                    let template_id = path.trim_start_matches("/api/templates/");
+
+                    match update_json(|data| Ok(templates::delete_template(data, template_id))) {
+                        Ok(Some(deleted)) => {
+                            emit(&app_handle, "data-changed", ());
+                            respond_json(request, 200, &deleted);
+                        }
+                        Ok(None) => respond_json(request, 404, &json!({"error": "template not found"})),
+                        Err(e) => respond_json(request, 500, &json!({"error": e})),
+                    }
+                }
+
+                // POST /api/import
+                ("POST", "/api/import") => {
+                    let body = read_body(&mut request);
+                    let import_request: import::ImportRequest = match serde_json::from_value(body) {
+                        Ok(r) => r,
+                        Err(e) => {
+                            respond_json(request, 400, &json!({"error": e.to_string()}));
+                            continue;
+                        }
+                    };
+
+                    let imported = if import_request.dry_run {
+                        import::import(&mut load_json(), &import_request)
+                    } else {
+                        snapshots::take_quietly("pre-import");
+                        update_json(|data| import::import(data, &import_request))
+                    };
+
+                    match imported {
+                        Ok(report) if report.dry_run || report.tasks.is_empty() => respond_json(request, 200, &json!(report)),
+                        Ok(report) => {
+                            emit(&app_handle, "data-changed", ());
+                            respond_json(request, 201, &json!(report));
+                        }
+                        Err(e) => respond_json(request, 400, &json!({"error": e})),
+                    }
+                }
+
+                // POST /api/calendars/sync
+                ("POST", "/api/calendars/sync") => {
+                    let report = import::ics::run_sync(&app_handle);
+                    respond_json(request, 200, &json!(report));
+                }
+
+                // GET /api/export
+                ("GET", "/api/export") => {
+                    let param = |key: &str| query_param(query_string, key);
+
+                    let Some(format) = export::ExportFormat::parse(param("format").as_deref().unwrap_or("markdown")) else {
+                        respond_json(request, 400, &json!({"error": "format must be markdown, todotxt, csv, org or json"}));
+                        continue;
+                    };
+                    let options = export::ExportOptions {
+                        format,
+                        project: param("project"),
+                        include_archived: matches!(param("archived").as_deref(), Some("true" | "1")),
+                    };
+
+                    match export::export(&load_json(), &options) {
+                        Ok(body) => respond_text(request, format.content_type(), body),
+                        Err(e) => respond_json(request, 404, &json!({"error": e})),
+                    }
+                }
+
+                // GET /api/report
+                ("GET", "/api/report") => {
+                    let param = |key: &str| query_param(query_string, key);
+
+                    let Some(range) = report::ReportRange::parse(param("range").as_deref().unwrap_or("today")) else {
+                        respond_json(request, 400, &json!({"error": "range must be today, yesterday or week"}));
+                        continue;
+                    };
+                    match report::report(&load_json(), range, param("project").as_deref(), chrono::Local::now()) {
+                        Ok(report) if param("format").as_deref() == Some("markdown") => respond_text(
+                            request,
+                            "text/markdown; charset=utf-8",
+                            report["markdown"].as_str().unwrap_or_default().to_string(),
+                        ),
+                        Ok(report) => respond_json(request, 200, &report),
+                        Err(e) => respond_json(request, 404, &json!({"error": e})),
+                    }
+                }
+
+                // GET /api/search
+                ("GET", "/api/search") => {
+                    let param = |key: &str| query_param(query_string, key);
+
+                    let Some(query) = param("q") else {
+                        respond_json(request, 400, &json!({"error": "q is required"}));
+                        continue;
+                    };
+                    let limit = param("limit").and_then(|l| l.parse().ok());
+                    match search::find(&query, limit) {
+                        Ok(hits) => respond_json(request, 200, &json!(hits)),
+                        Err(e) => respond_json(request, 400, &json!({"error": e})),
                     }
-                    if let Some(arr) = data["logEntries"].as_array_mut() {
-                        arr.push(entry.clone());
+                }
+
+                // GET /api/calendar.ics
+                ("GET", "/api/calendar.ics") => {
+                    let param = |key: &str| query_param(query_string, key);
+
+                    let Some(components) = calendar::Components::parse(param("type").as_deref().unwrap_or("all")) else {
+                        respond_json(request, 400, &json!({"error": "type must be todo, event or all"}));
+                        continue;
+                    };
+                    let options = calendar::FeedOptions {
+                        project: param("project"),
+                        components,
+                        include_completed: matches!(param("completed").as_deref(), Some("true" | "1")),
+                    };
+
+                    match calendar::feed(&load_json(), &options) {
+                        Ok(body) => respond_text(request, "text/calendar; charset=utf-8", body),
+                        Err(e) => respond_json(request, 404, &json!({"error": e})),
                     }
+                }
 
-                    save_json(&data);
-                    let _ = app_handle.emit("data-changed", ());
-                    respond_json(request, 201, &entry);
+                // GET /api/settings
+                ("GET", "/api/settings") => {
+                    respond_json(request, 200, &json!(settings::load()));
+                }
+
+                // PATCH /api/settings
+                ("PATCH", "/api/settings") => {
+                    let body = read_body(&mut request);
+                    match settings::update(&body) {
+                        Ok(settings) => {
+                            emit(&app_handle, "settings-changed", settings.clone());
+                            respond_json(request, 200, &json!(settings));
+                        }
+                        Err(errors) => {
+                            respond_json(request, 400, &json!({"error": errors.join("; "), "errors": errors}));
+                        }
+                    }
+                }
+
+                // GET /api/snapshots
+                ("GET", "/api/snapshots") => {
+                    respond_json(request, 200, &json!(snapshots::list()));
+                }
+
+                // POST /api/snapshots
+                ("POST", "/api/snapshots") => match snapshots::take("manual") {
+                    Ok(Some(snapshot)) => respond_json(request, 201, &json!(snapshot)),
+                    Ok(None) => respond_json(request, 200, &json!(snapshots::list().first())),
+                    Err(e) => respond_json(request, 500, &json!({"error": e})),
+                },
+
+                // POST /api/snapshots/:id/restore
+                _ if method == "POST" && path.starts_with("/api/snapshots/") && path.ends_with("/restore") => {
+                    let id = path.trim_start_matches("/api/snapshots/").trim_end_matches("/restore");
+                    match snapshots::restore(id) {
+                        Ok(_) => {
+                            emit(&app_handle, "data-changed", ());
+                            respond_json(request, 200, &json!({"restored": id}));
+                        }
+                        Err(e) if e.starts_with("snapshot not found") => respond_json(request, 404, &json!({"error": e})),
+                        Err(e) => respond_json(request, 500, &json!({"error": e})),
+                    }
+                }
+
+                // GET /api/snapshots/:id
+                _ if method == "GET" && path.starts_with("/api/snapshots/") => {
+                    let id = path.trim_start_matches("/api/snapshots/");
+                    match snapshots::diff(id) {
+                        Ok(diff) => respond_json(request, 200, &json!(diff)),
+                        Err(e) if e.starts_with("snapshot not found") => respond_json(request, 404, &json!({"error": e})),
+                        Err(e) => respond_json(request, 500, &json!({"error": e})),
+                    }
+                }
+
+                // GET /api/history
+                ("GET", "/api/history") => {
+                    let limit = query_param(query_string, "limit").and_then(|l| l.parse().ok()).unwrap_or(100);
+                    match history::log(limit) {
+                        Ok(entries) => respond_json(request, 200, &json!(entries)),
+                        Err(e) => respond_json(request, 500, &json!({"error": e})),
+                    }
+                }
+
+                // POST /api/history/:id/restore
+                _ if method == "POST" && path.starts_with("/api/history/") && path.ends_with("/restore") => {
+                    let id = path.trim_start_matches("/api/history/").trim_end_matches("/restore");
+                    match history::restore(id) {
+                        Ok(_) => {
+                            emit(&app_handle, "data-changed", ());
+                            respond_json(request, 200, &json!({"restored": id}));
+                        }
+                        Err(e) if e.starts_with("version not found") => respond_json(request, 404, &json!({"error": e})),
+                        Err(e) => respond_json(request, 500, &json!({"error": e})),
+                    }
+                }
+
+                // POST /api/sync
+                ("POST", "/api/sync") => match sync::sync_once() {
+                    Ok(report) => {
+                        if report.updated {
+                            emit(&app_handle, "data-changed", ());
+                        }
+                        respond_json(request, 200, &json!(report));
+                    }
+                    Err(e) => respond_json(request, 400, &json!({"error": e})),
+                },
+
+                // POST /api/focus
+                ("POST", "/api/focus") => {
+                    let body = read_body(&mut request);
+                    let minutes = body.get("minutes").and_then(|m| m.as_u64()).unwrap_or(25);
+
+                    if minutes == 0 || minutes > 24 * 60 {
+                        respond_json(request, 400, &json!({"error": "minutes must be between 1 and 1440"}));
+                        continue;
+                    }
+
+                    // The timer lives in the webview store, when there is one
+                    if app_handle.is_none() {
+                        state.start_focus(minutes);
+                    }
+                    emit(&app_handle, "focus-start", minutes);
+                    respond_json(request, 202, &json!({"minutes": minutes}));
                 }
 
                 // 404
@@ -361,5 +653,5 @@ pub fn start_api_server(app_handle: AppHandle) {
                 }
             }
         }
-    });
+    }))
 }
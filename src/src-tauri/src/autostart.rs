@@ -0,0 +1,29 @@
+//! Launch at login. Registration is OS state (an XDG autostart `.desktop`
+//! entry on Linux, a LaunchAgent on macOS, a `Run` registry value on Windows)
+//! managed by the autostart plugin; the entry starts Visor with `--hidden`.
+
+use tauri::AppHandle;
+use tauri_plugin_autostart::{MacosLauncher, ManagerExt};
+
+/// Startup flag: launch into the background without showing the visor.
+pub const HIDDEN_FLAG: &str = "--hidden";
+
+pub fn plugin() -> tauri::plugin::TauriPlugin<tauri::Wry> {
+    tauri_plugin_autostart::init(MacosLauncher::LaunchAgent, Some(vec![HIDDEN_FLAG]))
+}
+
+#[tauri::command]
+pub fn get_autostart(app: AppHandle) -> Result<bool, String> {
+    app.autolaunch().is_enabled().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_autostart(app: AppHandle, enabled: bool) -> Result<(), String> {
+    let autolaunch = app.autolaunch();
+    if enabled {
+        autolaunch.enable()
+    } else {
+        autolaunch.disable()
+    }
+    .map_err(|e| e.to_string())
+}
@@ -0,0 +1,485 @@
+//! `visor-cli`: terminal client for Visor. Talks to the running app's HTTP API
+//! and, when the app isn't running, works on `~/.visor/data.json` directly
+//! using the same task and journal code as the API.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::process::ExitCode;
+use std::time::Duration;
+
+use chrono::{Local, TimeZone};
+use serde_json::{json, Value};
+use visor_lib::model::{LogEntry, Project, Task};
+use visor_lib::{encryption, journal, report, settings, storage, tasks};
+
+const USAGE: &str = "\
+usage: visor-cli [--json] <command>
+
+commands:
+  add <content...> [-p <project>]       add a task (smart syntax: !fri @tom !every week)
+  ls [-p <project>] [-s <status>] [-a]  list pending tasks (-s todo|doing|done|..., -a for all)
+  done <id>                             complete a task (id prefix is enough)
+  archive <id>                          archive a task
+  log [<content...>] [-p <project>]     list journal entries (newest first), or add one
+  report [today|yesterday|week] [-p <project>]
+                                        standup summary as Markdown (default today)
+  projects                              list projects
+  status                                task counts
+  focus start [minutes]                 start a focus timer (default 25, needs the app)
+
+--json prints raw JSON instead of tables.";
+
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let json_output = take_flag(&mut args, &["--json"]);
+
+    let Some(command) = (!args.is_empty()).then(|| args.remove(0)) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::FAILURE;
+    };
+    if matches!(command.as_str(), "help" | "-h" | "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let result = Backend::connect().and_then(|backend| run(&backend, &command, args, json_output));
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(backend: &Backend, command: &str, mut args: Vec<String>, json_output: bool) -> Result<(), String> {
+    match command {
+        "add" => {
+            let project = take_option(&mut args, &["-p", "--project"]);
+            let content = args.join(" ");
+            let task = backend.add_task(&content, project.as_deref().unwrap_or("inbox"))?;
+            if json_output {
+                print_json(&task);
+            } else {
+                let task: Task = parse(task)?;
+                println!("added {}  {}", short_id(&task.id), task.content);
+            }
+        }
+        "ls" => {
+            let project = take_option(&mut args, &["-p", "--project"]);
+            let status = take_option(&mut args, &["-s", "--status"]);
+            let all = take_flag(&mut args, &["-a", "--all"]);
+            let status = match (status, all) {
+                (Some(s), _) => Some(s.to_lowercase()),
+                (None, true) => None,
+                (None, false) => Some("pending".to_string()),
+            };
+
+            let list = backend.list_tasks(project.as_deref(), status.as_deref())?;
+            if json_output {
+                print_json(&json!(list));
+            } else {
+                let slugs = project_slugs(&backend.projects()?);
+                let mut tasks: Vec<Task> = list.into_iter().filter_map(|t| serde_json::from_value(t).ok()).collect();
+                tasks.sort_by_key(|t| (slugs.get(&t.project_id).cloned().unwrap_or_default(), t.created_at));
+                print_table(
+                    &["ID", "STATUS", "DUE", "PROJECT", "CONTENT"],
+                    tasks
+                        .iter()
+                        .map(|t| {
+                            vec![
+                                short_id(&t.id).to_string(),
+                                t.status.as_str().to_string(),
+                                t.due_at.map(format_date).unwrap_or_default(),
+                                slugs.get(&t.project_id).cloned().unwrap_or_default(),
+                                t.content.clone(),
+                            ]
+                        })
+                        .collect(),
+                );
+            }
+        }
+        "done" | "archive" => {
+            let prefix = args.first().ok_or(format!("usage: visor-cli {} <id>", command))?;
+            let id = backend.resolve_task_id(prefix)?;
+            let task = if command == "done" {
+                backend.complete_task(&id)?
+            } else {
+                backend.archive_task(&id)?
+            };
+            if json_output {
+                print_json(&task);
+            } else {
+                let task: Task = parse(task)?;
+                let verb = if command == "done" { "completed" } else { "archived" };
+                println!("{} {}  {}", verb, short_id(&task.id), task.content);
+            }
+        }
+        "log" => {
+            let project = take_option(&mut args, &["-p", "--project"]);
+            if args.is_empty() {
+                let entries = backend.log_entries(project.as_deref())?;
+                if json_output {
+                    print_json(&entries);
+                } else {
+                    let slugs = project_slugs(&backend.projects()?);
+                    let entries: Vec<LogEntry> = serde_json::from_value(entries).unwrap_or_default();
+                    print_table(
+                        &["DATE", "PROJECT", "CONTENT"],
+                        entries
+                            .iter()
+                            .map(|e| {
+                                vec![
+                                    format_datetime(e.created_at),
+                                    slugs.get(&e.project_id).cloned().unwrap_or_default(),
+                                    e.content.clone(),
+                                ]
+                            })
+                            .collect(),
+                    );
+                }
+            } else {
+                let entry = backend.add_log_entry(&args.join(" "), project.as_deref())?;
+                if json_output {
+                    print_json(&entry);
+                } else {
+                    println!("logged");
+                }
+            }
+        }
+        "report" => {
+            let project = take_option(&mut args, &["-p", "--project"]);
+            let range = args.first().map(String::as_str).unwrap_or("today");
+            let report = backend.report(range, project.as_deref())?;
+            if json_output {
+                print_json(&report);
+            } else {
+                print!("{}", report.get("markdown").and_then(|m| m.as_str()).unwrap_or_default());
+            }
+        }
+        "projects" => {
+            let projects = backend.projects()?;
+            if json_output {
+                print_json(&json!(projects));
+            } else {
+                let mut projects: Vec<Project> =
+                    projects.into_iter().filter_map(|p| serde_json::from_value(p).ok()).collect();
+                projects.sort_by_key(|p| (!p.is_inbox, p.created_at));
+                print_table(
+                    &["SLUG", "NAME", "TASKS"],
+                    projects
+                        .iter()
+                        .map(|p| vec![p.slug.clone(), p.name.clone(), p.task_order.len().to_string()])
+                        .collect(),
+                );
+            }
+        }
+        "status" => {
+            let status = backend.status()?;
+            if json_output {
+                print_json(&status);
+            } else {
+                let count = |key: &str| status.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+                println!(
+                    "{} pending / {} tasks in {} projects",
+                    count("pending"),
+                    count("tasks"),
+                    count("projects")
+                );
+            }
+        }
+        "focus" => {
+            if args.first().map(String::as_str) != Some("start") {
+                return Err("usage: visor-cli focus start [minutes]".to_string());
+            }
+            let minutes = match args.get(1) {
+                Some(m) => m.parse::<u64>().map_err(|_| format!("invalid minutes: {}", m))?,
+                None => 25,
+            };
+            let result = backend.start_focus(minutes)?;
+            if json_output {
+                print_json(&result);
+            } else {
+                println!("focus started: {} min", minutes);
+            }
+        }
+        other => return Err(format!("unknown command: {}\n\n{}", other, USAGE)),
+    }
+    Ok(())
+}
+
+/// Where commands are executed: the running app's API, or the data file
+/// directly (holding the data lock so the app can't start mid-write).
+enum Backend {
+    Api,
+    File(#[allow(dead_code)] storage::DataLock),
+}
+
+impl Backend {
+    fn connect() -> Result<Self, String> {
+        let addr: SocketAddr = settings::load().api_addr().parse().map_err(|e| format!("{}", e))?;
+        if TcpStream::connect_timeout(&addr, Duration::from_millis(300)).is_ok() {
+            return Ok(Backend::Api);
+        }
+        let lock = storage::acquire_lock()
+            .map_err(|_| "Visor is running but its API is not reachable".to_string())?;
+        if !encryption::unlock_unattended() {
+            return Err("data is encrypted: start Visor, or set VISOR_PASSPHRASE".to_string());
+        }
+        Ok(Backend::File(lock))
+    }
+
+    /// Apply a mutation to the data file and save it.
+    fn mutate<T>(f: impl FnOnce(&mut Value) -> Result<T, String>) -> Result<T, String> {
+        storage::update_json(f)
+    }
+
+    fn status(&self) -> Result<Value, String> {
+        match self {
+            Backend::Api => http("GET", "/api/status", None),
+            Backend::File(_) => Ok(tasks::status_summary(&storage::load_json())),
+        }
+    }
+
+    fn projects(&self) -> Result<Vec<Value>, String> {
+        match self {
+            Backend::Api => as_list(http("GET", "/api/projects", None)?),
+            Backend::File(_) => Ok(storage::load_json()
+                .get("projects")
+                .and_then(|p| p.as_object())
+                .map(|p| p.values().cloned().collect())
+                .unwrap_or_default()),
+        }
+    }
+
+    fn list_tasks(&self, project: Option<&str>, status: Option<&str>) -> Result<Vec<Value>, String> {
+        match self {
+            Backend::Api => {
+                let mut query = Vec::new();
+                if let Some(p) = project {
+                    query.push(format!("project={}", encode(p)));
+                }
+                if let Some(s) = status {
+                    query.push(format!("status={}", encode(s)));
+                }
+                as_list(http("GET", &format!("/api/tasks?{}", query.join("&")), None)?)
+            }
+            Backend::File(_) => Ok(tasks::list_tasks(&storage::load_json(), project, status)),
+        }
+    }
+
+    fn add_task(&self, content: &str, project: &str) -> Result<Value, String> {
+        match self {
+            Backend::Api => http("POST", "/api/tasks", Some(&json!({"content": content, "project": project}))),
+            Backend::File(_) => Self::mutate(|data| tasks::create_task(data, content, project)),
+        }
+    }
+
+    fn complete_task(&self, id: &str) -> Result<Value, String> {
+        match self {
+            Backend::Api => http("PUT", &format!("/api/tasks/{}/complete", id), None),
+            Backend::File(_) => Self::mutate(|data| tasks::complete_task(data, id).ok_or("task not found".to_string())),
+        }
+    }
+
+    fn archive_task(&self, id: &str) -> Result<Value, String> {
+        match self {
+            Backend::Api => http("PUT", &format!("/api/tasks/{}/archive", id), None),
+            Backend::File(_) => Self::mutate(|data| tasks::archive_task(data, id).ok_or("task not found".to_string())),
+        }
+    }
+
+    fn log_entries(&self, project: Option<&str>) -> Result<Value, String> {
+        match self {
+            Backend::Api => match project {
+                Some(p) => http("GET", &format!("/api/log?project={}", encode(p)), None),
+                None => http("GET", "/api/log", None),
+            },
+            Backend::File(_) => {
+                let query = journal::LogQuery { project: project.map(String::from), ..Default::default() };
+                journal::list_entries(&storage::load_json(), &query)
+            }
+        }
+    }
+
+    fn add_log_entry(&self, content: &str, project: Option<&str>) -> Result<Value, String> {
+        match self {
+            Backend::Api => http("POST", "/api/log", Some(&json!({"content": content, "project": project}))),
+            Backend::File(_) => Self::mutate(|data| journal::add_entry(data, content, project, None)),
+        }
+    }
+
+    fn report(&self, range: &str, project: Option<&str>) -> Result<Value, String> {
+        match self {
+            Backend::Api => {
+                let mut path = format!("/api/report?range={}", encode(range));
+                if let Some(p) = project {
+                    path.push_str(&format!("&project={}", encode(p)));
+                }
+                http("GET", &path, None)
+            }
+            Backend::File(_) => {
+                let range = report::ReportRange::parse(range)
+                    .ok_or("range must be today, yesterday or week".to_string())?;
+                report::report(&storage::load_json(), range, project, Local::now())
+            }
+        }
+    }
+
+    fn start_focus(&self, minutes: u64) -> Result<Value, String> {
+        match self {
+            Backend::Api => http("POST", "/api/focus", Some(&json!({"minutes": minutes}))),
+            Backend::File(_) => Err("the focus timer needs the Visor app running".to_string()),
+        }
+    }
+
+    /// Expand an id prefix (as printed by `ls`) to a full task id.
+    fn resolve_task_id(&self, prefix: &str) -> Result<String, String> {
+        let matches: Vec<String> = self
+            .list_tasks(None, None)?
+            .iter()
+            .filter_map(|t| t.get("id").and_then(|i| i.as_str()))
+            .filter(|id| id.starts_with(prefix))
+            .map(String::from)
+            .collect();
+        match matches.as_slice() {
+            [id] => Ok(id.clone()),
+            [] => Err(format!("no task matches {}", prefix)),
+            _ => Err(format!("{} matches {} tasks, use a longer prefix", prefix, matches.len())),
+        }
+    }
+}
+
+/// Minimal HTTP/1.0 request to the local API; returns the JSON body, or the
+/// API's `error` message for non-2xx responses.
+fn http(method: &str, path: &str, body: Option<&Value>) -> Result<Value, String> {
+    let addr = settings::load().api_addr();
+    let mut stream = TcpStream::connect(&addr).map_err(|e| format!("cannot reach Visor: {}", e))?;
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(10)));
+
+    let body = body.map(|b| b.to_string()).unwrap_or_default();
+    let request = format!(
+        "{} {} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        method,
+        path,
+        addr,
+        body.len(),
+        body
+    );
+    stream.write_all(request.as_bytes()).map_err(|e| e.to_string())?;
+
+    let mut raw = String::new();
+    stream.read_to_string(&mut raw).map_err(|e| e.to_string())?;
+    let (head, body) = raw.split_once("\r\n\r\n").ok_or("malformed response from Visor")?;
+    let status: u16 = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or("malformed response from Visor")?;
+    let value: Value = serde_json::from_str(body).unwrap_or(Value::Null);
+
+    if (200..300).contains(&status) {
+        Ok(value)
+    } else {
+        Err(value
+            .get("error")
+            .and_then(|e| e.as_str())
+            .map(String::from)
+            .unwrap_or_else(|| format!("request failed with status {}", status)))
+    }
+}
+
+fn as_list(value: Value) -> Result<Vec<Value>, String> {
+    match value {
+        Value::Array(list) => Ok(list),
+        _ => Err("unexpected response from Visor".to_string()),
+    }
+}
+
+fn parse<T: serde::de::DeserializeOwned>(value: Value) -> Result<T, String> {
+    serde_json::from_value(value).map_err(|e| e.to_string())
+}
+
+/// Percent-encode a query value.
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Remove a boolean flag from `args`, returning whether it was present.
+fn take_flag(args: &mut Vec<String>, names: &[&str]) -> bool {
+    let before = args.len();
+    args.retain(|a| !names.contains(&a.as_str()));
+    args.len() != before
+}
+
+/// Remove `<name> <value>` from `args`, returning the value.
+fn take_option(args: &mut Vec<String>, names: &[&str]) -> Option<String> {
+    let idx = args.iter().position(|a| names.contains(&a.as_str()))?;
+    args.remove(idx);
+    (idx < args.len()).then(|| args.remove(idx))
+}
+
+fn project_slugs(projects: &[Value]) -> HashMap<String, String> {
+    projects
+        .iter()
+        .filter_map(|p| serde_json::from_value::<Project>(p.clone()).ok())
+        .map(|p| (p.id, p.slug))
+        .collect()
+}
+
+/// The first 8 characters of `id`; imported ids needn't be ASCII.
+fn short_id(id: &str) -> &str {
+    id.char_indices().nth(8).map_or(id, |(i, _)| &id[..i])
+}
+
+fn format_date(ms: i64) -> String {
+    Local
+        .timestamp_millis_opt(ms)
+        .single()
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+fn format_datetime(ms: i64) -> String {
+    Local
+        .timestamp_millis_opt(ms)
+        .single()
+        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+fn print_json(value: &Value) {
+    println!("{}", serde_json::to_string_pretty(value).unwrap_or_default());
+}
+
+fn print_table(headers: &[&str], rows: Vec<Vec<String>>) {
+    if rows.is_empty() {
+        println!("(none)");
+        return;
+    }
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<&str>| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(c, w)| format!("{:<width$}", c, width = w))
+            .collect();
+        println!("{}", padded.join("  ").trim_end());
+    };
+    line(headers.to_vec());
+    for row in &rows {
+        line(row.iter().map(String::as_str).collect());
+    }
+}
@@ -0,0 +1,255 @@
+//! iCalendar (RFC 5545) feed of dated tasks, served as `GET /api/calendar.ics`.
+//!
+//! Each task becomes a VTODO and/or all-day VEVENTs (one for the deadline, one
+//! for the scheduled day). UIDs derive from the task id so subscribed clients
+//! update entries in place.
+
+use chrono::{Datelike, Days, Local, NaiveDate, TimeZone, Utc};
+use serde_json::Value;
+
+use crate::model::{Project, RecurrenceType, Task, TaskStatus};
+
+const WEEKDAYS: [&str; 7] = ["SU", "MO", "TU", "WE", "TH", "FR", "SA"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Components {
+    Todos,
+    Events,
+    Both,
+}
+
+impl Components {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "todo" => Some(Components::Todos),
+            "event" => Some(Components::Events),
+            "all" => Some(Components::Both),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FeedOptions {
+    /// Project slug; all projects when absent.
+    pub project: Option<String>,
+    pub components: Components,
+    pub include_completed: bool,
+}
+
+pub fn feed(data: &Value, options: &FeedOptions) -> Result<String, String> {
+    let projects: Vec<Project> = data
+        .get("projects")
+        .and_then(|p| p.as_object())
+        .map(|p| p.values().filter_map(|v| serde_json::from_value(v.clone()).ok()).collect())
+        .unwrap_or_default();
+    let project_id = match &options.project {
+        Some(slug) => Some(
+            projects
+                .iter()
+                .find(|p| &p.slug == slug)
+                .map(|p| p.id.clone())
+                .ok_or(format!("project not found: {}", slug))?,
+        ),
+        None => None,
+    };
+
+    let mut tasks: Vec<Task> = data
+        .get("tasks")
+        .and_then(|t| t.as_object())
+        .map(|t| t.values().filter_map(|v| serde_json::from_value(v.clone()).ok()).collect())
+        .unwrap_or_default();
+    tasks.retain(|t| {
+        !t.archived
+            && (t.due_at.is_some() || t.scheduled.is_some())
+            && (options.include_completed || !matches!(t.status, TaskStatus::Done | TaskStatus::Cancelled))
+            && project_id.as_ref().is_none_or(|id| &t.project_id == id)
+    });
+    tasks.sort_by_key(|t| (t.due_at.or(t.scheduled), t.created_at));
+
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Visor//Visor//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        "X-WR-CALNAME:Visor".to_string(),
+    ];
+
+    for task in &tasks {
+        let project = projects.iter().find(|p| p.id == task.project_id);
+        let due = local_date(task.due_at);
+        let scheduled = local_date(task.scheduled);
+
+        if options.components != Components::Events {
+            lines.push("BEGIN:VTODO".to_string());
+            lines.push(format!("UID:{}@visor", task.id));
+            lines.push(format!("DTSTAMP:{}", stamp));
+            lines.push(format!("SUMMARY:{}", escape(&task.content)));
+            push_details(&mut lines, task, project);
+            // DUE must come after DTSTART, and RRULE repeats from DTSTART: a
+            // recurring task without an earlier scheduled day starts when due
+            let start = scheduled
+                .filter(|s| due.is_none_or(|d| *s < d))
+                .or(due.filter(|_| task.recurrence.is_some()));
+            if let Some(start) = start {
+                lines.push(format!("DTSTART;VALUE=DATE:{}", ical_date(start)));
+            }
+            if let Some(due) = due {
+                lines.push(format!("DUE;VALUE=DATE:{}", ical_date(due)));
+            }
+            lines.push(format!("STATUS:{}", todo_status(task.status)));
+            if let Some(completed) = task.completed_at.and_then(|ms| Utc.timestamp_millis_opt(ms).single()) {
+                lines.push(format!("COMPLETED:{}", completed.format("%Y%m%dT%H%M%SZ")));
+            }
+            if let Some(start) = start {
+                push_rrule(&mut lines, task, start);
+            }
+            lines.push("END:VTODO".to_string());
+        }
+
+        if options.components != Components::Todos {
+            for (suffix, date, prefix) in [("due", due, ""), ("scheduled", scheduled, "Scheduled: ")] {
+                let Some(date) = date else { continue };
+                lines.push("BEGIN:VEVENT".to_string());
+                lines.push(format!("UID:{}-{}@visor", task.id, suffix));
+                lines.push(format!("DTSTAMP:{}", stamp));
+                lines.push(format!("DTSTART;VALUE=DATE:{}", ical_date(date)));
+                lines.push(format!("DTEND;VALUE=DATE:{}", ical_date(date + Days::new(1))));
+                lines.push(format!("SUMMARY:{}{}", escape(prefix), escape(&task.content)));
+                push_details(&mut lines, task, project);
+                lines.push("TRANSP:TRANSPARENT".to_string());
+                // Recurrence moves the deadline; the scheduled day is a one-off
+                if suffix == "due" {
+                    push_rrule(&mut lines, task, date);
+                }
+                lines.push("END:VEVENT".to_string());
+            }
+        }
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+    Ok(lines.iter().map(|l| fold(l)).collect::<Vec<_>>().join("\r\n") + "\r\n")
+}
+
+fn push_details(lines: &mut Vec<String>, task: &Task, project: Option<&Project>) {
+    if let Some(notes) = task.notes.as_deref().filter(|n| !n.trim().is_empty()) {
+        lines.push(format!("DESCRIPTION:{}", escape(notes)));
+    }
+    if let Some(project) = project {
+        lines.push(format!("CATEGORIES:{}", escape(&project.name)));
+    }
+}
+
+/// The task's recurrence as an RRULE repeating from `start`. A start off the
+/// rule's days (a weekly task moved to another day) would make an undefined
+/// recurrence set, so it stays a one-off until Visor moves it back on the rule.
+fn push_rrule(lines: &mut Vec<String>, task: &Task, start: NaiveDate) {
+    let Some(recurrence) = task.recurrence else { return };
+    let weekday = start.weekday().num_days_from_sunday();
+    let rule = match (recurrence.kind, recurrence.day_of_week) {
+        (RecurrenceType::Daily, _) => "FREQ=DAILY".to_string(),
+        (RecurrenceType::Weekdays, _) if (1..=5).contains(&weekday) => "FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR".to_string(),
+        (RecurrenceType::Weekdays, _) => return,
+        (RecurrenceType::Weekly, Some(day)) if day == weekday => format!("FREQ=WEEKLY;BYDAY={}", WEEKDAYS[day as usize]),
+        (RecurrenceType::Weekly, Some(day)) if (day as usize) < WEEKDAYS.len() => return,
+        (RecurrenceType::Weekly, _) => "FREQ=WEEKLY".to_string(),
+        (RecurrenceType::Monthly, _) => "FREQ=MONTHLY".to_string(),
+    };
+    lines.push(format!("RRULE:{}", rule));
+}
+
+fn todo_status(status: TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Todo | TaskStatus::Waiting => "NEEDS-ACTION",
+        TaskStatus::Doing => "IN-PROCESS",
+        TaskStatus::Done => "COMPLETED",
+        TaskStatus::Cancelled => "CANCELLED",
+    }
+}
+
+fn local_date(ms: Option<i64>) -> Option<NaiveDate> {
+    Local.timestamp_millis_opt(ms?).single().map(|d| d.date_naive())
+}
+
+fn ical_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+/// Escape a TEXT value (RFC 5545 §3.3.11).
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Fold a content line at 75 octets without splitting a UTF-8 character.
+fn fold(line: &str) -> String {
+    let mut out = String::with_capacity(line.len() + line.len() / 70 * 3);
+    let mut width = 0;
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if width + len > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += len;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn todo(recurrence: Value, scheduled: Option<NaiveDate>) -> String {
+        let day = |d: NaiveDate| crate::parser::end_of_day_millis(d);
+        // Friday
+        let due = NaiveDate::from_ymd_opt(2025, 1, 24).unwrap();
+        let data = json!({
+            "projects": {},
+            "tasks": {"t": {"id": "t", "content": "Review", "projectId": "p", "createdAt": 1,
+                            "dueAt": day(due), "scheduled": scheduled.and_then(day), "recurrence": recurrence}}
+        });
+        let options = FeedOptions { project: None, components: Components::Todos, include_completed: false };
+        feed(&data, &options).unwrap()
+    }
+
+    #[test]
+    fn due_only_recurring_todos_start_when_due() {
+        let ics = todo(json!({"type": "weekly", "dayOfWeek": 5}), None);
+        assert!(ics.contains("DTSTART;VALUE=DATE:20250124\r\nDUE;VALUE=DATE:20250124\r\n"), "{}", ics);
+        assert!(ics.contains("RRULE:FREQ=WEEKLY;BYDAY=FR\r\n"), "{}", ics);
+
+        let ics = todo(json!({"type": "monthly"}), NaiveDate::from_ymd_opt(2025, 1, 20));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20250120\r\n"), "{}", ics);
+        assert!(ics.contains("RRULE:FREQ=MONTHLY\r\n"), "{}", ics);
+
+        let ics = todo(Value::Null, None);
+        assert!(!ics.contains("DTSTART") && !ics.contains("RRULE"), "{}", ics);
+    }
+
+    #[test]
+    fn byday_must_match_the_start() {
+        let ics = todo(json!({"type": "weekly", "dayOfWeek": 1}), None);
+        assert!(!ics.contains("RRULE"), "{}", ics);
+        // Scheduled on a Saturday
+        let ics = todo(json!({"type": "weekdays"}), NaiveDate::from_ymd_opt(2025, 1, 18));
+        assert!(!ics.contains("RRULE"), "{}", ics);
+        let ics = todo(json!({"type": "weekdays"}), None);
+        assert!(ics.contains("RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR\r\n"), "{}", ics);
+    }
+}
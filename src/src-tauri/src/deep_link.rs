@@ -0,0 +1,300 @@
+//! `visor://` links from browsers and other apps:
+//!
+//!   visor://add?content=Write%20report%20!fri&project=work
+//!   visor://project/work
+//!   visor://task/<id>
+//!   visor://focus?minutes=25
+//!
+//! Links come from untrusted pages, so anything that doesn't match exactly is
+//! rejected. Actions go through the same code as the HTTP API.
+
+use serde_json::json;
+use tauri::{AppHandle, Emitter, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+use crate::{storage, tasks};
+
+pub const SCHEME: &str = "visor";
+
+const MAX_CONTENT_CHARS: usize = 1000;
+const MAX_ID_CHARS: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeepLink {
+    Add { content: String, project: Option<String> },
+    Project(String),
+    Task(String),
+    Focus(u64),
+}
+
+pub fn is_deep_link(arg: &str) -> bool {
+    arg.starts_with(&format!("{}://", SCHEME))
+}
+
+impl DeepLink {
+    pub fn parse(url: &Url) -> Result<Self, String> {
+        if url.scheme() != SCHEME {
+            return Err(format!("unsupported scheme: {}", url.scheme()));
+        }
+        if !url.username().is_empty() || url.password().is_some() || url.port().is_some() {
+            return Err("unexpected credentials or port".to_string());
+        }
+
+        let params = query_params(url)?;
+        let segments: Vec<&str> = url
+            .path_segments()
+            .map(|s| s.filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+
+        match (url.host_str().unwrap_or(""), segments.as_slice()) {
+            ("add", []) => {
+                let allowed = ["content", "project"];
+                check_keys(&params, &allowed)?;
+                let content = param(&params, "content").ok_or("content required")?;
+                let content = content.trim();
+                if content.is_empty() || content.chars().count() > MAX_CONTENT_CHARS {
+                    return Err(format!("content must be 1-{} characters", MAX_CONTENT_CHARS));
+                }
+                if content.chars().any(char::is_control) {
+                    return Err("content contains control characters".to_string());
+                }
+                let project = param(&params, "project").map(validate_id).transpose()?;
+                Ok(DeepLink::Add { content: content.to_string(), project })
+            }
+            ("project", [slug]) => {
+                check_keys(&params, &[])?;
+                Ok(DeepLink::Project(validate_id(slug)?))
+            }
+            ("task", [id]) => {
+                check_keys(&params, &[])?;
+                Ok(DeepLink::Task(validate_id(id)?))
+            }
+            ("focus", []) => {
+                check_keys(&params, &["minutes"])?;
+                let minutes = match param(&params, "minutes") {
+                    Some(m) => m.parse::<u64>().map_err(|_| "minutes must be a number")?,
+                    None => 25,
+                };
+                if minutes == 0 || minutes > 24 * 60 {
+                    return Err("minutes must be between 1 and 1440".to_string());
+                }
+                Ok(DeepLink::Focus(minutes))
+            }
+            _ => Err(format!("unknown link: {}", url)),
+        }
+    }
+}
+
+/// The query's pairs, decoded strictly: `%` must start a valid escape and
+/// the result must be UTF-8, where `Url::query_pairs` would pass both through.
+fn query_params(url: &Url) -> Result<Vec<(String, String)>, String> {
+    let mut params: Vec<(String, String)> = Vec::new();
+    for pair in url.query().unwrap_or("").split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let (key, value) = (decode(key)?, decode(value)?);
+        if params.iter().any(|(k, _)| *k == key) {
+            return Err(format!("duplicate parameter: {}", key));
+        }
+        params.push((key, value));
+    }
+    Ok(params)
+}
+
+fn decode(value: &str) -> Result<String, String> {
+    let invalid = || format!("invalid percent-encoding: {}", value);
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok()).ok_or_else(invalid)?;
+                out.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+                i += 3;
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).map_err(|_| invalid())
+}
+
+fn check_keys(params: &[(String, String)], allowed: &[&str]) -> Result<(), String> {
+    match params.iter().find(|(k, _)| !allowed.contains(&k.as_str())) {
+        Some((key, _)) => Err(format!("unexpected parameter: {}", key)),
+        None => Ok(()),
+    }
+}
+
+fn param<'a>(params: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    params.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+}
+
+/// Project slugs and task ids: ASCII letters, digits, `-` and `_`.
+fn validate_id(value: &str) -> Result<String, String> {
+    let valid = !value.is_empty()
+        && value.len() <= MAX_ID_CHARS
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(value.to_string())
+    } else {
+        Err(format!("invalid id: {}", value))
+    }
+}
+
+pub fn handle(app: &AppHandle, url: &Url) {
+    let link = match DeepLink::parse(url) {
+        Ok(link) => link,
+        Err(e) => {
+            eprintln!("Ignoring deep link: {}", e);
+            return;
+        }
+    };
+
+    match link {
+        DeepLink::Add { content, project } => {
+            if let Err(e) = storage::ensure_unlocked() {
+                eprintln!("Failed to add task: {}", e);
+                return;
+            }
+            let slug = project.as_deref().unwrap_or("inbox");
+            match storage::update_json(|data| tasks::create_task(data, &content, slug)) {
+                Ok(_) => {
+                    let _ = app.emit("data-changed", ());
+                }
+                Err(e) => eprintln!("Failed to add task: {}", e),
+            }
+        }
+        DeepLink::Project(slug) => {
+            crate::show_visor(app);
+            let _ = app.emit("navigate", json!({ "type": "project", "slug": slug }));
+        }
+        DeepLink::Task(id) => {
+            crate::show_visor(app);
+            let _ = app.emit("navigate", json!({ "type": "task", "id": id }));
+        }
+        DeepLink::Focus(minutes) => {
+            let _ = app.emit("focus-start", minutes);
+        }
+    }
+}
+
+/// Handle links opened while running (and, via the single-instance plugin,
+/// links passed to a second launch) plus the one that started the app.
+pub fn register(app: &AppHandle) {
+    // Bundled installs register the scheme at install time; dev builds and
+    // AppImages have to do it at runtime.
+    #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+    if let Err(e) = app.deep_link().register_all() {
+        eprintln!("Failed to register {}:// links: {}", SCHEME, e);
+    }
+
+    let app_handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            handle(&app_handle, &url);
+        }
+    });
+
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        for url in urls {
+            handle(app, &url);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(link: &str) -> Result<DeepLink, String> {
+        DeepLink::parse(&Url::parse(link).map_err(|e| e.to_string())?)
+    }
+
+    #[test]
+    fn parses_valid_links() {
+        assert_eq!(
+            parse("visor://add?content=Write%20report+!fri&project=work").unwrap(),
+            DeepLink::Add { content: "Write report !fri".to_string(), project: Some("work".to_string()) }
+        );
+        assert_eq!(
+            parse("visor://add?content=%E2%9C%85%20caf%C3%A9").unwrap(),
+            DeepLink::Add { content: "✅ café".to_string(), project: None }
+        );
+        assert_eq!(parse("visor://project/side_project-2").unwrap(), DeepLink::Project("side_project-2".to_string()));
+        assert_eq!(parse("visor://project/work/").unwrap(), DeepLink::Project("work".to_string()));
+        assert_eq!(parse("visor://task/1a2b3c4d").unwrap(), DeepLink::Task("1a2b3c4d".to_string()));
+        assert_eq!(parse("visor://focus").unwrap(), DeepLink::Focus(25));
+        assert_eq!(parse("visor://focus?minutes=1440").unwrap(), DeepLink::Focus(1440));
+        assert!(is_deep_link("visor://focus") && !is_deep_link("--add"));
+    }
+
+    #[test]
+    fn rejects_bad_ids() {
+        for link in [
+            "visor://project/wo%20rk",
+            "visor://project/..",
+            "visor://task/a%2Fb",
+            "visor://task/ünïcode",
+            "visor://add?content=x&project=a;b",
+            "visor://add?content=x&project=",
+            "visor://project",
+            "visor://project/a/b",
+        ] {
+            assert!(parse(link).is_err(), "{}", link);
+        }
+        assert!(parse(&format!("visor://task/{}", "a".repeat(MAX_ID_CHARS))).is_ok());
+        assert!(parse(&format!("visor://task/{}", "a".repeat(MAX_ID_CHARS + 1))).is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_range_minutes() {
+        for minutes in ["0", "1441", "-5", "2.5", "", "ten", "99999999999999999999"] {
+            assert!(parse(&format!("visor://focus?minutes={}", minutes)).is_err(), "{}", minutes);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_hosts_and_extras() {
+        for link in [
+            "https://add?content=x",
+            "visor://delete?id=1",
+            "visor://ADD?content=x",
+            "visor://add/extra?content=x",
+            "visor://user:pw@add?content=x",
+            "visor://add:8080?content=x",
+            "visor://add?content=x&secret=1",
+            "visor://add?content=x&content=y",
+            "visor://task/1a2b?focus=1",
+            "visor://add",
+            "visor://add?content=%20%20",
+            "visor://add?content=a%0Ab",
+        ] {
+            assert!(parse(link).is_err(), "{}", link);
+        }
+        assert!(parse(&format!("visor://add?content={}", "x".repeat(MAX_CONTENT_CHARS))).is_ok());
+        assert!(parse(&format!("visor://add?content={}", "x".repeat(MAX_CONTENT_CHARS + 1))).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_percent_encoding() {
+        for link in [
+            "visor://add?content=100%",
+            "visor://add?content=%zz",
+            "visor://add?content=%4",
+            "visor://add?content=%FF%FE",
+            "visor://add?content=%C3",
+            "visor://add?content=x&%ZZ=1",
+        ] {
+            assert!(parse(link).is_err(), "{}", link);
+        }
+        // A valid escape in a key is decoded like any other
+        assert!(parse("visor://add?con%74ent=x").is_ok());
+    }
+}
@@ -0,0 +1,435 @@
+//! Optional encryption at rest. When enabled, `data.json` is replaced by
+//! `data.enc`: the same JSON sealed with XChaCha20-Poly1305 under a key derived
+//! from a passphrase with Argon2id. The salt and cost parameters are stored in
+//! the file, so only the passphrase (or the key, when remembered in the OS
+//! keyring) is needed to open it.
+//!
+//! Until the key is known the data is locked: reads and writes fail, the API
+//! answers 423 and the webview asks for the passphrase. While `data.enc`
+//! exists it is the source of truth; a `data.json` next to it is a leftover of
+//! an interrupted switch and is removed on unlock. Snapshots are stored the
+//! same way as the data and are converted along with it; the version history
+//! and the last synced copy (`sync-base.json`) are deleted, since they hold
+//! the data in plain text.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::{history, snapshots, storage, sync};
+
+const FORMAT_VERSION: u32 = 1;
+const MIN_PASSPHRASE_LEN: usize = 8;
+const KEYRING_SERVICE: &str = "visor";
+const KEYRING_USER: &str = "data-key";
+/// Read by headless mode and `visor-cli`, which can't prompt.
+const PASSPHRASE_ENV: &str = "VISOR_PASSPHRASE";
+
+pub const LOCKED: &str = "data is locked";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KdfParams {
+    algorithm: String,
+    salt: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+/// Layout of `data.enc`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Envelope {
+    version: u32,
+    kdf: KdfParams,
+    nonce: String,
+    ciphertext: String,
+}
+
+struct Unlocked {
+    key: [u8; 32],
+    kdf: KdfParams,
+}
+
+/// The key while unlocked. Held during every read and write of the data file,
+/// so switching modes can't interleave with a save.
+static VAULT: Mutex<Option<Unlocked>> = Mutex::new(None);
+
+/// Counts writes of the data file, so a writer can tell whether the data it
+/// read is still current.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionStatus {
+    pub enabled: bool,
+    pub unlocked: bool,
+    /// Whether the key is saved in the OS keyring.
+    pub remembered: bool,
+}
+
+fn encrypted_path() -> PathBuf {
+    storage::data_path().join("data.enc")
+}
+
+fn plain_path() -> PathBuf {
+    storage::data_path().join("data.json")
+}
+
+fn vault() -> std::sync::MutexGuard<'static, Option<Unlocked>> {
+    VAULT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn enabled() -> bool {
+    encrypted_path().exists()
+}
+
+pub fn is_locked() -> bool {
+    enabled() && vault().is_none()
+}
+
+fn new_kdf() -> KdfParams {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    KdfParams {
+        algorithm: "argon2id".to_string(),
+        salt: BASE64.encode(salt),
+        memory_kib: Params::DEFAULT_M_COST,
+        iterations: Params::DEFAULT_T_COST,
+        parallelism: Params::DEFAULT_P_COST,
+    }
+}
+
+fn derive_key(passphrase: &str, kdf: &KdfParams) -> Result<[u8; 32], String> {
+    if kdf.algorithm != "argon2id" {
+        return Err(format!("unsupported key derivation: {}", kdf.algorithm));
+    }
+    let salt = BASE64.decode(&kdf.salt).map_err(|e| e.to_string())?;
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32)).map_err(|e| e.to_string())?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+fn seal(text: &str, unlocked: &Unlocked) -> Result<Vec<u8>, String> {
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(&unlocked.key));
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, text.as_bytes())
+        .map_err(|_| "encryption failed".to_string())?;
+    let envelope = Envelope {
+        version: FORMAT_VERSION,
+        kdf: unlocked.kdf.clone(),
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    };
+    serde_json::to_vec_pretty(&envelope).map_err(|e| e.to_string())
+}
+
+fn parse_envelope(raw: &[u8]) -> Result<Envelope, String> {
+    let envelope: Envelope = serde_json::from_slice(raw).map_err(|e| format!("invalid data.enc: {}", e))?;
+    if envelope.version > FORMAT_VERSION {
+        return Err(format!("data.enc is version {}, newer than this Visor", envelope.version));
+    }
+    Ok(envelope)
+}
+
+fn read_envelope() -> Result<Envelope, String> {
+    parse_envelope(&fs::read(encrypted_path()).map_err(|e| e.to_string())?)
+}
+
+/// Decrypt `envelope`; a wrong key fails authentication.
+fn open(envelope: &Envelope, key: &[u8; 32]) -> Result<String, String> {
+    let nonce = BASE64.decode(&envelope.nonce).map_err(|e| e.to_string())?;
+    let ciphertext = BASE64.decode(&envelope.ciphertext).map_err(|e| e.to_string())?;
+    if nonce.len() != 24 {
+        return Err("invalid data.enc: bad nonce".to_string());
+    }
+    let plain = XChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(XNonce::from_slice(&nonce), ciphertext.as_ref())
+        .map_err(|_| "wrong passphrase".to_string())?;
+    String::from_utf8(plain).map_err(|e| e.to_string())
+}
+
+/// The data file's JSON text, decrypted if needed. `None` when there is none yet.
+pub fn read_raw() -> Result<Option<String>, String> {
+    read_with(&vault())
+}
+
+/// Replace the data file with `text`, encrypting it when enabled.
+pub fn write_raw(text: &str) -> Result<(), String> {
+    write_with(&vault(), text)
+}
+
+/// Read the data, transform it with `f` and write back what it returns (if
+/// anything), with no other write in between.
+pub fn update_raw<T>(f: impl FnOnce(Option<&str>) -> Result<(Option<String>, T), String>) -> Result<T, String> {
+    let guard = vault();
+    let (text, result) = f(read_with(&guard)?.as_deref())?;
+    if let Some(text) = text {
+        write_with(&guard, &text)?;
+    }
+    Ok(result)
+}
+
+/// The data file's JSON text with its generation, read together.
+pub fn read_versioned() -> Result<(Option<String>, u64), String> {
+    let guard = vault();
+    Ok((read_with(&guard)?, GENERATION.load(Ordering::SeqCst)))
+}
+
+/// Like [`update_raw`], but `f` is also given the data's generation, and the
+/// generation after the write is returned too.
+pub fn update_versioned<T>(
+    f: impl FnOnce(Option<&str>, u64) -> Result<(Option<String>, T), String>,
+) -> Result<(T, u64), String> {
+    let guard = vault();
+    let (text, result) = f(read_with(&guard)?.as_deref(), GENERATION.load(Ordering::SeqCst))?;
+    if let Some(text) = text {
+        write_with(&guard, &text)?;
+    }
+    Ok((result, GENERATION.load(Ordering::SeqCst)))
+}
+
+fn read_with(guard: &Option<Unlocked>) -> Result<Option<String>, String> {
+    if enabled() {
+        let unlocked = guard.as_ref().ok_or(LOCKED.to_string())?;
+        return open(&read_envelope()?, &unlocked.key).map(Some);
+    }
+    match fs::read_to_string(plain_path()) {
+        Ok(raw) => Ok(Some(raw)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn write_with(guard: &Option<Unlocked>, text: &str) -> Result<(), String> {
+    if enabled() {
+        let unlocked = guard.as_ref().ok_or(LOCKED.to_string())?;
+        storage::write_atomic(&encrypted_path(), &seal(text, unlocked)?)?;
+    } else {
+        storage::write_atomic(&plain_path(), text.as_bytes())?;
+    }
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    Ok(())
+}
+
+/// `text` as the data file would store it: sealed when encryption is on.
+/// Used for snapshots, so they're never more readable than the data.
+pub fn seal_raw(text: &str) -> Result<Vec<u8>, String> {
+    let guard = vault();
+    if enabled() {
+        return seal(text, guard.as_ref().ok_or(LOCKED.to_string())?);
+    }
+    Ok(text.as_bytes().to_vec())
+}
+
+/// Reverse of [`seal_raw`].
+pub fn open_raw(bytes: &[u8]) -> Result<String, String> {
+    let guard = vault();
+    if enabled() {
+        let unlocked = guard.as_ref().ok_or(LOCKED.to_string())?;
+        return open(&parse_envelope(bytes)?, &unlocked.key);
+    }
+    String::from_utf8(bytes.to_vec()).map_err(|e| e.to_string())
+}
+
+fn keyring_entry() -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(|e| e.to_string())
+}
+
+fn remembered_key() -> Option<[u8; 32]> {
+    let encoded = keyring_entry().ok()?.get_password().ok()?;
+    BASE64.decode(encoded).ok()?.try_into().ok()
+}
+
+/// Save or forget the key in the keyring. Failing to save (no keyring on
+/// this system) is reported but doesn't undo the change it belongs to.
+fn remember(key: Option<&[u8; 32]>) {
+    let result = keyring_entry().and_then(|entry| match key {
+        Some(key) => entry.set_password(&BASE64.encode(key)).map_err(|e| e.to_string()),
+        None => match entry.delete_credential() {
+            Err(keyring::Error::NoEntry) => Ok(()),
+            other => other.map_err(|e| e.to_string()),
+        },
+    });
+    if let Err(e) = result {
+        eprintln!("Keyring: {}", e);
+    }
+}
+
+fn check_passphrase(passphrase: &str) -> Result<(), String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!("passphrase must be at least {} characters", MIN_PASSPHRASE_LEN));
+    }
+    Ok(())
+}
+
+/// Take the key from `unlocked` if it opens the data, removing a plaintext
+/// leftover.
+fn accept(guard: &mut Option<Unlocked>, unlocked: Unlocked, envelope: &Envelope) -> Result<(), String> {
+    open(envelope, &unlocked.key)?;
+    if plain_path().exists() {
+        let _ = fs::remove_file(plain_path());
+    }
+    *guard = Some(unlocked);
+    Ok(())
+}
+
+pub fn unlock(passphrase: &str, keep_in_keyring: bool) -> Result<(), String> {
+    let mut guard = vault();
+    if !enabled() {
+        return Err("encryption is not enabled".to_string());
+    }
+    let envelope = read_envelope()?;
+    let key = derive_key(passphrase, &envelope.kdf)?;
+    accept(&mut guard, Unlocked { key, kdf: envelope.kdf.clone() }, &envelope)?;
+    if keep_in_keyring {
+        remember(Some(&key));
+    }
+    Ok(())
+}
+
+/// Unlock without asking: with the key remembered in the keyring, else with
+/// `VISOR_PASSPHRASE`. True if the data is usable afterwards.
+pub fn unlock_unattended() -> bool {
+    if !is_locked() {
+        return true;
+    }
+    let Ok(envelope) = read_envelope() else {
+        return false;
+    };
+    if let Some(key) = remembered_key() {
+        let unlocked = Unlocked { key, kdf: envelope.kdf.clone() };
+        if accept(&mut vault(), unlocked, &envelope).is_ok() {
+            return true;
+        }
+    }
+    match std::env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) => match unlock(&passphrase, false) {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("{}: {}", PASSPHRASE_ENV, e);
+                false
+            }
+        },
+        Err(_) => false,
+    }
+}
+
+/// Encrypt the current data under `passphrase` and remove the plaintext file.
+pub fn enable(passphrase: &str, keep_in_keyring: bool) -> Result<(), String> {
+    check_passphrase(passphrase)?;
+    let mut guard = vault();
+    if enabled() {
+        return Err("encryption is already enabled".to_string());
+    }
+    // Only a missing file means there's no data yet; anything else must not
+    // be replaced by an empty vault
+    let text = read_with(&guard)?.unwrap_or_else(|| "{}".to_string());
+    let kdf = new_kdf();
+    let unlocked = Unlocked { key: derive_key(passphrase, &kdf)?, kdf };
+    storage::write_atomic(&encrypted_path(), &seal(&text, &unlocked)?)?;
+    let _ = fs::remove_file(plain_path());
+    snapshots::convert(|bytes| seal(&String::from_utf8_lossy(bytes), &unlocked));
+    remember(keep_in_keyring.then_some(&unlocked.key));
+    *guard = Some(unlocked);
+    // Restores lock the history, and sync runs their own lock, before the
+    // vault, so neither is taken while holding it
+    drop(guard);
+    sync::forget_base().map_err(|e| format!("data encrypted, but {}", e))?;
+    history::purge().map_err(|e| format!("data encrypted, but {}", e))
+}
+
+/// Write the data back as plain `data.json`, after checking `passphrase`.
+pub fn disable(passphrase: &str) -> Result<(), String> {
+    let mut guard = vault();
+    if !enabled() {
+        return Err("encryption is not enabled".to_string());
+    }
+    let envelope = read_envelope()?;
+    let key = derive_key(passphrase, &envelope.kdf)?;
+    let text = open(&envelope, &key)?;
+    storage::write_atomic(&plain_path(), text.as_bytes())?;
+    fs::remove_file(encrypted_path()).map_err(|e| e.to_string())?;
+    snapshots::convert(|bytes| Ok(open(&parse_envelope(bytes)?, &key)?.into_bytes()));
+    remember(None);
+    *guard = None;
+    Ok(())
+}
+
+/// Re-encrypt under `new` with a fresh salt. The file is replaced in one
+/// rename, so it is readable with exactly one of the two passphrases.
+pub fn change_passphrase(current: &str, new: &str, keep_in_keyring: bool) -> Result<(), String> {
+    check_passphrase(new)?;
+    let mut guard = vault();
+    if !enabled() {
+        return Err("encryption is not enabled".to_string());
+    }
+    let envelope = read_envelope()?;
+    let old_key = derive_key(current, &envelope.kdf)?;
+    let text = open(&envelope, &old_key)?;
+    let kdf = new_kdf();
+    let unlocked = Unlocked { key: derive_key(new, &kdf)?, kdf };
+    storage::write_atomic(&encrypted_path(), &seal(&text, &unlocked)?)?;
+    snapshots::convert(|bytes| seal(&open(&parse_envelope(bytes)?, &old_key)?, &unlocked));
+    remember(keep_in_keyring.then_some(&unlocked.key));
+    *guard = Some(unlocked);
+    Ok(())
+}
+
+pub fn status() -> EncryptionStatus {
+    let enabled = enabled();
+    EncryptionStatus {
+        enabled,
+        unlocked: !enabled || vault().is_some(),
+        remembered: enabled && remembered_key().is_some(),
+    }
+}
+
+#[tauri::command]
+pub fn encryption_status() -> EncryptionStatus {
+    status()
+}
+
+#[tauri::command]
+pub fn unlock_data(app: AppHandle, passphrase: String, remember: bool) -> Result<(), String> {
+    unlock(&passphrase, remember)?;
+    snapshots::take_startup();
+    let data = storage::load_json();
+    crate::search::refresh(&data);
+    crate::tray::refresh(&app);
+    let _ = app.emit("data-changed", ());
+    Ok(())
+}
+
+#[tauri::command]
+pub fn enable_encryption(passphrase: String, remember: bool) -> Result<EncryptionStatus, String> {
+    enable(&passphrase, remember)?;
+    Ok(status())
+}
+
+#[tauri::command]
+pub fn disable_encryption(passphrase: String) -> Result<EncryptionStatus, String> {
+    disable(&passphrase)?;
+    Ok(status())
+}
+
+#[tauri::command]
+pub fn change_encryption_passphrase(current: String, new: String, remember: bool) -> Result<EncryptionStatus, String> {
+    change_passphrase(&current, &new, remember)?;
+    Ok(status())
+}
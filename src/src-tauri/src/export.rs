@@ -0,0 +1,438 @@
+//! Export tasks as Markdown, todo.txt, CSV, org-mode or a JSON bundle, for
+//! all projects or one. Served by `GET /api/export` and the `export_tasks` command.
+
+use std::collections::HashMap;
+
+use chrono::{Datelike, Local, TimeZone};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::model::{LogEntry, Project, RecurrenceType, Task, TaskStatus};
+use crate::storage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Todotxt,
+    Csv,
+    Org,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "markdown" | "md" => Some(ExportFormat::Markdown),
+            "todotxt" => Some(ExportFormat::Todotxt),
+            "csv" => Some(ExportFormat::Csv),
+            "org" => Some(ExportFormat::Org),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "text/markdown; charset=utf-8",
+            ExportFormat::Todotxt => "text/plain; charset=utf-8",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Org => "text/org; charset=utf-8",
+            ExportFormat::Json => "application/json",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// Project slug; all projects when absent.
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub include_archived: bool,
+}
+
+/// A project and its tasks in display order, each with its nesting depth.
+struct ProjectExport {
+    project: Project,
+    tasks: Vec<(Task, usize)>,
+}
+
+pub fn export(data: &Value, options: &ExportOptions) -> Result<String, String> {
+    let projects = collect(data, options)?;
+    match options.format {
+        ExportFormat::Markdown => Ok(markdown(&projects)),
+        ExportFormat::Todotxt => Ok(todotxt(&projects)),
+        ExportFormat::Csv => csv(&projects),
+        ExportFormat::Org => Ok(org(&projects)),
+        ExportFormat::Json => Ok(bundle(data, &projects)),
+    }
+}
+
+#[tauri::command]
+pub fn export_tasks(options: ExportOptions) -> Result<String, String> {
+    storage::ensure_unlocked()?;
+    export(&storage::load_json(), &options)
+}
+
+fn collect(data: &Value, options: &ExportOptions) -> Result<Vec<ProjectExport>, String> {
+    let mut projects: Vec<Project> = data
+        .get("projects")
+        .and_then(|p| p.as_object())
+        .map(|p| p.values().filter_map(|v| serde_json::from_value(v.clone()).ok()).collect())
+        .unwrap_or_default();
+    if let Some(slug) = &options.project {
+        projects.retain(|p| &p.slug == slug);
+        if projects.is_empty() {
+            return Err(format!("project not found: {}", slug));
+        }
+    }
+    projects.sort_by_key(|p| (!p.is_inbox, p.created_at));
+
+    let all_tasks: Vec<Task> = data
+        .get("tasks")
+        .and_then(|t| t.as_object())
+        .map(|t| t.values().filter_map(|v| serde_json::from_value(v.clone()).ok()).collect())
+        .unwrap_or_default();
+
+    Ok(projects
+        .into_iter()
+        .map(|project| {
+            let mut tasks: Vec<Task> = all_tasks
+                .iter()
+                .filter(|t| t.project_id == project.id && (options.include_archived || !t.archived))
+                .cloned()
+                .collect();
+            // taskOrder first, then anything missing from it by creation time
+            let position: HashMap<&str, usize> =
+                project.task_order.iter().enumerate().map(|(i, id)| (id.as_str(), i)).collect();
+            tasks.sort_by_key(|t| (position.get(t.id.as_str()).copied().unwrap_or(usize::MAX), t.created_at));
+            let tasks = nest(tasks);
+            ProjectExport { project, tasks }
+        })
+        .collect())
+}
+
+/// Depth-first order: each task followed by its subtasks. Tasks whose parent
+/// isn't exported are treated as top-level.
+fn nest(tasks: Vec<Task>) -> Vec<(Task, usize)> {
+    let ids: Vec<String> = tasks.iter().map(|t| t.id.clone()).collect();
+    let is_root = |t: &Task| t.parent_id.as_ref().is_none_or(|p| !ids.contains(p) || p == &t.id);
+
+    let mut out = Vec::with_capacity(tasks.len());
+    let mut stack: Vec<(usize, usize)> = tasks
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| is_root(t))
+        .map(|(i, _)| (i, 0))
+        .rev()
+        .collect();
+    let mut visited = vec![false; tasks.len()];
+    while let Some((i, depth)) = stack.pop() {
+        if std::mem::replace(&mut visited[i], true) {
+            continue;
+        }
+        out.push((tasks[i].clone(), depth));
+        let children = tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.parent_id.as_deref() == Some(tasks[i].id.as_str()))
+            .map(|(j, _)| (j, depth + 1));
+        stack.extend(children.collect::<Vec<_>>().into_iter().rev());
+    }
+    out
+}
+
+fn date(ms: Option<i64>) -> Option<String> {
+    Local
+        .timestamp_millis_opt(ms?)
+        .single()
+        .map(|d| d.format("%Y-%m-%d").to_string())
+}
+
+fn timestamp(ms: Option<i64>) -> String {
+    ms.and_then(|ms| Local.timestamp_millis_opt(ms).single())
+        .map(|d| d.to_rfc3339())
+        .unwrap_or_default()
+}
+
+fn recurrence_label(task: &Task) -> Option<String> {
+    const DAYS: [&str; 7] = ["sunday", "monday", "tuesday", "wednesday", "thursday", "friday", "saturday"];
+    let recurrence = task.recurrence?;
+    Some(match (recurrence.kind, recurrence.day_of_week) {
+        (RecurrenceType::Daily, _) => "every day".to_string(),
+        (RecurrenceType::Weekdays, _) => "every weekday".to_string(),
+        (RecurrenceType::Weekly, Some(day)) => format!("every {}", DAYS.get(day as usize).unwrap_or(&"week")),
+        (RecurrenceType::Weekly, None) => "every week".to_string(),
+        (RecurrenceType::Monthly, _) => "every month".to_string(),
+    })
+}
+
+fn markdown(projects: &[ProjectExport]) -> String {
+    let mut out = format!("# Visor export — {}\n", Local::now().format("%Y-%m-%d"));
+    for export in projects {
+        out.push_str(&format!("\n## {}\n\n", export.project.name));
+        if export.tasks.is_empty() {
+            out.push_str("_No tasks_\n");
+        }
+        for (task, depth) in &export.tasks {
+            let indent = "  ".repeat(*depth);
+            let checkbox = if task.status == TaskStatus::Done { "[x]" } else { "[ ]" };
+            let content = if task.status == TaskStatus::Cancelled {
+                format!("~~{}~~", task.content)
+            } else {
+                task.content.clone()
+            };
+
+            let mut meta = Vec::new();
+            if !matches!(task.status, TaskStatus::Todo | TaskStatus::Done) {
+                meta.push(task.status.as_str().to_string());
+            }
+            if let Some(due) = date(task.due_at) {
+                meta.push(format!("due {}", due));
+            }
+            if let Some(scheduled) = date(task.scheduled) {
+                meta.push(format!("scheduled {}", scheduled));
+            }
+            if let Some(recurrence) = recurrence_label(task) {
+                meta.push(recurrence);
+            }
+            if task.archived {
+                meta.push("archived".to_string());
+            }
+
+            out.push_str(&format!("{}- {} {}", indent, checkbox, content));
+            if !meta.is_empty() {
+                out.push_str(&format!(" _({})_", meta.join(" · ")));
+            }
+            out.push('\n');
+            if let Some(notes) = task.notes.as_deref().filter(|n| !n.trim().is_empty()) {
+                for line in notes.lines() {
+                    out.push_str(&format!("{}  > {}\n", indent, line));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// One line per task. todo.txt has no nesting or notes; Visor-only statuses
+/// and archiving are kept as `status:` and `archived:` tags.
+fn todotxt(projects: &[ProjectExport]) -> String {
+    let mut out = String::new();
+    for export in projects {
+        for (task, _) in &export.tasks {
+            let mut parts = Vec::new();
+            if task.status == TaskStatus::Done {
+                parts.push("x".to_string());
+                if let Some(completed) = date(task.completed_at) {
+                    parts.push(completed);
+                }
+            }
+            if let Some(created) = date(Some(task.created_at)) {
+                parts.push(created);
+            }
+            parts.push(task.content.clone());
+            if !export.project.is_inbox {
+                parts.push(format!("+{}", export.project.slug));
+            }
+            if let Some(due) = date(task.due_at) {
+                parts.push(format!("due:{}", due));
+            }
+            if let Some(scheduled) = date(task.scheduled) {
+                parts.push(format!("t:{}", scheduled));
+            }
+            if let Some(recurrence) = task.recurrence {
+                parts.push(
+                    match recurrence.kind {
+                        RecurrenceType::Daily => "rec:1d",
+                        RecurrenceType::Weekdays => "rec:1b",
+                        RecurrenceType::Weekly => "rec:1w",
+                        RecurrenceType::Monthly => "rec:1m",
+                    }
+                    .to_string(),
+                );
+            }
+            if matches!(task.status, TaskStatus::Doing | TaskStatus::Waiting | TaskStatus::Cancelled) {
+                parts.push(format!("status:{}", task.status.as_str().to_lowercase()));
+            }
+            if task.archived {
+                parts.push("archived:true".to_string());
+            }
+            out.push_str(&parts.join(" "));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn csv(projects: &[ProjectExport]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record([
+            "id", "project", "content", "status", "parentId", "indent", "createdAt", "completedAt",
+            "dueAt", "scheduled", "recurrence", "archived", "notes",
+        ])
+        .map_err(|e| e.to_string())?;
+    for export in projects {
+        for (task, depth) in &export.tasks {
+            writer
+                .write_record([
+                    task.id.as_str(),
+                    export.project.slug.as_str(),
+                    task.content.as_str(),
+                    task.status.as_str(),
+                    task.parent_id.as_deref().unwrap_or(""),
+                    &depth.to_string(),
+                    &timestamp(Some(task.created_at)),
+                    &timestamp(task.completed_at),
+                    &date(task.due_at).unwrap_or_default(),
+                    &date(task.scheduled).unwrap_or_default(),
+                    &recurrence_label(task).unwrap_or_default(),
+                    if task.archived { "true" } else { "false" },
+                    task.notes.as_deref().unwrap_or(""),
+                ])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+/// Org-mode outline: projects as top-level headings with their tasks nested
+/// below, ids and other Visor-only fields in property drawers. The `org`
+/// import reads it back.
+fn org(projects: &[ProjectExport]) -> String {
+    let mut out = format!(
+        "#+TITLE: Visor export — {}\n#+TODO: TODO DOING WAITING | DONE CANCELLED\n",
+        Local::now().format("%Y-%m-%d")
+    );
+    for export in projects {
+        let project = &export.project;
+        out.push_str(&format!(
+            "\n* {}\n:PROPERTIES:\n:ID: {}\n:SLUG: {}\n:COLOR: {}\n:END:\n",
+            project.name, project.id, project.slug, project.color
+        ));
+
+        for (task, depth) in &export.tasks {
+            out.push_str(&format!(
+                "{} {} {}",
+                "*".repeat(depth + 2),
+                task.status.as_str(),
+                task.content.replace('\n', " ")
+            ));
+            if task.archived {
+                out.push_str(" :ARCHIVE:");
+            }
+            out.push('\n');
+
+            // The repeater goes on the date recurrence moves: the deadline, or
+            // the scheduled date without one
+            let anchor = task.due_at.or(task.scheduled);
+            let (repeater, exact) = org_repeater(task, anchor);
+            let mut planning = Vec::new();
+            if matches!(task.status, TaskStatus::Done | TaskStatus::Cancelled) {
+                if let Some(closed) = org_inactive(task.completed_at) {
+                    planning.push(format!("CLOSED: {}", closed));
+                }
+            }
+            if let Some(due) = org_active(task.due_at, repeater) {
+                planning.push(format!("DEADLINE: {}", due));
+            }
+            let scheduled_repeater = if task.due_at.is_none() { repeater } else { None };
+            if let Some(scheduled) = org_active(task.scheduled, scheduled_repeater) {
+                planning.push(format!("SCHEDULED: {}", scheduled));
+            }
+            if !planning.is_empty() {
+                out.push_str(&planning.join(" "));
+                out.push('\n');
+            }
+
+            out.push_str(&format!(":PROPERTIES:\n:ID: {}\n", task.id));
+            if let Some(created) = org_inactive(Some(task.created_at)) {
+                out.push_str(&format!(":CREATED: {}\n", created));
+            }
+            if !exact {
+                if let Some(recurrence) = recurrence_label(task) {
+                    out.push_str(&format!(":RECURRENCE: {}\n", recurrence));
+                }
+            }
+            out.push_str(":END:\n");
+
+            if let Some(notes) = task.notes.as_deref().filter(|n| !n.trim().is_empty()) {
+                for line in notes.lines() {
+                    // `,*` keeps a note line from reading as a heading
+                    if line.trim_start_matches(',').starts_with('*') {
+                        out.push(',');
+                    }
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+        }
+    }
+    out
+}
+
+/// The org repeater for the task's recurrence, and whether it says it all.
+/// Weekdays, and weekly on a day other than the anchor's, also need the
+/// `RECURRENCE` property.
+fn org_repeater(task: &Task, anchor: Option<i64>) -> (Option<&'static str>, bool) {
+    let Some(recurrence) = task.recurrence else { return (None, true) };
+    let Some(anchor) = anchor.and_then(|ms| Local.timestamp_millis_opt(ms).single()) else {
+        return (None, false);
+    };
+    match recurrence.kind {
+        RecurrenceType::Daily => (Some("+1d"), true),
+        RecurrenceType::Weekdays => (Some("+1d"), false),
+        RecurrenceType::Weekly => (
+            Some("+1w"),
+            recurrence
+                .day_of_week
+                .is_none_or(|day| day == anchor.weekday().num_days_from_sunday()),
+        ),
+        RecurrenceType::Monthly => (Some("+1m"), true),
+    }
+}
+
+fn org_active(ms: Option<i64>, repeater: Option<&str>) -> Option<String> {
+    let date = Local.timestamp_millis_opt(ms?).single()?.format("%Y-%m-%d %a");
+    Some(match repeater {
+        Some(repeater) => format!("<{} {}>", date, repeater),
+        None => format!("<{}>", date),
+    })
+}
+
+fn org_inactive(ms: Option<i64>) -> Option<String> {
+    Local
+        .timestamp_millis_opt(ms?)
+        .single()
+        .map(|d| d.format("[%Y-%m-%d %a %H:%M]").to_string())
+}
+
+/// Projects, tasks and journal entries in `data.json` shape, restricted to
+/// the exported projects.
+fn bundle(data: &Value, projects: &[ProjectExport]) -> String {
+    let project_ids: Vec<&str> = projects.iter().map(|p| p.project.id.as_str()).collect();
+    let log: Vec<LogEntry> = data
+        .get("logEntries")
+        .and_then(|l| l.as_array())
+        .map(|l| {
+            l.iter()
+                .filter_map(|e| serde_json::from_value::<LogEntry>(e.clone()).ok())
+                .filter(|e| project_ids.contains(&e.project_id.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let bundle = json!({
+        "version": 1,
+        "exportedAt": chrono::Utc::now().timestamp_millis(),
+        "projects": projects.iter().map(|p| &p.project).collect::<Vec<_>>(),
+        "tasks": projects.iter().flat_map(|p| p.tasks.iter().map(|(t, _)| t)).collect::<Vec<_>>(),
+        "logEntries": log,
+    });
+    serde_json::to_string_pretty(&bundle).unwrap_or_default()
+}
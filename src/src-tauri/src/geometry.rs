@@ -0,0 +1,344 @@
+//! Visor window geometry. Everything here is pure so the placement math can be
+//! exercised with synthetic monitor layouts; `lib.rs` feeds in the real monitors.
+
+use serde::{Deserialize, Serialize};
+
+/// Screen edge the visor drops from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Edge {
+    #[default]
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// Which monitor the visor opens on.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum MonitorPolicy {
+    /// Monitor under the mouse cursor.
+    #[default]
+    FollowCursor,
+    /// Always the primary monitor.
+    Primary,
+    /// A monitor by name; falls back to the primary one when it is not connected.
+    Pinned { name: String },
+}
+
+/// Extra space (logical px) trimmed from the layout area, for desktops that
+/// misreport their work area. Negative values grow the area instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Insets {
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub left: i32,
+}
+
+/// Insets per platform, so one `settings.json` can be shared between machines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PlatformInsets {
+    pub macos: Insets,
+    pub linux: Insets,
+    pub windows: Insets,
+}
+
+impl PlatformInsets {
+    pub fn current(&self) -> Insets {
+        if cfg!(target_os = "macos") {
+            self.macos
+        } else if cfg!(target_os = "windows") {
+            self.windows
+        } else {
+            self.linux
+        }
+    }
+}
+
+/// User-configurable placement, stored as `window` in `settings.json`.
+///
+/// `heightRatio` is how far the visor extends away from its edge, as a fraction
+/// of the monitor. `maxWidth` (logical px) caps its length along the edge; a
+/// capped visor is centered on that edge.
+///
+/// The visor is laid out in the monitor's work area (minus panels, docks and
+/// the menu bar). `coverMenuBar` lays it out over the full monitor instead and,
+/// on macOS, raises it above the menu bar with window level 25.
+///
+/// `linuxWindowHints` asks the window manager to show the visor on all
+/// workspaces, above other windows and outside pagers (the Linux counterpart of
+/// the macOS collection behavior).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VisorGeometry {
+    pub height_ratio: f64,
+    pub max_width: Option<u32>,
+    pub edge: Edge,
+    pub monitor: MonitorPolicy,
+    pub cover_menu_bar: bool,
+    pub insets: PlatformInsets,
+    pub linux_window_hints: bool,
+}
+
+impl Default for VisorGeometry {
+    fn default() -> Self {
+        Self {
+            height_ratio: 0.45,
+            max_width: None,
+            edge: Edge::Top,
+            monitor: MonitorPolicy::FollowCursor,
+            cover_menu_bar: false,
+            insets: PlatformInsets::default(),
+            linux_window_hints: false,
+        }
+    }
+}
+
+impl VisorGeometry {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.height_ratio > 0.0 && self.height_ratio <= 1.0) {
+            return Err("heightRatio must be greater than 0 and at most 1".to_string());
+        }
+        if self.max_width == Some(0) {
+            return Err("maxWidth must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Physical-pixel rectangle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x as f64
+            && y >= self.y as f64
+            && x < self.x as f64 + self.width as f64
+            && y < self.y as f64 + self.height as f64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorInfo {
+    pub name: Option<String>,
+    pub rect: Rect,
+    /// Monitor bounds minus menu bar, docks and panels, as reported by the OS.
+    pub work_area: Rect,
+    pub scale_factor: f64,
+    pub is_primary: bool,
+}
+
+/// Pick the monitor for `policy`. Every policy falls back to the primary
+/// monitor, then to the first one listed.
+pub fn select_monitor<'a>(
+    monitors: &'a [MonitorInfo],
+    policy: &MonitorPolicy,
+    cursor: Option<(f64, f64)>,
+) -> Option<&'a MonitorInfo> {
+    let chosen = match policy {
+        MonitorPolicy::FollowCursor => {
+            cursor.and_then(|(x, y)| monitors.iter().find(|m| m.rect.contains(x, y)))
+        }
+        MonitorPolicy::Primary => None,
+        MonitorPolicy::Pinned { name } => monitors
+            .iter()
+            .find(|m| m.name.as_deref() == Some(name.as_str())),
+    };
+
+    chosen
+        .or_else(|| monitors.iter().find(|m| m.is_primary))
+        .or_else(|| monitors.first())
+}
+
+/// Area of `monitor` the visor is laid out in: the work area (or the full
+/// monitor with `coverMenuBar`, or when the OS reports no work area), shrunk by
+/// this platform's insets.
+pub fn layout_area(monitor: &MonitorInfo, geometry: &VisorGeometry) -> Rect {
+    let work_area_known = monitor.work_area.width > 0 && monitor.work_area.height > 0;
+    let base = if geometry.cover_menu_bar || !work_area_known {
+        monitor.rect
+    } else {
+        monitor.work_area
+    };
+
+    let insets = geometry.insets.current();
+    let px = |v: i32| (v as f64 * monitor.scale_factor).round() as i64;
+    let (top, right, bottom, left) = (
+        px(insets.top),
+        px(insets.right),
+        px(insets.bottom),
+        px(insets.left),
+    );
+
+    Rect {
+        x: (base.x as i64 + left) as i32,
+        y: (base.y as i64 + top) as i32,
+        width: (base.width as i64 - left - right).max(1) as u32,
+        height: (base.height as i64 - top - bottom).max(1) as u32,
+    }
+}
+
+/// Window rectangle for the visor inside `area` (see `layout_area`).
+pub fn visor_rect(area: Rect, scale_factor: f64, geometry: &VisorGeometry) -> Rect {
+    let ratio = geometry.height_ratio.clamp(0.0, 1.0);
+    let horizontal = matches!(geometry.edge, Edge::Top | Edge::Bottom);
+
+    // Length along the edge and depth away from it
+    let (span, depth_extent) = if horizontal {
+        (area.width, area.height)
+    } else {
+        (area.height, area.width)
+    };
+    let length = geometry
+        .max_width
+        .map(|w| ((w as f64 * scale_factor).round() as u32).min(span))
+        .unwrap_or(span);
+    let depth = ((depth_extent as f64 * ratio) as u32).max(1);
+    let offset = ((span - length) / 2) as i32;
+
+    match geometry.edge {
+        Edge::Top => Rect {
+            x: area.x + offset,
+            y: area.y,
+            width: length,
+            height: depth,
+        },
+        Edge::Bottom => Rect {
+            x: area.x + offset,
+            y: area.y + area.height.saturating_sub(depth) as i32,
+            width: length,
+            height: depth,
+        },
+        Edge::Left => Rect {
+            x: area.x,
+            y: area.y + offset,
+            width: depth,
+            height: length,
+        },
+        Edge::Right => Rect {
+            x: area.x + area.width.saturating_sub(depth) as i32,
+            y: area.y + offset,
+            width: depth,
+            height: length,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) -> Rect {
+        Rect { x, y, width, height }
+    }
+
+    fn monitor(name: &str, rect: Rect, work_area: Rect, scale_factor: f64, is_primary: bool) -> MonitorInfo {
+        MonitorInfo { name: Some(name.to_string()), rect, work_area, scale_factor, is_primary }
+    }
+
+    /// A 1080p laptop on the left, a primary 4K monitor at 2x to its right
+    /// (menu bar on top) and a portrait monitor stacked above the laptop.
+    fn layout() -> Vec<MonitorInfo> {
+        vec![
+            monitor("laptop", rect(0, 0, 1920, 1080), rect(0, 0, 1920, 1040), 1.0, false),
+            monitor("4k", rect(1920, 0, 3840, 2160), rect(1920, 50, 3840, 2110), 2.0, true),
+            monitor("portrait", rect(0, -1920, 1080, 1920), rect(0, 0, 0, 0), 1.0, false),
+        ]
+    }
+
+    fn name(monitor: Option<&MonitorInfo>) -> Option<&str> {
+        monitor.and_then(|m| m.name.as_deref())
+    }
+
+    #[test]
+    fn follows_the_cursor() {
+        let monitors = layout();
+        let follow = MonitorPolicy::FollowCursor;
+        assert_eq!(name(select_monitor(&monitors, &follow, Some((100.0, 100.0)))), Some("laptop"));
+        assert_eq!(name(select_monitor(&monitors, &follow, Some((5000.0, 10.0)))), Some("4k"));
+        assert_eq!(name(select_monitor(&monitors, &follow, Some((500.0, -5.0)))), Some("portrait"));
+        // Right edges are exclusive
+        assert_eq!(name(select_monitor(&monitors, &follow, Some((1920.0, 0.0)))), Some("4k"));
+        // Off every monitor, or no cursor: the primary one
+        assert_eq!(name(select_monitor(&monitors, &follow, Some((-10.0, 5000.0)))), Some("4k"));
+        assert_eq!(name(select_monitor(&monitors, &follow, None)), Some("4k"));
+    }
+
+    #[test]
+    fn primary_and_pinned_fall_back() {
+        let mut monitors = layout();
+        assert_eq!(name(select_monitor(&monitors, &MonitorPolicy::Primary, Some((0.0, 0.0)))), Some("4k"));
+        let pinned = |n: &str| MonitorPolicy::Pinned { name: n.to_string() };
+        assert_eq!(name(select_monitor(&monitors, &pinned("portrait"), None)), Some("portrait"));
+        assert_eq!(name(select_monitor(&monitors, &pinned("projector"), None)), Some("4k"));
+
+        // Without a primary monitor, the first one listed
+        monitors[1].is_primary = false;
+        assert_eq!(name(select_monitor(&monitors, &pinned("projector"), None)), Some("laptop"));
+        assert_eq!(name(select_monitor(&monitors, &MonitorPolicy::Primary, None)), Some("laptop"));
+        assert!(select_monitor(&[], &MonitorPolicy::Primary, None).is_none());
+    }
+
+    #[test]
+    fn lays_out_in_the_work_area_minus_insets() {
+        let monitors = layout();
+        let mut geometry = VisorGeometry::default();
+        assert_eq!(layout_area(&monitors[1], &geometry), rect(1920, 50, 3840, 2110));
+
+        // Over the menu bar, or when the OS reports no work area
+        geometry.cover_menu_bar = true;
+        assert_eq!(layout_area(&monitors[1], &geometry), rect(1920, 0, 3840, 2160));
+        geometry.cover_menu_bar = false;
+        assert_eq!(layout_area(&monitors[2], &geometry), rect(0, -1920, 1080, 1920));
+
+        // Insets are logical, so they scale; negative ones grow the area
+        let insets = Insets { top: 10, right: 20, bottom: 0, left: -5 };
+        geometry.insets = PlatformInsets { macos: insets, linux: insets, windows: insets };
+        assert_eq!(layout_area(&monitors[1], &geometry), rect(1910, 70, 3810, 2090));
+        assert_eq!(layout_area(&monitors[0], &geometry), rect(-5, 10, 1905, 1030));
+
+        // Never collapses to nothing
+        let huge = Insets { top: 5000, right: 0, bottom: 0, left: 0 };
+        geometry.insets = PlatformInsets { macos: huge, linux: huge, windows: huge };
+        assert_eq!(layout_area(&monitors[0], &geometry).height, 1);
+    }
+
+    #[test]
+    fn places_the_visor_on_every_edge() {
+        let area = rect(100, 50, 2000, 1000);
+        let at = |edge: Edge| {
+            let geometry = VisorGeometry { height_ratio: 0.5, edge, ..VisorGeometry::default() };
+            visor_rect(area, 1.0, &geometry)
+        };
+        assert_eq!(at(Edge::Top), rect(100, 50, 2000, 500));
+        assert_eq!(at(Edge::Bottom), rect(100, 550, 2000, 500));
+        assert_eq!(at(Edge::Left), rect(100, 50, 1000, 1000));
+        assert_eq!(at(Edge::Right), rect(1100, 50, 1000, 1000));
+    }
+
+    #[test]
+    fn centers_a_capped_visor_in_physical_pixels() {
+        let area = rect(1920, 50, 3840, 2110);
+        let geometry = VisorGeometry { max_width: Some(1200), ..VisorGeometry::default() };
+        // 1200 logical px at 2x is 2400 physical, centered in 3840
+        assert_eq!(visor_rect(area, 2.0, &geometry), rect(2640, 50, 2400, 949));
+
+        // Along a side edge the cap applies to the height
+        let side = VisorGeometry { max_width: Some(500), edge: Edge::Right, height_ratio: 0.25, ..VisorGeometry::default() };
+        assert_eq!(visor_rect(area, 1.5, &side), rect(4800, 730, 960, 750));
+
+        // A cap wider than the area is ignored
+        let wide = VisorGeometry { max_width: Some(5000), ..VisorGeometry::default() };
+        assert_eq!(visor_rect(area, 2.0, &wide).width, 3840);
+    }
+}
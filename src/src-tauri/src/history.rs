@@ -0,0 +1,340 @@
+//! Optional version history of `~/.visor` in a local git repository.
+//!
+//! When `history.enabled` is set, a background thread watches the data and
+//! settings files and commits the directory once writes have been quiet for
+//! `history.quietSeconds`, so a burst of edits becomes one commit. Messages
+//! describe what changed ("Completed 3 tasks in work"), worked out by
+//! comparing the data with the previous commit; while the data is encrypted
+//! they stay generic so they don't leak it. Nothing is ever pushed anywhere.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use chrono::{Local, TimeZone};
+use git2::{Commit, IndexAddOption, Repository, Signature, Sort, Tree};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+use crate::{encryption, search, settings, storage};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Files whose changes are committed.
+const WATCHED: [&str; 3] = ["data.json", "data.enc", "settings.json"];
+/// Everything else in the directory stays out of the repository.
+const GITIGNORE: &str = "*\n!.gitignore\n!data.json\n!data.enc\n!settings.json\n";
+/// Subject lines list this many changes; the rest go in the body.
+const SUBJECT_CHANGES: usize = 3;
+
+/// Serializes commits and restores.
+static REPO: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: String,
+    pub created_at: i64,
+    pub summary: String,
+    pub message: String,
+}
+
+fn open_or_init() -> Result<Repository, String> {
+    let dir = storage::data_path();
+    if let Ok(repo) = Repository::open(&dir) {
+        return Ok(repo);
+    }
+    let repo = Repository::init(&dir).map_err(|e| e.to_string())?;
+    fs::write(dir.join(".gitignore"), GITIGNORE).map_err(|e| e.to_string())?;
+    Ok(repo)
+}
+
+fn entry(commit: &Commit) -> HistoryEntry {
+    HistoryEntry {
+        id: commit.id().to_string(),
+        created_at: commit.time().seconds() * 1000,
+        summary: commit.summary().unwrap_or_default().to_string(),
+        message: commit.message().unwrap_or_default().trim_end().to_string(),
+    }
+}
+
+fn blob_text(repo: &Repository, tree: &Tree, name: &str) -> Option<String> {
+    let blob = tree.get_name(name)?.to_object(repo).ok()?.peel_to_blob().ok()?;
+    String::from_utf8(blob.content().to_vec()).ok()
+}
+
+fn count(n: usize, one: &str, many: &str) -> String {
+    format!("{} {}", n, if n == 1 { one } else { many })
+}
+
+/// What happened between two versions of the data, one phrase per kind of
+/// change and project, most significant first.
+pub fn describe(old: &Value, new: &Value) -> Vec<String> {
+    let tasks = |data: &Value| data.get("tasks").and_then(|t| t.as_object()).cloned().unwrap_or_default();
+    let (before, after) = (tasks(old), tasks(new));
+    let projects = |data: &Value| data.get("projects").and_then(|p| p.as_object()).cloned().unwrap_or_default();
+    let (old_projects, new_projects) = (projects(old), projects(new));
+    let project_name = |task: &Value| {
+        let id = task.get("projectId").and_then(|p| p.as_str()).unwrap_or("inbox");
+        new_projects
+            .get(id)
+            .or(old_projects.get(id))
+            .and_then(|p| p.get("slug"))
+            .and_then(|s| s.as_str())
+            .unwrap_or(id)
+            .to_string()
+    };
+    let is_done = |task: &Value| task.get("status").and_then(|s| s.as_str()) == Some("DONE");
+    let is_archived = |task: &Value| task.get("archived").and_then(|a| a.as_bool()).unwrap_or(false);
+
+    // (verb, project) -> tasks; the verbs sort in the order they're listed here
+    let mut changes: BTreeMap<(usize, String), usize> = BTreeMap::new();
+    let verbs = ["Added", "Completed", "Deleted", "Archived", "Reopened", "Edited"];
+    let mut note = |verb: &str, task: &Value| {
+        let index = verbs.iter().position(|v| *v == verb).unwrap_or(0);
+        *changes.entry((index, project_name(task))).or_default() += 1;
+    };
+    for (id, task) in &after {
+        match before.get(id) {
+            None => note("Added", task),
+            Some(old) if old == task => {}
+            Some(old) if is_done(task) && !is_done(old) => note("Completed", task),
+            Some(old) if is_archived(task) && !is_archived(old) => note("Archived", task),
+            Some(old) if is_done(old) && !is_done(task) => note("Reopened", task),
+            Some(_) => note("Edited", task),
+        }
+    }
+    for (id, task) in &before {
+        if !after.contains_key(id) {
+            note("Deleted", task);
+        }
+    }
+
+    let mut phrases: Vec<String> = changes
+        .into_iter()
+        .map(|((verb, project), n)| format!("{} {} in {}", verbs[verb], count(n, "task", "tasks"), project))
+        .collect();
+    for (id, project) in &new_projects {
+        if !old_projects.contains_key(id) {
+            let slug = project.get("slug").and_then(|s| s.as_str()).unwrap_or(id);
+            phrases.push(format!("Created project {}", slug));
+        }
+    }
+    for (id, project) in &old_projects {
+        if !new_projects.contains_key(id) {
+            let slug = project.get("slug").and_then(|s| s.as_str()).unwrap_or(id);
+            phrases.push(format!("Deleted project {}", slug));
+        }
+    }
+    let entries = |data: &Value| data.get("logEntries").and_then(|l| l.as_array()).map_or(0, |l| l.len());
+    let (old_entries, new_entries) = (entries(old), entries(new));
+    if new_entries > old_entries {
+        phrases.push(format!("Logged {}", count(new_entries - old_entries, "journal entry", "journal entries")));
+    } else if new_entries < old_entries {
+        phrases.push(format!("Removed {}", count(old_entries - new_entries, "journal entry", "journal entries")));
+    }
+    phrases
+}
+
+/// Subject line plus a body listing every change when they don't all fit.
+fn message(phrases: &[String]) -> String {
+    let Some((first, rest)) = phrases.split_first() else {
+        return "Updated data".to_string();
+    };
+    let mut subject = first.clone();
+    for phrase in rest.iter().take(SUBJECT_CHANGES - 1) {
+        subject.push_str(", ");
+        subject.push_str(&phrase[..1].to_lowercase());
+        subject.push_str(&phrase[1..]);
+    }
+    if phrases.len() <= SUBJECT_CHANGES {
+        return subject;
+    }
+    let more = phrases.len() - SUBJECT_CHANGES;
+    format!("{} and {}\n\n{}", subject, count(more, "more change", "more changes"), phrases.join("\n"))
+}
+
+/// Message for going from `parent` to `tree`.
+fn describe_commit(repo: &Repository, parent: Option<&Commit>, tree: &Tree) -> String {
+    let Some(parent) = parent else {
+        return "Start version history".to_string();
+    };
+    let Ok(old_tree) = parent.tree() else {
+        return "Updated data".to_string();
+    };
+    let changed = |name: &str| old_tree.get_name(name).map(|e| e.id()) != tree.get_name(name).map(|e| e.id());
+
+    let mut phrases = Vec::new();
+    if tree.get_name("data.enc").is_some() {
+        if old_tree.get_name("data.enc").is_none() {
+            phrases.push("Encrypted data".to_string());
+        } else if changed("data.enc") {
+            phrases.push("Updated encrypted data".to_string());
+        }
+    } else if old_tree.get_name("data.enc").is_some() {
+        phrases.push("Decrypted data".to_string());
+    } else if changed("data.json") {
+        let parse = |text: Option<String>| {
+            text.and_then(|t| serde_json::from_str::<Value>(&t).ok()).unwrap_or(Value::Null)
+        };
+        let found = describe(&parse(blob_text(repo, &old_tree, "data.json")), &parse(blob_text(repo, tree, "data.json")));
+        if found.is_empty() {
+            phrases.push("Updated data".to_string());
+        }
+        phrases.extend(found);
+    }
+    if changed("settings.json") {
+        phrases.push("Changed settings".to_string());
+    }
+    message(&phrases)
+}
+
+/// Commit the directory as it is now, unless nothing changed since the last
+/// commit. `message` overrides the generated one.
+fn commit(message: Option<&str>) -> Result<Option<HistoryEntry>, String> {
+    let repo = open_or_init()?;
+    let mut index = repo.index().map_err(|e| e.to_string())?;
+    index.add_all(["*"], IndexAddOption::DEFAULT, None).map_err(|e| e.to_string())?;
+    index.update_all(["*"], None).map_err(|e| e.to_string())?;
+    index.write().map_err(|e| e.to_string())?;
+    let tree_id = index.write_tree().map_err(|e| e.to_string())?;
+    let tree = repo.find_tree(tree_id).map_err(|e| e.to_string())?;
+
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    if parent.as_ref().is_some_and(|p| p.tree_id() == tree_id) {
+        return Ok(None);
+    }
+    let text = match message {
+        Some(m) => m.to_string(),
+        None => describe_commit(&repo, parent.as_ref(), &tree),
+    };
+    let signature = Signature::now("Visor", "visor@localhost").map_err(|e| e.to_string())?;
+    let parents: Vec<&Commit> = parent.iter().collect();
+    let id = repo
+        .commit(Some("HEAD"), &signature, &signature, &text, &tree, &parents)
+        .map_err(|e| e.to_string())?;
+    let commit = repo.find_commit(id).map_err(|e| e.to_string())?;
+    Ok(Some(entry(&commit)))
+}
+
+/// Commits, newest first; empty when history was never enabled.
+pub fn log(limit: usize) -> Result<Vec<HistoryEntry>, String> {
+    let Ok(repo) = Repository::open(storage::data_path()) else {
+        return Ok(Vec::new());
+    };
+    if repo.head().is_err() {
+        return Ok(Vec::new());
+    }
+    let mut walk = repo.revwalk().map_err(|e| e.to_string())?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME).map_err(|e| e.to_string())?;
+    walk.push_head().map_err(|e| e.to_string())?;
+    Ok(walk
+        .flatten()
+        .filter_map(|id| repo.find_commit(id).ok())
+        .take(limit)
+        .map(|c| entry(&c))
+        .collect())
+}
+
+/// Replace the task data with its version in commit `id`. Pending changes
+/// are committed first and the restore is a commit of its own, so nothing
+/// is lost. Settings are left as they are.
+pub fn restore(id: &str) -> Result<Value, String> {
+    let _guard = REPO.lock().unwrap_or_else(|e| e.into_inner());
+    let repo = Repository::open(storage::data_path()).map_err(|_| "version history is empty".to_string())?;
+    let target = repo
+        .revparse_single(id)
+        .and_then(|o| o.peel_to_commit())
+        .map_err(|_| format!("version not found: {}", id))?;
+    let tree = target.tree().map_err(|e| e.to_string())?;
+    let text = if let Some(text) = blob_text(&repo, &tree, "data.json") {
+        text
+    } else if let Some(entry) = tree.get_name("data.enc") {
+        let blob = entry.to_object(&repo).and_then(|o| o.peel_to_blob()).map_err(|e| e.to_string())?;
+        encryption::open_raw(blob.content())
+            .map_err(|e| format!("can't decrypt this version with the current passphrase: {}", e))?
+    } else {
+        return Err("this version has no task data".to_string());
+    };
+    let data: Value = serde_json::from_str(&text).map_err(|e| format!("version is not valid JSON: {}", e))?;
+
+    commit(None)?;
+    encryption::write_raw(&text)?;
+    search::refresh(&data);
+    let when = Local
+        .timestamp_opt(target.time().seconds(), 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+    commit(Some(&format!("Restored version from {}\n\nRestored {}", when, target.id())))?;
+    Ok(data)
+}
+
+/// Delete the repository. Encrypting the data calls this, as older commits
+/// would keep it in plain text; history starts over from the encrypted file.
+pub fn purge() -> Result<(), String> {
+    let _guard = REPO.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = storage::data_path();
+    match fs::remove_dir_all(dir.join(".git")) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("can't delete the version history: {}", e)),
+    }
+    let _ = fs::remove_file(dir.join(".gitignore"));
+    Ok(())
+}
+
+/// Modification time and size of each watched file.
+fn fingerprint(dir: &Path) -> Vec<Option<(SystemTime, u64)>> {
+    WATCHED
+        .iter()
+        .map(|name| {
+            let meta = fs::metadata(dir.join(name)).ok()?;
+            Some((meta.modified().ok()?, meta.len()))
+        })
+        .collect()
+}
+
+/// Commit changes once they've been quiet for the configured period.
+pub fn start_watcher() {
+    thread::spawn(|| {
+        let mut committed = None;
+        loop {
+            let dir = storage::data_path();
+            let current = fingerprint(&dir);
+            if committed.as_ref() != Some(&current) {
+                let newest = current.iter().flatten().map(|(t, _)| *t).max();
+                let quiet_for = newest.and_then(|t| t.elapsed().ok()).unwrap_or_default();
+                let history = settings::load().history;
+                if !history.enabled {
+                    committed = Some(current);
+                } else if quiet_for >= Duration::from_secs(history.quiet_seconds) {
+                    let _guard = REPO.lock().unwrap_or_else(|e| e.into_inner());
+                    if let Err(e) = commit(None) {
+                        eprintln!("Version history: {}", e);
+                    }
+                    // On failure, tried again after the next change
+                    committed = Some(current);
+                }
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn list_history(limit: Option<usize>) -> Result<Vec<HistoryEntry>, String> {
+    log(limit.unwrap_or(100))
+}
+
+#[tauri::command]
+pub fn restore_history(app: AppHandle, id: String) -> Result<(), String> {
+    storage::ensure_unlocked()?;
+    restore(&id)?;
+    crate::tray::refresh(&app);
+    let _ = app.emit("data-changed", ());
+    Ok(())
+}
@@ -0,0 +1,674 @@
+//! Calendar events as scheduled tasks, kept in sync with local `.ics` files.
+//!
+//! Each entry in `settings.calendars` names a file (or a directory of them), a
+//! target project and optional title/calendar filters. Events in the next
+//! `lookaheadDays` become tasks scheduled on the event's day, tagged with a
+//! `TaskSource` holding the event UID. Re-syncing updates or archives those
+//! tasks as the calendar changes, unless the user has edited them since.
+//!
+//! Supported recurrence: DAILY/WEEKLY/MONTHLY/YEARLY with INTERVAL, COUNT,
+//! UNTIL, weekly BYDAY, EXDATE and RECURRENCE-ID overrides. TZID times are
+//! read as local time.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use chrono::{Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter};
+
+use crate::model::{Task, TaskSource, TaskStatus};
+use crate::settings::{self, Settings};
+use crate::{parser, storage, tasks};
+
+const SOURCE_KIND: &str = "ics";
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// Re-sync even without changes so the lookahead window moves forward.
+const RESYNC_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const MAX_OCCURRENCES: usize = 5000;
+/// How far ahead moved or removed events are tracked.
+const HORIZON_DAYS: u64 = 2 * 366;
+
+/// One entry of `settings.calendars`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarImport {
+    /// An `.ics` file, or a directory whose `.ics` files are all read.
+    pub path: String,
+    /// Project slug for the tasks (inbox if it doesn't exist).
+    #[serde(default = "default_project")]
+    pub project: String,
+    /// `|`-separated, case-insensitive substrings of the event title; empty
+    /// matches every event.
+    #[serde(default)]
+    pub title_pattern: String,
+    /// Only events from the calendar with this name (`X-WR-CALNAME`).
+    #[serde(default)]
+    pub calendar: String,
+    #[serde(default = "default_lookahead")]
+    pub lookahead_days: u32,
+}
+
+fn default_project() -> String {
+    "inbox".to_string()
+}
+
+fn default_lookahead() -> u32 {
+    14
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub created: usize,
+    pub updated: usize,
+    pub archived: usize,
+    /// Changed events whose task the user has edited, left alone.
+    pub skipped_edited: usize,
+    pub errors: Vec<String>,
+}
+
+impl SyncReport {
+    fn changed(&self) -> bool {
+        self.created + self.updated + self.archived > 0
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Event {
+    uid: String,
+    summary: String,
+    description: Option<String>,
+    location: Option<String>,
+    start: Option<(NaiveDate, Option<NaiveTime>)>,
+    calendar: Option<String>,
+    cancelled: bool,
+    rrule: Option<String>,
+    exdates: Vec<NaiveDate>,
+    recurrence_id: Option<NaiveDate>,
+}
+
+/// A single dated instance of an event within the sync window.
+struct Occurrence<'a> {
+    key: String,
+    event: &'a Event,
+    date: NaiveDate,
+    time: Option<NaiveTime>,
+}
+
+/// Calendars with a path set (one may be half-filled in the settings).
+pub fn configured(settings: &Settings) -> Vec<CalendarImport> {
+    settings
+        .calendars
+        .iter()
+        .filter(|c| !c.path.trim().is_empty())
+        .cloned()
+        .collect()
+}
+
+/// Sync each of `sources` into `data`.
+pub fn sync_all(data: &mut Value, sources: &[CalendarImport], today: NaiveDate) -> SyncReport {
+    let mut report = SyncReport::default();
+    for source in sources {
+        sync(data, source, today, &mut report);
+    }
+    report
+}
+
+fn sync(data: &mut Value, source: &CalendarImport, today: NaiveDate, report: &mut SyncReport) {
+    let events = match read_events(&storage::expand_home(&source.path)) {
+        Ok(events) => events,
+        Err(e) => {
+            // Never archive anything on a read error
+            report.errors.push(format!("{}: {}", source.path, e));
+            return;
+        }
+    };
+
+    // Updates follow events anywhere ahead; only new tasks wait for the lookahead window
+    let window_end = today + Days::new(source.lookahead_days as u64);
+    let horizon = today + Days::new(HORIZON_DAYS);
+    let upcoming = occurrences(&events, today, horizon)
+        .into_iter()
+        .filter(|o| matches(source, o.event))
+        .collect::<Vec<_>>();
+
+    let existing: HashMap<String, Task> = data
+        .get("tasks")
+        .and_then(|t| t.as_object())
+        .map(|t| {
+            t.values()
+                .filter_map(|v| serde_json::from_value::<Task>(v.clone()).ok())
+                .filter_map(|task| {
+                    let s = task.source.clone()?;
+                    (s.kind == SOURCE_KIND && s.origin == source.path).then_some((s.uid, task))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let project_id = tasks::project_id_for_slug(data, &source.project);
+
+    for occurrence in &upcoming {
+        let content = match occurrence.event.summary.trim() {
+            "" => "(untitled event)".to_string(),
+            summary => summary.to_string(),
+        };
+        let scheduled = parser::end_of_day_millis(occurrence.date);
+        let notes = event_notes(occurrence);
+        let fingerprint = fingerprint(&content, scheduled, notes.as_deref());
+
+        match existing.get(&occurrence.key) {
+            None if occurrence.date > window_end => {}
+            None => {
+                let task = Task {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    content,
+                    completed: false,
+                    status: TaskStatus::Todo,
+                    archived: false,
+                    project_id: project_id.clone(),
+                    parent_id: None,
+                    indent: 0,
+                    created_at: Utc::now().timestamp_millis(),
+                    completed_at: None,
+                    started_at: None,
+                    due_at: None,
+                    scheduled,
+                    notes,
+                    recurrence: None,
+                    source: Some(TaskSource {
+                        kind: SOURCE_KIND.to_string(),
+                        uid: occurrence.key.clone(),
+                        origin: source.path.clone(),
+                        fingerprint,
+                    }),
+                };
+                match tasks::insert_task(data, &task) {
+                    Ok(_) => report.created += 1,
+                    Err(e) => report.errors.push(format!("{}: {}", source.path, e)),
+                }
+            }
+            Some(task) => {
+                let stored = task.source.as_ref().map(|s| s.fingerprint.as_str()).unwrap_or("");
+                if stored == fingerprint {
+                    continue;
+                }
+                if !is_unedited(task) {
+                    report.skipped_edited += 1;
+                    continue;
+                }
+                if !task.is_pending() {
+                    continue;
+                }
+                let value = &mut data["tasks"][&task.id];
+                value["content"] = json!(content);
+                value["scheduled"] = json!(scheduled);
+                value["notes"] = json!(notes);
+                value["source"]["fingerprint"] = json!(fingerprint);
+                report.updated += 1;
+            }
+        }
+    }
+
+    // Upcoming events that were removed or cancelled: archive their tasks
+    let upcoming_keys: HashSet<&str> = upcoming.iter().map(|o| o.key.as_str()).collect();
+    let today_end = parser::end_of_day_millis(today).unwrap_or(i64::MIN);
+    for (uid, task) in &existing {
+        let upcoming_task = task.scheduled.is_some_and(|s| s >= today_end);
+        if !upcoming_keys.contains(uid.as_str()) && upcoming_task && task.is_pending() && is_unedited(task) {
+            data["tasks"][&task.id]["archived"] = json!(true);
+            report.archived += 1;
+        }
+    }
+}
+
+fn matches(source: &CalendarImport, event: &Event) -> bool {
+    let calendar = source.calendar.trim();
+    if !calendar.is_empty()
+        && !event.calendar.as_deref().is_some_and(|c| c.eq_ignore_ascii_case(calendar))
+    {
+        return false;
+    }
+    let patterns: Vec<String> = source
+        .title_pattern
+        .split('|')
+        .map(|p| p.trim().to_lowercase())
+        .filter(|p| !p.is_empty())
+        .collect();
+    let title = event.summary.to_lowercase();
+    patterns.is_empty() || patterns.iter().any(|p| title.contains(p.as_str()))
+}
+
+fn event_notes(occurrence: &Occurrence) -> Option<String> {
+    let mut lines = Vec::new();
+    let when = occurrence.time.map(|t| t.format("%H:%M").to_string());
+    let location = occurrence.event.location.clone().filter(|l| !l.trim().is_empty());
+    match (when, location) {
+        (Some(when), Some(location)) => lines.push(format!("{} · {}", when, location)),
+        (Some(when), None) => lines.push(when),
+        (None, Some(location)) => lines.push(location),
+        (None, None) => {}
+    }
+    if let Some(description) = occurrence.event.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        lines.push(description.trim().to_string());
+    }
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+fn is_unedited(task: &Task) -> bool {
+    let current = fingerprint(&task.content, task.scheduled, task.notes.as_deref());
+    task.source.as_ref().is_some_and(|s| s.fingerprint == current)
+}
+
+/// FNV-1a over the synced fields; stable across builds, unlike `DefaultHasher`.
+fn fingerprint(content: &str, scheduled: Option<i64>, notes: Option<&str>) -> String {
+    let input = format!("{}\u{0}{:?}\u{0}{}", content, scheduled, notes.unwrap_or(""));
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in input.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+fn calendar_files(path: &Path) -> Result<Vec<PathBuf>, String> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(path)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e.eq_ignore_ascii_case("ics")))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn read_events(path: &Path) -> Result<Vec<Event>, String> {
+    let mut events = Vec::new();
+    for file in calendar_files(path)? {
+        let text = fs::read_to_string(&file).map_err(|e| format!("{}: {}", file.display(), e))?;
+        events.extend(parse_events(&text));
+    }
+    Ok(events)
+}
+
+/// Undo RFC 5545 line folding.
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match line.strip_prefix([' ', '\t']) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Split `NAME;PARAM=x:value` at the first colon outside a quoted parameter.
+fn split_property(line: &str) -> Option<(&str, &str)> {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ':' if !quoted => return Some((&line[..i], &line[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n' | 'N') => out.push('\n'),
+                Some(other) => out.push(other),
+                None => {}
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// `20250120` (all day), `20250120T100000Z` (UTC) or `20250120T100000`
+/// (floating or TZID, read as local time).
+fn parse_date_time(value: &str) -> Option<(NaiveDate, Option<NaiveTime>)> {
+    let value = value.trim();
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        let local = Utc.from_utc_datetime(&naive).with_timezone(&Local);
+        return Some((local.date_naive(), Some(local.time())));
+    }
+    if let Ok(naive) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        return Some((naive.date(), Some(naive.time())));
+    }
+    NaiveDate::parse_from_str(value, "%Y%m%d").ok().map(|d| (d, None))
+}
+
+fn parse_events(text: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut calendar = None;
+    let mut current: Option<Event> = None;
+    // Components nested in an event (VALARM)
+    let mut nested = 0;
+
+    for line in unfold(text) {
+        let Some((name, value)) = split_property(&line) else { continue };
+        let name = name.split(';').next().unwrap_or("").to_uppercase();
+
+        match name.as_str() {
+            "BEGIN" if value.eq_ignore_ascii_case("VEVENT") && current.is_none() => {
+                current = Some(Event::default());
+                continue;
+            }
+            "END" if value.eq_ignore_ascii_case("VEVENT") && nested == 0 => {
+                if let Some(event) = current.take().filter(|e| !e.uid.is_empty() && e.start.is_some()) {
+                    events.push(event);
+                }
+                continue;
+            }
+            "BEGIN" if current.is_some() => nested += 1,
+            "END" if nested > 0 => nested -= 1,
+            _ => {}
+        }
+        if nested > 0 {
+            continue;
+        }
+
+        let Some(event) = current.as_mut() else {
+            if name == "X-WR-CALNAME" {
+                calendar = Some(unescape(value));
+            }
+            continue;
+        };
+        match name.as_str() {
+            "UID" => event.uid = value.trim().to_string(),
+            "SUMMARY" => event.summary = unescape(value),
+            "DESCRIPTION" => event.description = Some(unescape(value)),
+            "LOCATION" => event.location = Some(unescape(value)),
+            "DTSTART" => event.start = parse_date_time(value),
+            "STATUS" => event.cancelled = value.trim().eq_ignore_ascii_case("CANCELLED"),
+            "RRULE" => event.rrule = Some(value.trim().to_uppercase()),
+            "EXDATE" => event
+                .exdates
+                .extend(value.split(',').filter_map(parse_date_time).map(|(d, _)| d)),
+            "RECURRENCE-ID" => event.recurrence_id = parse_date_time(value).map(|(d, _)| d),
+            _ => {}
+        }
+    }
+
+    // X-WR-CALNAME may come after the events
+    for event in &mut events {
+        event.calendar = calendar.clone();
+    }
+    events
+}
+
+/// Instances of `events` dated within `[from, until]`, keyed by UID (plus
+/// the original date for recurring events).
+fn occurrences(events: &[Event], from: NaiveDate, until: NaiveDate) -> Vec<Occurrence<'_>> {
+    let overridden: HashSet<(&str, NaiveDate)> = events
+        .iter()
+        .filter_map(|e| Some((e.uid.as_str(), e.recurrence_id?)))
+        .collect();
+
+    let mut out = Vec::new();
+    for event in events {
+        let Some((start, time)) = event.start else { continue };
+        if let Some(original) = event.recurrence_id {
+            if !event.cancelled && start >= from && start <= until {
+                out.push(Occurrence { key: recurring_key(&event.uid, original), event, date: start, time });
+            }
+            continue;
+        }
+        if event.cancelled {
+            continue;
+        }
+        match &event.rrule {
+            None => {
+                if start >= from && start <= until {
+                    out.push(Occurrence { key: event.uid.clone(), event, date: start, time });
+                }
+            }
+            Some(rule) => {
+                for date in expand_rrule(rule, start, until) {
+                    if date >= from
+                        && !event.exdates.contains(&date)
+                        && !overridden.contains(&(event.uid.as_str(), date))
+                    {
+                        out.push(Occurrence { key: recurring_key(&event.uid, date), event, date, time });
+                    }
+                }
+            }
+        }
+    }
+    out.sort_by_key(|o| (o.date, o.time));
+    out
+}
+
+fn recurring_key(uid: &str, date: NaiveDate) -> String {
+    format!("{}/{}", uid, date.format("%Y%m%d"))
+}
+
+/// Dates of a recurrence rule from `start` up to `until`. Rules this doesn't
+/// understand yield only `start`.
+fn expand_rrule(rule: &str, start: NaiveDate, until: NaiveDate) -> Vec<NaiveDate> {
+    let parts: HashMap<&str, &str> = rule.split(';').filter_map(|p| p.split_once('=')).collect();
+    let interval = parts.get("INTERVAL").and_then(|i| i.parse::<u32>().ok()).unwrap_or(1).max(1);
+    let count = parts.get("COUNT").and_then(|c| c.parse::<usize>().ok());
+    let rule_until = parts.get("UNTIL").and_then(|u| parse_date_time(u)).map(|(d, _)| d);
+    let end = rule_until.map_or(until, |u| u.min(until));
+    let limit = count.unwrap_or(MAX_OCCURRENCES).min(MAX_OCCURRENCES);
+
+    let unsupported = ["BYMONTHDAY", "BYSETPOS", "BYMONTH", "BYYEARDAY", "BYWEEKNO", "BYHOUR"]
+        .iter()
+        .any(|k| parts.contains_key(k));
+    let freq = parts.get("FREQ").copied().unwrap_or("");
+    if unsupported || (parts.contains_key("BYDAY") && freq != "WEEKLY") {
+        return vec![start];
+    }
+
+    let mut dates = Vec::new();
+    match freq {
+        "DAILY" | "MONTHLY" | "YEARLY" => {
+            for n in 0..limit {
+                let step = n as u32 * interval;
+                let date = match freq {
+                    "DAILY" => start.checked_add_days(Days::new(step as u64)),
+                    "MONTHLY" => start.checked_add_months(Months::new(step)),
+                    _ => start.checked_add_months(Months::new(step * 12)),
+                };
+                match date {
+                    Some(date) if date <= end => dates.push(date),
+                    _ => break,
+                }
+            }
+        }
+        "WEEKLY" => {
+            const CODES: [&str; 7] = ["MO", "TU", "WE", "TH", "FR", "SA", "SU"];
+            let mut days: Vec<u64> = parts
+                .get("BYDAY")
+                .map(|d| {
+                    d.split(',')
+                        .filter_map(|code| CODES.iter().position(|c| *c == code.trim()))
+                        .map(|i| i as u64)
+                        .collect()
+                })
+                .unwrap_or_default();
+            if days.is_empty() {
+                days.push(start.weekday().num_days_from_monday() as u64);
+            }
+            days.sort_unstable();
+
+            let week_start = start - Days::new(start.weekday().num_days_from_monday() as u64);
+            'weeks: for week in 0.. {
+                let Some(monday) = week_start.checked_add_days(Days::new(week * 7 * interval as u64)) else { break };
+                for day in &days {
+                    let date = monday + Days::new(*day);
+                    if date < start {
+                        continue;
+                    }
+                    if date > end || dates.len() >= limit {
+                        break 'weeks;
+                    }
+                    dates.push(date);
+                }
+            }
+        }
+        _ => dates.push(start),
+    }
+    dates
+}
+
+/// Load, sync and save as one update, notifying the webview when tasks changed.
+pub fn run_sync(app_handle: &Option<AppHandle>) -> SyncReport {
+    let sources = configured(&settings::load());
+    let today = Local::now().date_naive();
+    let report = storage::update_json(|data| Ok(sync_all(data, &sources, today)))
+        .unwrap_or_else(|e| SyncReport { errors: vec![e], ..Default::default() });
+    if report.changed() {
+        if let Some(app) = app_handle {
+            let _ = app.emit("data-changed", ());
+        }
+    }
+    for error in &report.errors {
+        eprintln!("Calendar sync: {}", error);
+    }
+    report
+}
+
+#[tauri::command]
+pub fn sync_calendars(app: AppHandle) -> SyncReport {
+    run_sync(&Some(app))
+}
+
+/// What a sync depends on: the settings, the files' sizes and modification
+/// times, and the date.
+fn signature(sources: &[CalendarImport]) -> String {
+    let mut parts = vec![
+        serde_json::to_string(sources).unwrap_or_default(),
+        Local::now().date_naive().to_string(),
+    ];
+    for source in sources {
+        for file in calendar_files(&storage::expand_home(&source.path)).unwrap_or_default() {
+            let meta = fs::metadata(&file).ok();
+            let modified = meta
+                .as_ref()
+                .and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_millis());
+            parts.push(format!("{}:{}:{}", file.display(), modified, meta.map_or(0, |m| m.len())));
+        }
+    }
+    parts.join("\n")
+}
+
+/// Poll the configured calendars and re-sync when they (or the settings)
+/// change, and at least hourly.
+pub fn start_watcher(app_handle: Option<AppHandle>) {
+    thread::spawn(move || {
+        let mut last_signature = String::new();
+        let mut last_sync: Option<Instant> = None;
+        loop {
+            let sources = configured(&settings::load());
+            if !sources.is_empty() && storage::ensure_unlocked().is_ok() {
+                let current = signature(&sources);
+                if current != last_signature || last_sync.is_none_or(|t| t.elapsed() >= RESYNC_INTERVAL) {
+                    run_sync(&app_handle);
+                    last_signature = current;
+                    last_sync = Some(Instant::now());
+                }
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn expands_rrules() {
+        let until = date(2027, 12, 31);
+        // Starts on a Thursday
+        assert_eq!(
+            expand_rrule("FREQ=WEEKLY;BYDAY=MO,WE;COUNT=5", date(2026, 10, 1), until),
+            [date(2026, 10, 5), date(2026, 10, 7), date(2026, 10, 12), date(2026, 10, 14), date(2026, 10, 19)]
+        );
+        assert_eq!(
+            expand_rrule("FREQ=WEEKLY;INTERVAL=2", date(2026, 10, 1), date(2026, 10, 31)),
+            [date(2026, 10, 1), date(2026, 10, 15), date(2026, 10, 29)]
+        );
+        assert_eq!(
+            expand_rrule("FREQ=DAILY;INTERVAL=3;UNTIL=20261010T000000Z", date(2026, 10, 1), until),
+            [date(2026, 10, 1), date(2026, 10, 4), date(2026, 10, 7), date(2026, 10, 10)]
+        );
+        assert_eq!(
+            expand_rrule("FREQ=MONTHLY;COUNT=3", date(2026, 10, 15), until),
+            [date(2026, 10, 15), date(2026, 11, 15), date(2026, 12, 15)]
+        );
+        assert_eq!(expand_rrule("FREQ=YEARLY", date(2026, 2, 1), until), [date(2026, 2, 1), date(2027, 2, 1)]);
+        // Rules it can't follow keep just the first date
+        assert_eq!(expand_rrule("FREQ=MONTHLY;BYMONTHDAY=1,15", date(2026, 10, 1), until), [date(2026, 10, 1)]);
+        assert_eq!(expand_rrule("FREQ=DAILY;BYDAY=MO", date(2026, 10, 1), until), [date(2026, 10, 1)]);
+    }
+
+    const ICS: &str = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n\
+        BEGIN:VEVENT\r\nUID:review\r\nSUMMARY:Design review\\, v2\r\nDTSTART:20261020T090000Z\r\n\
+        DESCRIPTION:Bring slides\r\nEND:VEVENT\r\n\
+        BEGIN:VEVENT\r\nUID:standup\r\nSUMMARY:Standup\r\nDTSTART;TZID=Europe/Berlin:20261019T093000\r\n\
+        RRULE:FREQ=WEEKLY;BYDAY=MO,WE;COUNT=6\r\nEXDATE;TZID=Europe/Berlin:20261021T093000\r\nEND:VEVENT\r\n\
+        BEGIN:VEVENT\r\nUID:standup\r\nRECURRENCE-ID;TZID=Europe/Berlin:20261026T093000\r\n\
+        SUMMARY:Standup (moved)\r\nDTSTART;TZID=Europe/Berlin:20261027T100000\r\nEND:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    #[test]
+    fn resync_is_idempotent() {
+        let dir = std::env::temp_dir().join(format!("visor-ics-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("work.ics"), ICS).unwrap();
+        let sources = vec![CalendarImport {
+            path: dir.display().to_string(),
+            project: "inbox".to_string(),
+            title_pattern: String::new(),
+            calendar: String::new(),
+            lookahead_days: 14,
+        }];
+        let mut data = json!({
+            "projects": {"inbox": {"id": "inbox", "name": "Inbox", "slug": "inbox", "taskOrder": [], "isInbox": true}},
+            "tasks": {}
+        });
+        let today = date(2026, 10, 18);
+
+        let first = sync_all(&mut data, &sources, today);
+        assert!(first.errors.is_empty(), "{:?}", first.errors);
+        // The review and the standups of the 19th, 27th (moved) and 28th: the
+        // 21st is excluded and November is past the lookahead
+        assert_eq!(first.created, 4);
+        let synced = data.clone();
+
+        let again = sync_all(&mut data, &sources, today);
+        assert!(!again.changed() && again.errors.is_empty(), "{:?}", again);
+        assert_eq!(data, synced);
+
+        let mut contents: Vec<&str> =
+            data["tasks"].as_object().unwrap().values().filter_map(|t| t["content"].as_str()).collect();
+        contents.sort_unstable();
+        assert_eq!(contents, ["Design review, v2", "Standup", "Standup", "Standup (moved)"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
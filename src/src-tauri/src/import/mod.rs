@@ -0,0 +1,467 @@
+//! Import tasks from other apps. Each format has a parser producing
+//! `ImportedTask`s; `import` maps them onto Visor projects and tasks, skipping
+//! duplicates, and can run as a dry run to preview the result.
+
+pub mod ics;
+mod taskwarrior;
+mod org;
+mod todoist;
+mod todotxt;
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter};
+
+use crate::model::{Recurrence, RecurrenceType, Task, TaskStatus};
+use crate::{parser, storage, tasks};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ImportFormat {
+    TodoistJson,
+    TodoistCsv,
+    Taskwarrior,
+    TodoTxt,
+    Org,
+}
+
+/// Body of `POST /api/import` and arguments of the `import_tasks` command.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRequest {
+    pub format: ImportFormat,
+    /// The exported file's contents.
+    pub content: String,
+    /// Project for tasks the source doesn't assign one (default: inbox).
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    High,
+    Medium,
+    Low,
+}
+
+impl Priority {
+    fn label(&self) -> &'static str {
+        match self {
+            Priority::High => "high",
+            Priority::Medium => "medium",
+            Priority::Low => "low",
+        }
+    }
+}
+
+/// A task as read from the source, before it's mapped onto Visor.
+#[derive(Debug, Clone, Default)]
+pub struct ImportedTask {
+    /// Visor id to keep, from sources that carry one (org exports).
+    pub id: Option<String>,
+    /// Source id, used to resolve `parent_key`.
+    pub key: Option<String>,
+    pub parent_key: Option<String>,
+    pub content: String,
+    /// Project name in the source.
+    pub project: Option<String>,
+    pub status: TaskStatus,
+    pub archived: bool,
+    pub priority: Option<Priority>,
+    pub created_at: Option<i64>,
+    pub completed_at: Option<i64>,
+    pub due_at: Option<i64>,
+    pub scheduled: Option<i64>,
+    pub recurrence: Option<Recurrence>,
+    /// Description, annotations, labels...; joined into the task's notes.
+    pub notes: Vec<String>,
+}
+
+/// Project details from sources that carry them (org exports), used when the
+/// project has to be created.
+#[derive(Debug, Clone, Default)]
+pub struct ImportedProject {
+    pub name: String,
+    pub id: Option<String>,
+    pub slug: Option<String>,
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct ParsedImport {
+    pub tasks: Vec<ImportedTask>,
+    pub projects: Vec<ImportedProject>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub dry_run: bool,
+    /// Tasks created (or that would be, in a dry run).
+    pub tasks: Vec<Value>,
+    pub projects_created: Vec<String>,
+    /// Content of tasks skipped because they already exist.
+    pub duplicates: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+pub fn parse(format: ImportFormat, content: &str) -> Result<ParsedImport, String> {
+    match format {
+        ImportFormat::TodoistJson => todoist::parse_json(content),
+        ImportFormat::TodoistCsv => todoist::parse_csv(content),
+        ImportFormat::Taskwarrior => taskwarrior::parse(content),
+        ImportFormat::TodoTxt => Ok(todotxt::parse(content)),
+        ImportFormat::Org => Ok(org::parse(content)),
+    }
+}
+
+/// Parse `request.content` and add its tasks to `data` (left untouched for a
+/// dry run). A task is a duplicate when its project already has a task with
+/// the same content, ignoring case and spacing, or, for tasks that keep their
+/// id, when that id is taken.
+pub fn import(data: &mut Value, request: &ImportRequest) -> Result<ImportReport, String> {
+    let parsed = parse(request.format, &request.content)?;
+    let mut working = data.clone();
+    let mut report = ImportReport {
+        dry_run: request.dry_run,
+        warnings: parsed.warnings,
+        ..Default::default()
+    };
+
+    let mut seen = existing_task_keys(&working);
+    let mut project_ids: HashMap<String, String> = HashMap::new();
+    // Source key -> (Visor id, indent), for children of imported or duplicate tasks
+    let mut placed: HashMap<String, (String, u32)> = HashMap::new();
+
+    for imported in in_tree_order(parsed.tasks, &mut report.warnings) {
+        let content = imported.content.trim().to_string();
+        if content.is_empty() {
+            continue;
+        }
+
+        let project_name = imported
+            .project
+            .clone()
+            .or_else(|| request.project.clone())
+            .unwrap_or_else(|| "Inbox".to_string());
+        let project_id = match project_ids.get(&project_name) {
+            Some(id) => id.clone(),
+            None => {
+                let details = parsed.projects.iter().find(|p| p.name == project_name);
+                let id = resolve_project(&mut working, &project_name, details, &mut report);
+                project_ids.insert(project_name, id.clone());
+                id
+            }
+        };
+
+        let dedupe_key = (project_id.clone(), normalize(&content));
+        let existing = match &imported.id {
+            Some(id) => working["tasks"].get(id).map(|t| {
+                let indent = t.get("indent").and_then(|i| i.as_u64()).unwrap_or(0) as u32;
+                (id.clone(), indent)
+            }),
+            None => seen.get(&dedupe_key).cloned(),
+        };
+        if let Some(existing) = existing {
+            if let Some(key) = &imported.key {
+                placed.insert(key.clone(), existing);
+            }
+            report.duplicates.push(content);
+            continue;
+        }
+
+        let parent = imported.parent_key.as_ref().and_then(|k| placed.get(k)).cloned();
+        let mut notes = imported.notes.clone();
+        if let Some(priority) = imported.priority {
+            notes.push(format!("Priority: {}", priority.label()));
+        }
+        let completed = imported.status == TaskStatus::Done;
+
+        let task = Task {
+            id: imported.id.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            content,
+            completed,
+            status: imported.status,
+            archived: imported.archived,
+            project_id: project_id.clone(),
+            parent_id: parent.as_ref().map(|(id, _)| id.clone()),
+            indent: parent.as_ref().map_or(0, |(_, indent)| indent + 1),
+            created_at: imported.created_at.unwrap_or_else(|| Utc::now().timestamp_millis()),
+            completed_at: if completed { imported.completed_at } else { None },
+            started_at: None,
+            due_at: imported.due_at,
+            scheduled: imported.scheduled,
+            notes: (!notes.is_empty()).then(|| notes.join("\n")),
+            recurrence: imported.recurrence,
+            source: None,
+        };
+
+        if let Some(key) = &imported.key {
+            placed.insert(key.clone(), (task.id.clone(), task.indent));
+        }
+        seen.insert(dedupe_key, (task.id.clone(), task.indent));
+
+        report.tasks.push(tasks::insert_task(&mut working, &task)?);
+    }
+
+    if !request.dry_run {
+        *data = working;
+    }
+    Ok(report)
+}
+
+/// Import into `data.json`, saving and refreshing the webview unless it's a dry run.
+#[tauri::command]
+pub fn import_tasks(app: AppHandle, request: ImportRequest) -> Result<ImportReport, String> {
+    storage::ensure_unlocked()?;
+    if request.dry_run {
+        return import(&mut storage::load_json(), &request);
+    }
+    crate::snapshots::take_quietly("pre-import");
+    let report = storage::update_json(|data| import(data, &request))?;
+    if !report.tasks.is_empty() {
+        let _ = app.emit("data-changed", ());
+    }
+    Ok(report)
+}
+
+fn normalize(content: &str) -> String {
+    content.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn existing_task_keys(data: &Value) -> HashMap<(String, String), (String, u32)> {
+    data.get("tasks")
+        .and_then(|t| t.as_object())
+        .map(|tasks| {
+            tasks
+                .values()
+                .filter_map(|t| serde_json::from_value::<Task>(t.clone()).ok())
+                .map(|t| ((t.project_id, normalize(&t.content)), (t.id, t.indent)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Parents before their children, otherwise in source order. Tasks whose
+/// parent isn't in the import become top-level, and so do tasks caught in a
+/// parent cycle (with a warning).
+fn in_tree_order(tasks: Vec<ImportedTask>, warnings: &mut Vec<String>) -> Vec<ImportedTask> {
+    let keys: HashSet<String> = tasks.iter().filter_map(|t| t.key.clone()).collect();
+    let mut children: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut roots = Vec::new();
+    for (i, task) in tasks.iter().enumerate() {
+        match &task.parent_key {
+            Some(parent) if keys.contains(parent) && task.key.as_ref() != Some(parent) => {
+                children.entry(parent.clone()).or_default().push(i)
+            }
+            _ => roots.push(i),
+        }
+    }
+
+    let mut order = Vec::with_capacity(tasks.len());
+    let mut visited = vec![false; tasks.len()];
+    let mut visit = |root: usize, visited: &mut Vec<bool>| {
+        let mut stack = vec![root];
+        while let Some(i) = stack.pop() {
+            if std::mem::replace(&mut visited[i], true) {
+                continue;
+            }
+            order.push(i);
+            if let Some(kids) = tasks[i].key.as_ref().and_then(|k| children.get(k)) {
+                stack.extend(kids.iter().rev());
+            }
+        }
+    };
+    for root in roots {
+        visit(root, &mut visited);
+    }
+    // Whatever is left hangs off a cycle (A's parent is B, B's is A)
+    for i in 0..tasks.len() {
+        if !visited[i] {
+            warnings.push(format!("\"{}\" is in a parent cycle; imported at the top level", tasks[i].content));
+            visit(i, &mut visited);
+        }
+    }
+
+    let mut slots: Vec<Option<ImportedTask>> = tasks.into_iter().map(Some).collect();
+    order.into_iter().filter_map(|i| slots[i].take()).collect()
+}
+
+/// Find a project by id, slug or name, creating it if needed. Returns its id.
+fn resolve_project(
+    data: &mut Value,
+    name: &str,
+    details: Option<&ImportedProject>,
+    report: &mut ImportReport,
+) -> String {
+    let known_id = details.and_then(|d| d.id.clone()).filter(|id| data["projects"].get(id).is_some());
+    if let Some(id) = known_id {
+        return id;
+    }
+    let slug = details.and_then(|d| d.slug.clone()).unwrap_or_else(|| slugify(name));
+    let existing = data
+        .get("projects")
+        .and_then(|p| p.as_object())
+        .and_then(|projects| {
+            projects.iter().find(|(_, p)| {
+                p.get("slug").and_then(|s| s.as_str()) == Some(slug.as_str())
+                    || p.get("name")
+                        .and_then(|n| n.as_str())
+                        .is_some_and(|n| n.eq_ignore_ascii_case(name))
+            })
+        })
+        .map(|(id, _)| id.clone());
+    if let Some(id) = existing {
+        return id;
+    }
+
+    let id = details
+        .and_then(|d| d.id.clone())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let color = details.and_then(|d| d.color.clone()).unwrap_or_else(|| "#83a598".to_string());
+    if data.get("projects").is_none() {
+        data["projects"] = json!({});
+    }
+    data["projects"][&id] = json!({
+        "id": id,
+        "name": name,
+        "slug": slug,
+        "color": color,
+        "taskOrder": [],
+        "createdAt": Utc::now().timestamp_millis(),
+        "isInbox": false
+    });
+    report.projects_created.push(name.to_string());
+    id
+}
+
+/// Lowercase, with runs of anything but letters and digits collapsed to `-`.
+pub(crate) fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.trim().chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() {
+        "imported".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Parse the timestamp formats used by the supported apps: RFC 3339,
+/// `YYYY-MM-DDTHH:MM:SS` (local), Taskwarrior's `YYYYMMDDTHHMMSSZ` and plain
+/// `YYYY-MM-DD` (start of day).
+pub(crate) fn parse_timestamp(value: &str) -> Option<DateTime<Local>> {
+    let value = value.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Local));
+    }
+    if let Ok(naive) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ") {
+        return Some(Utc.from_utc_datetime(&naive).with_timezone(&Local));
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+            return Local.from_local_datetime(&naive).earliest();
+        }
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    Local.from_local_datetime(&date.and_hms_opt(0, 0, 0)?).earliest()
+}
+
+pub(crate) fn parse_millis(value: &str) -> Option<i64> {
+    parse_timestamp(value).map(|dt| dt.timestamp_millis())
+}
+
+/// A due/scheduled date, normalized to Visor's end-of-day convention.
+pub(crate) fn parse_day(value: &str) -> Option<NaiveDate> {
+    parse_timestamp(value).map(|dt| dt.date_naive())
+}
+
+pub(crate) fn day_millis(date: Option<NaiveDate>) -> Option<i64> {
+    date.and_then(parser::end_of_day_millis)
+}
+
+/// Recurrence from phrases like "every day", "every weekday", "weekly" or
+/// "every monday". Weekly recurrences without a day keep `due`'s weekday.
+pub(crate) fn recurrence_from_text(text: &str, due: Option<NaiveDate>) -> Option<Recurrence> {
+    let text = text.trim().to_lowercase();
+    let word = text.strip_prefix("every ").unwrap_or(&text).trim();
+    let word = match word {
+        "daily" => "day",
+        "weekly" => "week",
+        "monthly" => "month",
+        "weekdays" | "workday" | "workdays" => "weekday",
+        w if w.len() > 3 && w.ends_with("day") && parser::parse_recurrence(&w[..3]).is_some() => &w[..3],
+        w => w,
+    };
+    let recurrence = parser::parse_recurrence(word)?;
+    Some(with_due_weekday(recurrence, due))
+}
+
+pub(crate) fn with_due_weekday(mut recurrence: Recurrence, due: Option<NaiveDate>) -> Recurrence {
+    if recurrence.kind == RecurrenceType::Weekly && recurrence.day_of_week.is_none() {
+        recurrence.day_of_week = due.map(|d| d.weekday().num_days_from_sunday());
+    }
+    recurrence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(key: &str, parent: Option<&str>) -> ImportedTask {
+        ImportedTask {
+            key: Some(key.to_string()),
+            parent_key: parent.map(String::from),
+            content: key.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn orders_parents_first_and_keeps_cycles() {
+        let mut warnings = Vec::new();
+        let tasks = vec![
+            task("child", Some("root")),
+            task("a", Some("b")),
+            task("root", None),
+            task("b", Some("a")),
+            task("c", Some("a")),
+            task("self", Some("self")),
+        ];
+        let order: Vec<String> = in_tree_order(tasks, &mut warnings).into_iter().map(|t| t.content).collect();
+        assert_eq!(order, ["root", "child", "self", "a", "b", "c"]);
+        assert_eq!(warnings, ["\"a\" is in a parent cycle; imported at the top level"]);
+    }
+
+    #[test]
+    fn imports_a_parent_cycle() {
+        let mut data = json!({"tasks": {}, "projects": {}});
+        let request = ImportRequest {
+            format: ImportFormat::TodoistJson,
+            content: r#"[{"id": "1", "parent_id": "2", "content": "A"}, {"id": "2", "parent_id": "1", "content": "B"}]"#
+                .to_string(),
+            project: None,
+            dry_run: false,
+        };
+        let report = import(&mut data, &request).unwrap();
+        assert_eq!(report.tasks.len(), 2);
+        assert_eq!(report.warnings.len(), 1);
+        let a = &report.tasks[0];
+        assert_eq!(a["content"], "A");
+        assert!(a.get("parentId").is_none_or(|p| p.is_null()));
+        assert_eq!(report.tasks[1]["parentId"], a["id"]);
+        assert_eq!(data["tasks"].as_object().unwrap().len(), 2);
+    }
+}
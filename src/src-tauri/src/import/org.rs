@@ -0,0 +1,524 @@
+//! Org-mode outlines, as written by the `org` export. Top-level headings
+//! without a TODO keyword are projects; every other heading is a task, nested
+//! under the heading above it.
+//!
+//! ```text
+//! * Work
+//! ** TODO [#A] Write report                                :ARCHIVE:
+//! DEADLINE: <2025-01-24 Fri +1w> SCHEDULED: <2025-01-22 Wed>
+//! :PROPERTIES:
+//! :ID: 3f2b...
+//! :END:
+//! Notes, as body text.
+//! ```
+//!
+//! `ID` properties keep Visor's ids, so an export imports back as the same
+//! tasks and projects.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Local, NaiveDate, NaiveTime, TimeZone};
+
+use super::{
+    day_millis, recurrence_from_text, with_due_weekday, ImportedProject, ImportedTask, ParsedImport, Priority,
+};
+use crate::model::{Recurrence, RecurrenceType, TaskStatus};
+
+const PLANNING: [&str; 3] = ["CLOSED:", "DEADLINE:", "SCHEDULED:"];
+
+/// `#+TODO:` lines (also `SEQ_TODO`/`TYP_TODO`). Visor's own statuses are
+/// always keywords; other keywords count as TODO before the `|` and DONE after.
+fn keywords(content: &str) -> HashMap<String, TaskStatus> {
+    let mut keywords: HashMap<String, TaskStatus> = [
+        TaskStatus::Todo,
+        TaskStatus::Doing,
+        TaskStatus::Waiting,
+        TaskStatus::Done,
+        TaskStatus::Cancelled,
+    ]
+    .into_iter()
+    .map(|s| (s.as_str().to_string(), s))
+    .collect();
+
+    for line in content.lines() {
+        let line = line.trim();
+        let Some((key, value)) = line.split_once(':') else { continue };
+        if !["#+TODO", "#+SEQ_TODO", "#+TYP_TODO"].iter().any(|k| k.eq_ignore_ascii_case(key)) {
+            continue;
+        }
+        let words: Vec<&str> = value.split_whitespace().collect();
+        // Without a `|`, the last keyword is the done state
+        let done_from = words.iter().position(|w| *w == "|").unwrap_or(words.len().saturating_sub(1));
+        for (i, word) in words.iter().enumerate().filter(|(_, w)| **w != "|") {
+            // `DONE(d!)`: fast-access key and logging options
+            let name = word.split('(').next().unwrap_or(word);
+            let status = match TaskStatus::parse(name).filter(|s| s.as_str() == name) {
+                Some(status) => status,
+                None if i < done_from => TaskStatus::Todo,
+                None => TaskStatus::Done,
+            };
+            keywords.entry(name.to_string()).or_insert(status);
+        }
+    }
+    keywords
+}
+
+/// `*** title` → (3, "title").
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.bytes().take_while(|b| *b == b'*').count();
+    if level == 0 {
+        return None;
+    }
+    let rest = &line[level..];
+    if rest.is_empty() {
+        return Some((level, ""));
+    }
+    rest.strip_prefix([' ', '\t']).map(|title| (level, title.trim()))
+}
+
+struct Title {
+    keyword: Option<TaskStatus>,
+    priority: Option<char>,
+    text: String,
+    tags: Vec<String>,
+}
+
+fn split_first(text: &str) -> (&str, &str) {
+    match text.split_once(' ') {
+        Some((first, rest)) => (first, rest.trim_start()),
+        None => (text, ""),
+    }
+}
+
+fn split_title(title: &str, keywords: &HashMap<String, TaskStatus>) -> Title {
+    let mut rest = title;
+    let (first, after) = split_first(rest);
+    let keyword = keywords.get(first).copied();
+    if keyword.is_some() {
+        rest = after;
+    }
+
+    let (first, after) = split_first(rest);
+    let priority = if first.len() == 4 && first.starts_with("[#") && first.ends_with(']') {
+        rest = after;
+        first.chars().nth(2)
+    } else {
+        None
+    };
+
+    let (text, tags) = split_tags(rest);
+    Title {
+        keyword,
+        priority,
+        text: text.to_string(),
+        tags,
+    }
+}
+
+/// Trailing `:tag1:tag2:`.
+fn split_tags(text: &str) -> (&str, Vec<String>) {
+    if let Some((before, last)) = text.trim_end().rsplit_once([' ', '\t']) {
+        let inner = last.strip_prefix(':').and_then(|l| l.strip_suffix(':')).unwrap_or("");
+        let is_tags = !inner.is_empty()
+            && inner
+                .split(':')
+                .all(|t| !t.is_empty() && t.chars().all(|c| c.is_alphanumeric() || "_@#%".contains(c)));
+        if is_tags {
+            return (before.trim_end(), inner.split(':').map(String::from).collect());
+        }
+    }
+    (text, Vec::new())
+}
+
+fn priority(letter: char) -> Option<Priority> {
+    match letter {
+        'A' => Some(Priority::High),
+        'B' => Some(Priority::Medium),
+        'C' => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+struct Timestamp<'a> {
+    date: NaiveDate,
+    time: Option<NaiveTime>,
+    repeater: Option<&'a str>,
+}
+
+/// The inside of `<2025-01-24 Fri 10:00-11:00 +1w -2d>`.
+fn timestamp(inner: &str) -> Option<Timestamp<'_>> {
+    let mut words = inner.split_whitespace();
+    let date = NaiveDate::parse_from_str(words.next()?, "%Y-%m-%d").ok()?;
+    let mut ts = Timestamp { date, time: None, repeater: None };
+    for word in words {
+        if word.starts_with('+') || word.starts_with(".+") {
+            ts.repeater.get_or_insert(word);
+        } else if let Ok(time) = NaiveTime::parse_from_str(word.split('-').next().unwrap_or(word), "%H:%M") {
+            ts.time.get_or_insert(time);
+        }
+    }
+    Some(ts)
+}
+
+fn timestamp_millis(ts: &Timestamp) -> Option<i64> {
+    let naive = ts.date.and_time(ts.time.unwrap_or_default());
+    Local.from_local_datetime(&naive).earliest().map(|dt| dt.timestamp_millis())
+}
+
+/// A planning line: `CLOSED: [...] DEADLINE: <...> SCHEDULED: <...>`, as
+/// (keyword without the colon, timestamp inside the brackets) pairs.
+fn planning(line: &str) -> Option<Vec<(&str, &str)>> {
+    let mut rest = line.trim();
+    if !PLANNING.iter().any(|k| rest.starts_with(k)) {
+        return None;
+    }
+    let mut entries = Vec::new();
+    while let Some(keyword) = PLANNING.iter().find(|k| rest.starts_with(**k)) {
+        let after = rest[keyword.len()..].trim_start();
+        let close = match after.chars().next() {
+            Some('<') => '>',
+            Some('[') => ']',
+            _ => break,
+        };
+        let Some(end) = after.find(close) else { break };
+        entries.push((&keyword[..keyword.len() - 1], &after[1..end]));
+        rest = after[end + 1..].trim_start();
+    }
+    Some(entries)
+}
+
+/// Repeaters Visor can represent: one day, week or month, in any of org's
+/// three repeat styles (`+`, `++`, `.+`).
+fn repeater(value: &str) -> Option<Recurrence> {
+    let value = value.trim_start_matches(['.', '+']);
+    // Habits: `.+2d/3d`
+    let kind = match value.split('/').next().unwrap_or(value) {
+        "1d" => RecurrenceType::Daily,
+        "1w" | "7d" => RecurrenceType::Weekly,
+        "1m" => RecurrenceType::Monthly,
+        _ => return None,
+    };
+    Some(Recurrence { kind, day_of_week: None })
+}
+
+/// Drawers that can open a section. Other `:word:` lines are body text, like
+/// a note starting with an emoji shortcode.
+const DRAWERS: [&str; 2] = ["PROPERTIES", "LOGBOOK"];
+
+/// A `:PROPERTIES:` or `:LOGBOOK:` line opening a drawer.
+fn drawer(line: &str) -> Option<&str> {
+    let name = line.trim().strip_prefix(':')?.strip_suffix(':')?;
+    DRAWERS.iter().any(|d| d.eq_ignore_ascii_case(name)).then_some(name)
+}
+
+/// Body lines starting with `*` are written as `,*` so they don't read as headings.
+fn unescape(line: &str) -> &str {
+    match line.strip_prefix(',') {
+        Some(rest) if rest.trim_start_matches(',').starts_with('*') => rest,
+        _ => line,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Target {
+    Project(usize),
+    Task(usize),
+    Skip,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    /// Right after the heading, where the planning line goes.
+    Planning,
+    /// Property and other drawers, before any body text.
+    Drawers,
+    Body,
+}
+
+/// The heading being read and what's collected below it.
+struct Section {
+    target: Target,
+    state: State,
+    /// Inside a drawer; `true` for the property drawer.
+    drawer: Option<bool>,
+    body: Vec<String>,
+    tags: Vec<String>,
+    deadline: Option<NaiveDate>,
+    scheduled: Option<NaiveDate>,
+    /// Repeater of the deadline, or of the scheduled date without one.
+    repeater: Option<(String, NaiveDate)>,
+    recurrence: Option<String>,
+    line: usize,
+}
+
+impl Section {
+    fn new(target: Target, tags: Vec<String>, line: usize) -> Self {
+        Section {
+            target,
+            state: State::Planning,
+            drawer: None,
+            body: Vec::new(),
+            tags,
+            deadline: None,
+            scheduled: None,
+            repeater: None,
+            recurrence: None,
+            line,
+        }
+    }
+
+    fn read(&mut self, line: &str, parsed: &mut ParsedImport, ids: &mut HashSet<String>) {
+        if let Some(properties) = self.drawer {
+            if line.trim().eq_ignore_ascii_case(":END:") {
+                self.drawer = None;
+            } else if properties {
+                self.property(line, parsed, ids);
+            }
+            return;
+        }
+
+        if self.state == State::Planning {
+            self.state = State::Drawers;
+            if let Some(entries) = planning(line) {
+                for (keyword, inner) in entries {
+                    self.plan(keyword, inner, parsed);
+                }
+                return;
+            }
+        }
+        if self.state == State::Drawers {
+            if let Some(name) = drawer(line) {
+                self.drawer = Some(name.eq_ignore_ascii_case("PROPERTIES"));
+                return;
+            }
+            if !line.trim().is_empty() {
+                self.state = State::Body;
+            }
+        }
+        self.body.push(unescape(line.trim_end()).to_string());
+    }
+
+    fn plan(&mut self, keyword: &str, inner: &str, parsed: &mut ParsedImport) {
+        let Target::Task(index) = self.target else { return };
+        let Some(ts) = timestamp(inner) else {
+            parsed.warnings.push(format!("line {}: unreadable {} date \"{}\"", self.line, keyword, inner));
+            return;
+        };
+        let task = &mut parsed.tasks[index];
+        match keyword {
+            "CLOSED" => task.completed_at = timestamp_millis(&ts),
+            "DEADLINE" => {
+                task.due_at = day_millis(Some(ts.date));
+                self.deadline = Some(ts.date);
+                if let Some(r) = ts.repeater {
+                    self.repeater = Some((r.to_string(), ts.date));
+                }
+            }
+            _ => {
+                task.scheduled = day_millis(Some(ts.date));
+                self.scheduled = Some(ts.date);
+                if let Some(r) = ts.repeater {
+                    self.repeater.get_or_insert((r.to_string(), ts.date));
+                }
+            }
+        }
+    }
+
+    fn property(&mut self, line: &str, parsed: &mut ParsedImport, ids: &mut HashSet<String>) {
+        let Some((key, value)) = line.trim().strip_prefix(':').and_then(|l| l.split_once(':')) else {
+            return;
+        };
+        let key = key.to_uppercase();
+        let value = value.trim();
+        if value.is_empty() {
+            return;
+        }
+
+        match self.target {
+            Target::Project(index) => {
+                let project = &mut parsed.projects[index];
+                match key.as_str() {
+                    "ID" => project.id = Some(value.to_string()),
+                    "SLUG" => project.slug = Some(value.to_string()),
+                    "COLOR" => project.color = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+            Target::Task(index) => match key.as_str() {
+                "ID" if value.contains(char::is_whitespace) => {}
+                "ID" if !ids.insert(value.to_string()) => {
+                    parsed.warnings.push(format!("line {}: duplicate ID {}, a new one is used", self.line, value));
+                }
+                "ID" => parsed.tasks[index].id = Some(value.to_string()),
+                "CREATED" => {
+                    let inner = value.trim_start_matches(['[', '<']).trim_end_matches([']', '>']);
+                    parsed.tasks[index].created_at = timestamp(inner).as_ref().and_then(timestamp_millis);
+                }
+                "RECURRENCE" => self.recurrence = Some(value.to_string()),
+                _ => {}
+            },
+            Target::Skip => {}
+        }
+    }
+
+    /// Apply what needs the whole section: notes, tags and recurrence.
+    fn finish(self, parsed: &mut ParsedImport) {
+        let Target::Task(index) = self.target else { return };
+
+        let recurrence = if let Some(text) = &self.recurrence {
+            let rule = recurrence_from_text(text, self.deadline.or(self.scheduled));
+            if rule.is_none() {
+                parsed.warnings.push(format!("line {}: unsupported recurrence \"{}\"", self.line, text));
+            }
+            rule
+        } else if let Some((value, anchor)) = &self.repeater {
+            let rule = repeater(value).map(|r| with_due_weekday(r, Some(*anchor)));
+            if rule.is_none() {
+                parsed.warnings.push(format!("line {}: unsupported repeater \"{}\"", self.line, value));
+            }
+            rule
+        } else {
+            None
+        };
+
+        let start = self.body.iter().position(|l| !l.trim().is_empty());
+        let end = self.body.iter().rposition(|l| !l.trim().is_empty());
+        let task = &mut parsed.tasks[index];
+        task.recurrence = recurrence;
+        if let (Some(start), Some(end)) = (start, end) {
+            let lines = &self.body[start..=end];
+            // Body text is often indented to its heading
+            let indent = lines
+                .iter()
+                .filter(|l| !l.trim().is_empty())
+                .map(|l| l.len() - l.trim_start_matches([' ', '\t']).len())
+                .min()
+                .unwrap_or(0);
+            let lines: Vec<&str> = lines.iter().map(|l| l.get(indent..).unwrap_or("")).collect();
+            task.notes.push(lines.join("\n"));
+        }
+        let tags: Vec<&String> = self.tags.iter().filter(|t| t.as_str() != "ARCHIVE").collect();
+        task.archived = tags.len() < self.tags.len();
+        if !tags.is_empty() {
+            task.notes.push(format!("Tags: {}", tags.iter().map(|t| t.as_str()).collect::<Vec<_>>().join(", ")));
+        }
+    }
+}
+
+pub fn parse(content: &str) -> ParsedImport {
+    let keywords = keywords(content);
+    let mut parsed = ParsedImport::default();
+    let mut project: Option<String> = None;
+    // (level, key) of the task headings enclosing the current one
+    let mut parents: Vec<(usize, String)> = Vec::new();
+    let mut ids = HashSet::new();
+    let mut section: Option<Section> = None;
+
+    for (number, line) in content.lines().enumerate() {
+        let number = number + 1;
+        let Some((level, title)) = heading(line) else {
+            if let Some(section) = section.as_mut() {
+                section.read(line, &mut parsed, &mut ids);
+            }
+            continue;
+        };
+
+        if let Some(previous) = section.take() {
+            previous.finish(&mut parsed);
+        }
+        let title = split_title(title, &keywords);
+
+        if level == 1 && title.keyword.is_none() {
+            parents.clear();
+            if title.text.is_empty() {
+                project = None;
+                section = Some(Section::new(Target::Skip, Vec::new(), number));
+                continue;
+            }
+            project = Some(title.text.clone());
+            parsed.projects.push(ImportedProject {
+                name: title.text,
+                ..Default::default()
+            });
+            section = Some(Section::new(Target::Project(parsed.projects.len() - 1), Vec::new(), number));
+            continue;
+        }
+
+        while parents.last().is_some_and(|(l, _)| *l >= level) {
+            parents.pop();
+        }
+        if title.text.is_empty() {
+            parsed.warnings.push(format!("line {}: no task text", number));
+            section = Some(Section::new(Target::Skip, Vec::new(), number));
+            continue;
+        }
+
+        let key = format!("line {}", number);
+        parsed.tasks.push(ImportedTask {
+            key: Some(key.clone()),
+            parent_key: parents.last().map(|(_, k)| k.clone()),
+            content: title.text,
+            project: project.clone(),
+            status: title.keyword.unwrap_or_default(),
+            priority: title.priority.and_then(priority),
+            ..Default::default()
+        });
+        parents.push((level, key));
+        section = Some(Section::new(Target::Task(parsed.tasks.len() - 1), title.tags, number));
+    }
+
+    if let Some(last) = section {
+        last.finish(&mut parsed);
+    }
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::super::{import, ImportFormat, ImportRequest};
+    use crate::export::{export, ExportFormat, ExportOptions};
+
+    #[test]
+    fn export_then_import_keeps_ids_and_notes() {
+        let data = json!({
+            "projects": {
+                "p1": {"id": "p1", "name": "Home", "slug": "home", "color": "#fabd2f",
+                       "taskOrder": ["t1", "t2", "t3"], "createdAt": 1}
+            },
+            "tasks": {
+                "t1": {"id": "t1", "content": "Plan party", "projectId": "p1", "createdAt": 1_700_000_000_000i64,
+                       "notes": ":tada: guests first\n* not a heading\n:END:"},
+                "t2": {"id": "t2", "content": "Invite", "projectId": "p1", "parentId": "t1", "indent": 1,
+                       "createdAt": 1_700_000_000_000i64, "status": "WAITING"},
+                "t3": {"id": "t3", "content": "Water plants", "projectId": "p1", "createdAt": 1_700_000_000_000i64,
+                       "dueAt": 1_737_763_199_000i64, "recurrence": {"type": "weekly", "dayOfWeek": 5}}
+            }
+        });
+        let options = ExportOptions { format: ExportFormat::Org, project: None, include_archived: true };
+        let content = export(&data, &options).unwrap();
+
+        let mut imported = json!({"tasks": {}, "projects": {}});
+        let request = ImportRequest { format: ImportFormat::Org, content, project: None, dry_run: false };
+        let report = import(&mut imported, &request).unwrap();
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+        assert_eq!(report.projects_created, ["Home"]);
+
+        let tasks = &imported["tasks"];
+        for id in ["t1", "t2", "t3"] {
+            assert_eq!(tasks[id]["content"], data["tasks"][id]["content"], "{}", id);
+            assert_eq!(tasks[id]["projectId"], "p1", "{}", id);
+        }
+        assert_eq!(tasks["t1"]["notes"], data["tasks"]["t1"]["notes"]);
+        assert_eq!(tasks["t2"]["parentId"], "t1");
+        assert_eq!(tasks["t2"]["status"], "WAITING");
+        assert_eq!(tasks["t3"]["recurrence"], data["tasks"]["t3"]["recurrence"]);
+        assert_eq!(imported["projects"]["p1"]["taskOrder"], json!(["t1", "t2", "t3"]));
+
+        // Importing the same export again finds every task already there
+        let report = import(&mut imported, &request).unwrap();
+        assert!(report.tasks.is_empty());
+        assert_eq!(report.duplicates.len(), 3);
+    }
+}
@@ -0,0 +1,188 @@
+//! Taskwarrior `task export` JSON.
+//!
+//! Recurring tasks export as a template (`status: recurring`) plus instances
+//! pointing at it through `parent`. Only instances are imported; pending ones
+//! keep the recurrence so Visor schedules the next occurrence itself.
+
+use serde_json::Value;
+
+use super::{
+    day_millis, parse_day, parse_millis, with_due_weekday, ImportedTask, ParsedImport, Priority,
+};
+use crate::model::{Recurrence, RecurrenceType, TaskStatus};
+
+fn priority(value: &str) -> Option<Priority> {
+    match value {
+        "H" => Some(Priority::High),
+        "M" => Some(Priority::Medium),
+        "L" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+/// `recur` values Visor can represent: the named periods and 1-unit durations.
+fn recurrence(value: &str) -> Option<Recurrence> {
+    let kind = match value.to_lowercase().as_str() {
+        "daily" | "day" | "1d" | "1day" | "p1d" => RecurrenceType::Daily,
+        "weekdays" => RecurrenceType::Weekdays,
+        "weekly" | "week" | "1w" | "1wk" | "1week" | "7d" | "p1w" | "p7d" => RecurrenceType::Weekly,
+        "monthly" | "month" | "1mo" | "1month" | "p1m" => RecurrenceType::Monthly,
+        _ => return None,
+    };
+    Some(Recurrence { kind, day_of_week: None })
+}
+
+fn string<'a>(task: &'a Value, key: &str) -> Option<&'a str> {
+    task.get(key).and_then(|v| v.as_str()).filter(|s| !s.is_empty())
+}
+
+pub fn parse(content: &str) -> Result<ParsedImport, String> {
+    // `task export` prints a JSON array; older versions print one object per line
+    let tasks: Vec<Value> = match serde_json::from_str::<Value>(content) {
+        Ok(Value::Array(tasks)) => tasks,
+        Ok(_) => return Err("Taskwarrior export must be a JSON array".to_string()),
+        Err(_) => content
+            .lines()
+            .map(|l| l.trim().trim_end_matches(','))
+            .filter(|l| !l.is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("invalid Taskwarrior export: {}", e))?,
+    };
+
+    let mut parsed = ParsedImport::default();
+    let mut deleted = 0;
+
+    for task in &tasks {
+        let Some(description) = string(task, "description") else {
+            continue;
+        };
+        let status = match string(task, "status").unwrap_or("pending") {
+            "pending" => TaskStatus::Todo,
+            "waiting" => TaskStatus::Waiting,
+            "completed" => TaskStatus::Done,
+            "recurring" => continue,
+            "deleted" => {
+                deleted += 1;
+                continue;
+            }
+            other => {
+                parsed.warnings.push(format!("unknown status \"{}\" on \"{}\"", other, description));
+                TaskStatus::Todo
+            }
+        };
+
+        let due = string(task, "due").and_then(parse_day);
+        let mut recurrence_rule = None;
+        if let Some(recur) = string(task, "recur").filter(|_| status != TaskStatus::Done) {
+            recurrence_rule = recurrence(recur).map(|r| with_due_weekday(r, due));
+            if recurrence_rule.is_none() {
+                parsed.warnings.push(format!("unsupported recurrence \"{}\" on \"{}\"", recur, description));
+            }
+        }
+
+        let mut notes: Vec<String> = task
+            .get("annotations")
+            .and_then(|a| a.as_array())
+            .map(|a| {
+                a.iter()
+                    .filter_map(|a| a.get("description").and_then(|d| d.as_str()))
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let tags: Vec<&str> = task
+            .get("tags")
+            .and_then(|t| t.as_array())
+            .map(|t| t.iter().filter_map(|t| t.as_str()).collect())
+            .unwrap_or_default();
+        if !tags.is_empty() {
+            notes.push(format!("Tags: {}", tags.join(", ")));
+        }
+
+        parsed.tasks.push(ImportedTask {
+            key: string(task, "uuid").map(String::from),
+            // `parent` is the recurrence template, not a parent task
+            parent_key: None,
+            content: description.to_string(),
+            project: string(task, "project").map(String::from),
+            status,
+            priority: string(task, "priority").and_then(priority),
+            created_at: string(task, "entry").and_then(parse_millis),
+            completed_at: string(task, "end").and_then(parse_millis),
+            due_at: day_millis(due),
+            scheduled: day_millis(string(task, "scheduled").and_then(parse_day)),
+            recurrence: recurrence_rule,
+            notes,
+            ..Default::default()
+        });
+    }
+
+    if deleted > 0 {
+        parsed.warnings.push(format!("skipped {} deleted task(s)", deleted));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn parses_an_export() {
+        let parsed = parse(
+            r#"[
+                {"uuid": "a", "description": "Water plants", "status": "pending", "recur": "weekly",
+                 "due": "20250124T120000Z", "parent": "t", "priority": "H", "project": "home",
+                 "tags": ["garden"], "annotations": [{"entry": "20250101T000000Z", "description": "both balconies"}]},
+                {"uuid": "t", "description": "Water plants", "status": "recurring", "recur": "weekly"},
+                {"uuid": "b", "description": "Old", "status": "deleted"},
+                {"uuid": "c", "description": "Taxes", "status": "completed", "recur": "monthly",
+                 "entry": "20250101T090000Z", "end": "20250110T090000Z", "priority": "L"},
+                {"uuid": "d", "description": "Reply", "status": "waiting", "scheduled": "2025-02-01"},
+                {"uuid": "e", "description": "Odd", "recur": "3d"}
+            ]"#,
+        )
+        .unwrap();
+
+        let contents: Vec<&str> = parsed.tasks.iter().map(|t| t.content.as_str()).collect();
+        assert_eq!(contents, ["Water plants", "Taxes", "Reply", "Odd"]);
+
+        let plants = &parsed.tasks[0];
+        assert_eq!(plants.key.as_deref(), Some("a"));
+        assert_eq!(plants.parent_key, None);
+        assert_eq!(plants.project.as_deref(), Some("home"));
+        assert_eq!(plants.priority, Some(Priority::High));
+        assert_eq!(plants.notes, ["both balconies", "Tags: garden"]);
+        let due = plants.due_at.unwrap();
+        assert_eq!(
+            plants.recurrence,
+            Some(with_due_weekday(Recurrence { kind: RecurrenceType::Weekly, day_of_week: None }, parse_day("20250124T120000Z")))
+        );
+        assert_eq!(Some(due), day_millis(parse_day("20250124T120000Z")));
+
+        let taxes = &parsed.tasks[1];
+        assert_eq!(taxes.status, TaskStatus::Done);
+        assert_eq!(taxes.recurrence, None);
+        assert!(taxes.created_at.is_some() && taxes.completed_at > taxes.created_at);
+
+        assert_eq!(parsed.tasks[2].status, TaskStatus::Waiting);
+        assert_eq!(parsed.tasks[2].scheduled, day_millis(NaiveDate::from_ymd_opt(2025, 2, 1)));
+
+        assert_eq!(parsed.tasks[3].recurrence, None);
+        assert_eq!(
+            parsed.warnings,
+            ["unsupported recurrence \"3d\" on \"Odd\"", "skipped 1 deleted task(s)"]
+        );
+    }
+
+    #[test]
+    fn parses_one_object_per_line() {
+        let parsed = parse("{\"description\": \"One\"},\n{\"description\": \"Two\", \"status\": \"completed\"}\n").unwrap();
+        assert_eq!(parsed.tasks.len(), 2);
+        assert_eq!(parsed.tasks[1].status, TaskStatus::Done);
+        assert!(parse("{\"description\": \"One\"}\nnot json\n").is_err());
+        assert!(parse("{\"a\": 1, \"b\": 2}").is_err());
+    }
+}
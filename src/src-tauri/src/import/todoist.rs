@@ -0,0 +1,313 @@
+//! Todoist exports: JSON from the API (`{projects, items}` from sync, or the
+//! REST task list) and the per-project CSV template export.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::{
+    day_millis, parse_day, parse_millis, recurrence_from_text, ImportedTask, ParsedImport, Priority,
+};
+use crate::model::TaskStatus;
+
+/// Todoist API priorities run the other way round: 4 is p1.
+fn api_priority(value: i64) -> Option<Priority> {
+    match value {
+        4 => Some(Priority::High),
+        3 => Some(Priority::Medium),
+        2 => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+/// CSV priorities match the app: 1 is p1.
+fn csv_priority(value: &str) -> Option<Priority> {
+    match value.trim() {
+        "1" => Some(Priority::High),
+        "2" => Some(Priority::Medium),
+        "3" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+fn id_string(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn truthy(value: Option<&Value>) -> bool {
+    match value {
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Number(n)) => n.as_i64().unwrap_or(0) != 0,
+        _ => false,
+    }
+}
+
+pub fn parse_json(content: &str) -> Result<ParsedImport, String> {
+    let root: Value = serde_json::from_str(content).map_err(|e| format!("invalid Todoist JSON: {}", e))?;
+    let (projects, items) = match &root {
+        Value::Array(items) => (Vec::new(), items.clone()),
+        Value::Object(obj) => {
+            let items = obj
+                .get("items")
+                .or_else(|| obj.get("tasks"))
+                .and_then(|i| i.as_array())
+                .cloned()
+                .ok_or("Todoist JSON has no items or tasks")?;
+            let projects = obj.get("projects").and_then(|p| p.as_array()).cloned().unwrap_or_default();
+            (projects, items)
+        }
+        _ => return Err("Todoist JSON must be an object or an array of tasks".to_string()),
+    };
+
+    let project_names: HashMap<String, String> = projects
+        .iter()
+        .filter_map(|p| Some((id_string(p.get("id"))?, p.get("name")?.as_str()?.to_string())))
+        .collect();
+
+    let mut parsed = ParsedImport::default();
+    for item in &items {
+        if truthy(item.get("is_deleted")) {
+            continue;
+        }
+        let Some(content) = item.get("content").and_then(|c| c.as_str()) else {
+            parsed.warnings.push("skipped an item without content".to_string());
+            continue;
+        };
+
+        let completed = truthy(item.get("checked")) || truthy(item.get("is_completed"));
+        let due = item.get("due").filter(|d| d.is_object());
+        let due_date = due.and_then(|d| d.get("date")).and_then(|d| d.as_str()).and_then(parse_day);
+
+        let mut recurrence = None;
+        if due.is_some_and(|d| truthy(d.get("is_recurring"))) {
+            let rule = due.and_then(|d| d.get("string")).and_then(|s| s.as_str()).unwrap_or("");
+            recurrence = recurrence_from_text(rule, due_date);
+            if recurrence.is_none() {
+                parsed.warnings.push(format!("unsupported recurrence \"{}\" on \"{}\"", rule, content));
+            }
+        }
+
+        let mut notes = Vec::new();
+        if let Some(description) = item.get("description").and_then(|d| d.as_str()).filter(|d| !d.trim().is_empty()) {
+            notes.push(description.to_string());
+        }
+        let labels: Vec<&str> = item
+            .get("labels")
+            .and_then(|l| l.as_array())
+            .map(|l| l.iter().filter_map(|l| l.as_str()).collect())
+            .unwrap_or_default();
+        if !labels.is_empty() {
+            notes.push(format!("Labels: {}", labels.join(", ")));
+        }
+
+        let project = id_string(item.get("project_id")).and_then(|id| project_names.get(&id).cloned());
+
+        parsed.tasks.push(ImportedTask {
+            key: id_string(item.get("id")),
+            parent_key: id_string(item.get("parent_id")),
+            content: content.to_string(),
+            project,
+            status: if completed { TaskStatus::Done } else { TaskStatus::Todo },
+            priority: item.get("priority").and_then(|p| p.as_i64()).and_then(api_priority),
+            created_at: item
+                .get("added_at")
+                .or_else(|| item.get("created_at"))
+                .and_then(|c| c.as_str())
+                .and_then(parse_millis),
+            completed_at: item.get("completed_at").and_then(|c| c.as_str()).and_then(parse_millis),
+            due_at: day_millis(due_date),
+            scheduled: None,
+            recurrence,
+            notes,
+            ..Default::default()
+        });
+    }
+    Ok(parsed)
+}
+
+pub fn parse_csv(content: &str) -> Result<ParsedImport, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(content.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| format!("invalid Todoist CSV: {}", e))?
+        .iter()
+        .map(|h| h.trim().to_uppercase())
+        .collect();
+    let column = |name: &str| headers.iter().position(|h| h == name);
+    let (Some(type_col), Some(content_col)) = (column("TYPE"), column("CONTENT")) else {
+        return Err("Todoist CSV needs TYPE and CONTENT columns".to_string());
+    };
+    let description_col = column("DESCRIPTION");
+    let priority_col = column("PRIORITY");
+    let indent_col = column("INDENT");
+    let date_col = column("DATE");
+
+    let mut parsed = ParsedImport::default();
+    // Keys of the most recent task at each indent level
+    let mut ancestors: Vec<String> = Vec::new();
+
+    for (row, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| format!("invalid Todoist CSV: {}", e))?;
+        let field = |col: Option<usize>| col.and_then(|c| record.get(c)).unwrap_or("").trim();
+
+        match field(Some(type_col)) {
+            "task" => {}
+            "note" => {
+                if let Some(task) = parsed.tasks.last_mut() {
+                    task.notes.push(field(Some(content_col)).to_string());
+                }
+                continue;
+            }
+            "section" => {
+                parsed.warnings.push(format!("ignored section \"{}\" (its tasks are still imported)", field(Some(content_col))));
+                ancestors.clear();
+                continue;
+            }
+            _ => continue,
+        }
+
+        let key = format!("row-{}", row);
+        let indent = field(indent_col).parse::<usize>().unwrap_or(1).max(1);
+        ancestors.truncate(indent - 1);
+        let parent_key = (indent > 1).then(|| ancestors.last().cloned()).flatten();
+        ancestors.push(key.clone());
+
+        let content = field(Some(content_col));
+        let date = field(date_col);
+        let (due_at, recurrence) = if date.to_lowercase().starts_with("every") {
+            let recurrence = recurrence_from_text(date, None);
+            if recurrence.is_none() {
+                parsed.warnings.push(format!("unsupported recurrence \"{}\" on \"{}\"", date, content));
+            }
+            let first = recurrence.map(|r| crate::parser::next_occurrence(&r, chrono::Local::now().date_naive()));
+            (day_millis(first), recurrence)
+        } else if !date.is_empty() {
+            let day = parse_day(date).or_else(|| {
+                crate::parser::resolve_date(&date.to_lowercase(), chrono::Local::now().date_naive())
+            });
+            if day.is_none() {
+                parsed.warnings.push(format!("unrecognized date \"{}\" on \"{}\"", date, content));
+            }
+            (day_millis(day), None)
+        } else {
+            (None, None)
+        };
+
+        let description = field(description_col);
+        parsed.tasks.push(ImportedTask {
+            key: Some(key),
+            parent_key,
+            content: content.to_string(),
+            priority: csv_priority(field(priority_col)),
+            due_at,
+            recurrence,
+            notes: if description.is_empty() { Vec::new() } else { vec![description.to_string()] },
+            ..Default::default()
+        });
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Recurrence, RecurrenceType};
+    use chrono::NaiveDate;
+
+    fn day(y: i32, m: u32, d: u32) -> Option<i64> {
+        day_millis(NaiveDate::from_ymd_opt(y, m, d))
+    }
+
+    #[test]
+    fn parses_sync_json() {
+        let parsed = parse_json(
+            r#"{
+                "projects": [{"id": "p1", "name": "Work"}],
+                "items": [
+                    {"id": "1", "content": "Report", "project_id": "p1", "priority": 4,
+                     "description": "Q3", "labels": ["deep", "focus"],
+                     "due": {"date": "2025-01-24", "is_recurring": true, "string": "every week"}},
+                    {"id": 2, "parent_id": "1", "content": "Draft", "checked": 1, "priority": 1},
+                    {"id": "3", "content": "Gone", "is_deleted": true},
+                    {"id": "4", "content": "Odd", "due": {"date": "2025-01-24", "is_recurring": true, "string": "every 3rd"}},
+                    {"id": "5"}
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let contents: Vec<&str> = parsed.tasks.iter().map(|t| t.content.as_str()).collect();
+        assert_eq!(contents, ["Report", "Draft", "Odd"]);
+
+        let report = &parsed.tasks[0];
+        assert_eq!(report.project.as_deref(), Some("Work"));
+        assert_eq!(report.priority, Some(Priority::High));
+        assert_eq!(report.due_at, day(2025, 1, 24));
+        assert_eq!(report.recurrence, Some(Recurrence { kind: RecurrenceType::Weekly, day_of_week: Some(5) }));
+        assert_eq!(report.notes, ["Q3", "Labels: deep, focus"]);
+
+        let draft = &parsed.tasks[1];
+        assert_eq!(draft.key.as_deref(), Some("2"));
+        assert_eq!(draft.parent_key.as_deref(), Some("1"));
+        assert_eq!(draft.status, TaskStatus::Done);
+        assert_eq!(draft.priority, None);
+
+        assert_eq!(parsed.tasks[2].recurrence, None);
+        assert_eq!(parsed.warnings.len(), 2, "{:?}", parsed.warnings);
+    }
+
+    #[test]
+    fn parses_the_rest_task_list() {
+        let parsed = parse_json(r#"[{"id": "9", "content": "Call", "is_completed": true, "priority": 3}]"#).unwrap();
+        assert_eq!(parsed.tasks.len(), 1);
+        assert_eq!(parsed.tasks[0].status, TaskStatus::Done);
+        assert_eq!(parsed.tasks[0].priority, Some(Priority::Medium));
+        assert!(parse_json(r#"{"projects": []}"#).is_err());
+        assert!(parse_json("42").is_err());
+    }
+
+    #[test]
+    fn parses_csv_with_indents_notes_and_sections() {
+        let parsed = parse_csv(
+            "TYPE,CONTENT,DESCRIPTION,PRIORITY,INDENT,AUTHOR,RESPONSIBLE,DATE,DATE_LANG,TIMEZONE\n\
+             task,Plan trip,,1,1,,,2025-03-01,en,\n\
+             note,Check visas,,,,,,,,\n\
+             task,Book flights,Window seat,2,2,,,,en,\n\
+             task,Pick seats,,4,3,,,,en,\n\
+             task,Pack,,3,2,,,,en,\n\
+             section,Later,,,,,,,,\n\
+             task,Unpack,,4,2,,,,en,\n",
+        )
+        .unwrap();
+
+        let tree: Vec<(&str, Option<&str>)> =
+            parsed.tasks.iter().map(|t| (t.content.as_str(), t.parent_key.as_deref())).collect();
+        assert_eq!(
+            tree,
+            [
+                ("Plan trip", None),
+                ("Book flights", Some("row-0")),
+                ("Pick seats", Some("row-2")),
+                ("Pack", Some("row-0")),
+                ("Unpack", None),
+            ]
+        );
+        assert_eq!(parsed.tasks[0].notes, ["Check visas"]);
+        assert_eq!(parsed.tasks[0].due_at, day(2025, 3, 1));
+        assert_eq!(parsed.tasks[0].priority, Some(Priority::High));
+        assert_eq!(parsed.tasks[1].notes, ["Window seat"]);
+        assert_eq!(parsed.tasks[2].priority, None);
+        assert_eq!(parsed.warnings.len(), 1, "{:?}", parsed.warnings);
+    }
+
+    #[test]
+    fn rejects_csv_without_the_required_columns() {
+        assert!(parse_csv("CONTENT,PRIORITY\nfoo,1\n").is_err());
+    }
+}
@@ -0,0 +1,200 @@
+//! todo.txt (<https://github.com/todotxt/todo.txt>), plus the common `due:`,
+//! `t:` (threshold, imported as scheduled) and `rec:` extensions.
+//!
+//!   x 2025-01-21 2025-01-20 Call mom +family @phone
+//!   (A) Write report +work due:2025-01-24 rec:1w
+
+use chrono::NaiveDate;
+
+use super::{day_millis, parse_millis, with_due_weekday, ImportedTask, ParsedImport, Priority};
+use crate::model::{Recurrence, RecurrenceType, TaskStatus};
+
+fn priority(letter: char) -> Option<Priority> {
+    match letter {
+        'A' => Some(Priority::High),
+        'B' => Some(Priority::Medium),
+        'A'..='Z' => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+/// `(A) ` at the start of a line.
+fn strip_priority(line: &str) -> (Option<char>, &str) {
+    let bytes = line.as_bytes();
+    if bytes.len() >= 4 && bytes[0] == b'(' && bytes[1].is_ascii_uppercase() && bytes[2] == b')' && bytes[3] == b' ' {
+        (Some(bytes[1] as char), &line[4..])
+    } else {
+        (None, line)
+    }
+}
+
+/// A leading `YYYY-MM-DD ` token.
+fn strip_date(line: &str) -> (Option<NaiveDate>, &str) {
+    match line.split_once(' ') {
+        Some((token, rest)) => match NaiveDate::parse_from_str(token, "%Y-%m-%d") {
+            Ok(date) => (Some(date), rest),
+            Err(_) => (None, line),
+        },
+        None => (None, line),
+    }
+}
+
+/// `rec:` values Visor can represent: one day, business day, week or month.
+fn recurrence(value: &str) -> Option<Recurrence> {
+    let value = value.trim_start_matches('+');
+    let kind = match value.strip_prefix('1').unwrap_or(value) {
+        "d" => RecurrenceType::Daily,
+        "b" => RecurrenceType::Weekdays,
+        "w" => RecurrenceType::Weekly,
+        "m" => RecurrenceType::Monthly,
+        _ => return None,
+    };
+    Some(Recurrence { kind, day_of_week: None })
+}
+
+fn date_millis(date: NaiveDate) -> Option<i64> {
+    parse_millis(&date.format("%Y-%m-%d").to_string())
+}
+
+pub fn parse(content: &str) -> ParsedImport {
+    let mut parsed = ParsedImport::default();
+
+    for (number, raw) in content.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (done, line) = match line.strip_prefix("x ") {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (mut letter, line) = strip_priority(line);
+        let (first_date, line) = strip_date(line);
+        // Completed tasks carry the completion date first, then the creation date
+        let (completed_on, created_on, line) = if done {
+            let (second_date, line) = strip_date(line);
+            match second_date {
+                Some(created) => (first_date, Some(created), line),
+                None => (first_date, None, line),
+            }
+        } else {
+            (None, first_date, line)
+        };
+
+        let mut words = Vec::new();
+        let mut project = None;
+        let mut due = None;
+        let mut threshold = None;
+        let mut rec = None;
+
+        for word in line.split_whitespace() {
+            if let Some(name) = word.strip_prefix('+').filter(|n| !n.is_empty()) {
+                project.get_or_insert_with(|| name.to_string());
+                continue;
+            }
+            if let Some((key, value)) = word.split_once(':').filter(|(k, v)| !k.is_empty() && !v.is_empty()) {
+                let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok();
+                match key {
+                    "due" if date.is_some() => {
+                        due = date;
+                        continue;
+                    }
+                    "t" if date.is_some() => {
+                        threshold = date;
+                        continue;
+                    }
+                    "rec" => {
+                        rec = Some(value.to_string());
+                        continue;
+                    }
+                    // Completed tasks keep their priority as `pri:A`
+                    "pri" if value.len() == 1 => {
+                        letter = letter.or(value.chars().next());
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+            words.push(word);
+        }
+
+        let content = words.join(" ");
+        if content.is_empty() {
+            parsed.warnings.push(format!("line {}: no task text", number + 1));
+            continue;
+        }
+
+        let recurrence_rule = rec.filter(|_| !done).and_then(|r| {
+            let rule = recurrence(&r).map(|rule| with_due_weekday(rule, due));
+            if rule.is_none() {
+                parsed.warnings.push(format!("line {}: unsupported recurrence \"rec:{}\"", number + 1, r));
+            }
+            rule
+        });
+
+        parsed.tasks.push(ImportedTask {
+            content,
+            project,
+            status: if done { TaskStatus::Done } else { TaskStatus::Todo },
+            priority: letter.and_then(priority),
+            created_at: created_on.and_then(date_millis),
+            completed_at: completed_on.and_then(date_millis),
+            due_at: day_millis(due),
+            scheduled: day_millis(threshold),
+            recurrence: recurrence_rule,
+            ..Default::default()
+        });
+    }
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn parses_todo_lines() {
+        let parsed = parse(
+            "x 2025-01-21 2025-01-20 Call mom +family @phone pri:B\n\
+             \n\
+             (A) 2025-01-19 Write report +work +q1 due:2025-01-24 t:2025-01-22 rec:1w\n\
+             (C) Stretch rec:+d\n\
+             Fix bike rec:2w see:url\n\
+             +lonely due:2025-01-24\n",
+        );
+
+        let mom = &parsed.tasks[0];
+        assert_eq!(mom.content, "Call mom @phone");
+        assert_eq!(mom.status, TaskStatus::Done);
+        assert_eq!(mom.project.as_deref(), Some("family"));
+        assert_eq!(mom.priority, Some(Priority::Medium));
+        assert_eq!(mom.completed_at, date_millis(date(2025, 1, 21)));
+        assert_eq!(mom.created_at, date_millis(date(2025, 1, 20)));
+
+        let report = &parsed.tasks[1];
+        assert_eq!(report.content, "Write report");
+        assert_eq!(report.project.as_deref(), Some("work"));
+        assert_eq!(report.priority, Some(Priority::High));
+        assert_eq!(report.created_at, date_millis(date(2025, 1, 19)));
+        assert_eq!(report.due_at, day_millis(Some(date(2025, 1, 24))));
+        assert_eq!(report.scheduled, day_millis(Some(date(2025, 1, 22))));
+        // 2025-01-24 is a Friday
+        assert_eq!(report.recurrence, Some(Recurrence { kind: RecurrenceType::Weekly, day_of_week: Some(5) }));
+
+        let stretch = &parsed.tasks[2];
+        assert_eq!(stretch.priority, Some(Priority::Low));
+        assert_eq!(stretch.recurrence, Some(Recurrence { kind: RecurrenceType::Daily, day_of_week: None }));
+
+        let bike = &parsed.tasks[3];
+        assert_eq!(bike.content, "Fix bike see:url");
+        assert_eq!(bike.recurrence, None);
+
+        assert_eq!(parsed.tasks.len(), 4);
+        assert_eq!(parsed.warnings, ["line 5: unsupported recurrence \"rec:2w\"", "line 6: no task text"]);
+    }
+}
@@ -0,0 +1,194 @@
+//! Journal (`logEntries`) operations shared by the HTTP API and `visor-cli`.
+
+use chrono::{Local, NaiveDate, TimeZone};
+use serde_json::{json, Value};
+
+use crate::model::{LogEntry, Task};
+use crate::tasks;
+
+/// Filters for `GET /api/log`.
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    /// Project slug.
+    pub project: Option<String>,
+    pub task_id: Option<String>,
+    /// Inclusive bounds on `createdAt`, in ms.
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    /// Case-insensitive substring of the content.
+    pub text: Option<String>,
+    /// Id of the last entry of the previous page.
+    pub before: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// A `from`/`to` bound: milliseconds, or a `YYYY-MM-DD` local day (its start
+/// for `from`, its end for `to`).
+pub fn parse_bound(value: &str, end_of_day: bool) -> Option<i64> {
+    if let Ok(ms) = value.parse::<i64>() {
+        return Some(ms);
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    let time = if end_of_day {
+        date.and_hms_milli_opt(23, 59, 59, 999)?
+    } else {
+        date.and_hms_opt(0, 0, 0)?
+    };
+    Local.from_local_datetime(&time).earliest().map(|dt| dt.timestamp_millis())
+}
+
+fn project_id(data: &Value, slug: &str) -> Result<String, String> {
+    data.get("projects")
+        .and_then(|p| p.as_object())
+        .and_then(|p| p.iter().find(|(_, v)| v.get("slug").and_then(|s| s.as_str()) == Some(slug)))
+        .map(|(id, _)| id.clone())
+        .ok_or(format!("project not found: {}", slug))
+}
+
+fn find_task(data: &Value, id: &str) -> Result<Task, String> {
+    data.get("tasks")
+        .and_then(|t| t.get(id))
+        .and_then(|t| serde_json::from_value(t.clone()).ok())
+        .ok_or(format!("task not found: {}", id))
+}
+
+/// Matching entries, newest first, as served by `GET /api/log`. Pass the
+/// last id of a page as `before` to get the next one.
+pub fn list_entries(data: &Value, query: &LogQuery) -> Result<Value, String> {
+    let project_id = query.project.as_deref().map(|slug| project_id(data, slug)).transpose()?;
+    let text = query.text.as_ref().map(|t| t.to_lowercase());
+
+    let mut entries: Vec<(usize, LogEntry, &Value)> = data
+        .get("logEntries")
+        .and_then(|l| l.as_array())
+        .map(|l| {
+            l.iter()
+                .enumerate()
+                .filter_map(|(i, v)| Some((i, serde_json::from_value(v.clone()).ok()?, v)))
+                .collect()
+        })
+        .unwrap_or_default();
+    // Later entries first among equal timestamps
+    entries.sort_by_key(|(i, e, _)| std::cmp::Reverse((e.created_at, *i)));
+
+    let start = match &query.before {
+        Some(id) => {
+            entries
+                .iter()
+                .position(|(_, e, _)| &e.id == id)
+                .ok_or(format!("entry not found: {}", id))?
+                + 1
+        }
+        None => 0,
+    };
+
+    let page: Vec<Value> = entries[start..]
+        .iter()
+        .filter(|(_, e, _)| {
+            project_id.as_ref().is_none_or(|id| &e.project_id == id)
+                && query.task_id.as_ref().is_none_or(|id| e.task_id.as_ref() == Some(id))
+                && query.from.is_none_or(|from| e.created_at >= from)
+                && query.to.is_none_or(|to| e.created_at <= to)
+                && text.as_ref().is_none_or(|t| e.content.to_lowercase().contains(t))
+        })
+        .take(query.limit.unwrap_or(usize::MAX))
+        .map(|(_, _, v)| (*v).clone())
+        .collect();
+    Ok(json!(page))
+}
+
+/// Append an entry, optionally attached to `task_id`. The project defaults to
+/// the task's, then to the inbox; unknown slugs also go to the inbox.
+pub fn add_entry(
+    data: &mut Value,
+    content: &str,
+    project_slug: Option<&str>,
+    task_id: Option<&str>,
+) -> Result<Value, String> {
+    let content = content.trim();
+    if content.is_empty() {
+        return Err("content required".to_string());
+    }
+    let task = task_id.map(|id| find_task(data, id)).transpose()?;
+
+    let entry = LogEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        content: content.to_string(),
+        created_at: chrono::Utc::now().timestamp_millis(),
+        project_id: match (project_slug, &task) {
+            (Some(slug), _) => tasks::project_id_for_slug(data, slug),
+            (None, Some(task)) => task.project_id.clone(),
+            (None, None) => "inbox".to_string(),
+        },
+        task_id: task.map(|t| t.id),
+    };
+    let value = serde_json::to_value(&entry).map_err(|e| e.to_string())?;
+
+    if data.get("logEntries").is_none() {
+        data["logEntries"] = json!([]);
+    }
+    if let Some(arr) = data["logEntries"].as_array_mut() {
+        arr.push(value.clone());
+    }
+
+    Ok(value)
+}
+
+/// Apply `content`, `project` (slug) and `taskId` (null detaches) from
+/// `patch`. `Ok(None)` if there's no entry with `id`.
+pub fn update_entry(data: &mut Value, id: &str, patch: &Value) -> Result<Option<Value>, String> {
+    let content = match patch.get("content") {
+        Some(c) => {
+            let content = c.as_str().map(str::trim).unwrap_or("");
+            if content.is_empty() {
+                return Err("content must be a non-empty string".to_string());
+            }
+            Some(content.to_string())
+        }
+        None => None,
+    };
+    let project_id = match patch.get("project") {
+        Some(p) => Some(project_id(data, p.as_str().ok_or("project must be a slug")?)?),
+        None => None,
+    };
+    let task_id = match patch.get("taskId") {
+        Some(Value::Null) => Some(None),
+        Some(Value::String(id)) => Some(Some(find_task(data, id)?.id)),
+        Some(_) => return Err("taskId must be a task id or null".to_string()),
+        None => None,
+    };
+    if content.is_none() && project_id.is_none() && task_id.is_none() {
+        return Err("nothing to update: expected content, project or taskId".to_string());
+    }
+
+    let Some(entry) = data
+        .get_mut("logEntries")
+        .and_then(|l| l.as_array_mut())
+        .and_then(|l| l.iter_mut().find(|e| e.get("id").and_then(|i| i.as_str()) == Some(id)))
+    else {
+        return Ok(None);
+    };
+    if let Some(content) = content {
+        entry["content"] = json!(content);
+    }
+    if let Some(project_id) = project_id {
+        entry["projectId"] = json!(project_id);
+    }
+    match task_id {
+        Some(Some(task_id)) => entry["taskId"] = json!(task_id),
+        Some(None) => {
+            if let Some(obj) = entry.as_object_mut() {
+                obj.remove("taskId");
+            }
+        }
+        None => {}
+    }
+    Ok(Some(entry.clone()))
+}
+
+/// Remove the entry with `id`, returning it.
+pub fn delete_entry(data: &mut Value, id: &str) -> Option<Value> {
+    let entries = data.get_mut("logEntries")?.as_array_mut()?;
+    let index = entries.iter().position(|e| e.get("id").and_then(|i| i.as_str()) == Some(id))?;
+    Some(entries.remove(index))
+}
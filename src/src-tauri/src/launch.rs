@@ -0,0 +1,87 @@
+//! Command-line arguments, both at startup and forwarded from a second
+//! launch by the single-instance plugin:
+//!
+//!   visor --toggle
+//!   visor --add "Write report !fri" [--project work]
+//!   visor --project work
+//!   visor --hidden
+//!   visor --headless   (API only, no window; not forwarded)
+//!   visor visor://...  (see `deep_link`)
+
+use serde_json::json;
+use tauri::{AppHandle, Emitter};
+
+use crate::{autostart, deep_link, storage, tasks};
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LaunchArgs {
+    pub hidden: bool,
+    pub headless: bool,
+    pub toggle: bool,
+    pub add: Option<String>,
+    pub project: Option<String>,
+    /// A `visor://` link; the deep-link plugin acts on it.
+    pub link: Option<String>,
+}
+
+impl LaunchArgs {
+    /// Parse arguments, excluding the program name. Unknown flags are ignored.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Self {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                a if a == autostart::HIDDEN_FLAG => parsed.hidden = true,
+                "--headless" => parsed.headless = true,
+                "--toggle" => parsed.toggle = true,
+                "--add" => parsed.add = args.next().filter(|c| !c.trim().is_empty()),
+                "--project" => parsed.project = args.next().filter(|p| !p.trim().is_empty()),
+                a if deep_link::is_deep_link(a) => parsed.link = Some(arg),
+                _ => {}
+            }
+        }
+        parsed
+    }
+
+    pub fn from_env() -> Self {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    /// Whether the visor should stay in the background at startup. With
+    /// `--toggle` it starts hidden so `handle` can toggle it up.
+    pub fn stays_hidden(&self) -> bool {
+        self.hidden || self.toggle || self.add.is_some() || self.link.is_some()
+    }
+}
+
+/// Act on launch arguments. `forwarded` is set when a second launch handed
+/// them over; a bare second launch brings the visor up.
+pub fn handle(app: &AppHandle, args: &LaunchArgs, forwarded: bool) {
+    if args.link.is_some() {
+        return;
+    }
+
+    if let Some(content) = &args.add {
+        if let Err(e) = storage::ensure_unlocked() {
+            eprintln!("Failed to add task: {}", e);
+            return;
+        }
+        let slug = args.project.as_deref().unwrap_or("inbox");
+        match storage::update_json(|data| tasks::create_task(data, content, slug)) {
+            Ok(_) => {
+                let _ = app.emit("data-changed", ());
+            }
+            Err(e) => eprintln!("Failed to add task: {}", e),
+        }
+        return;
+    }
+
+    if let Some(slug) = &args.project {
+        crate::show_visor(app);
+        let _ = app.emit("navigate", json!({ "type": "project", "slug": slug }));
+    } else if args.toggle {
+        crate::toggle_visor(app);
+    } else if forwarded && !args.hidden {
+        crate::show_visor(app);
+    }
+}
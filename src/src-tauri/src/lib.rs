@@ -1,8 +1,35 @@
 mod api_server;
+mod autostart;
+mod calendar;
+mod deep_link;
+pub mod encryption;
+mod export;
+mod geometry;
+pub mod history;
+mod import;
+pub mod journal;
+mod launch;
+pub mod model;
+pub mod parser;
+mod quick_capture;
+pub mod report;
+mod search;
+pub mod settings;
+pub mod snapshots;
+pub mod storage;
+pub mod sync;
+pub mod tasks;
+pub mod templates;
+mod tray;
 
+use geometry::{MonitorInfo, Rect, VisorGeometry};
+use settings::Settings;
 use std::fs;
-use std::path::PathBuf;
-use tauri::{Emitter, Manager, PhysicalPosition, PhysicalSize, Position, Size};
+use std::sync::{Arc, Mutex};
+use storage::data_path;
+use tauri::{Emitter, Listener, Manager, Monitor, PhysicalPosition, PhysicalSize, Position, Size};
+#[cfg(desktop)]
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 
 #[cfg(target_os = "macos")]
 use cocoa::appkit::{NSApp, NSApplication, NSWindow, NSWindowCollectionBehavior};
@@ -12,28 +39,62 @@ use cocoa::base::{nil, YES};
 #[macro_use]
 extern crate objc;
 
-const VISOR_HEIGHT_RATIO: f32 = 0.45;
+/// Current visor placement, updated live on `settings-changed`.
+struct GeometryState(Mutex<VisorGeometry>);
 
-fn data_path() -> PathBuf {
-    let home = dirs::home_dir().expect("Could not find home directory");
-    home.join(".visor")
+/// Registered global shortcuts: (toggle visor, quick capture).
+#[cfg(desktop)]
+struct ShortcutsState(Mutex<Option<(Shortcut, Shortcut)>>);
+
+/// The data as the webview last loaded or saved it, with its generation: the
+/// base its next save was edited from.
+static WEBVIEW_BASE: Mutex<(u64, String)> = Mutex::new((0, String::new()));
+
+/// The data file's JSON text and the generation to pass back to `save_data`.
+#[derive(serde::Serialize)]
+struct LoadedData {
+    data: String,
+    generation: u64,
+}
+
+#[derive(serde::Serialize)]
+struct SavedData {
+    generation: u64,
+    /// Whether the save was replayed over a newer write, so the webview
+    /// should reload to see both.
+    rebased: bool,
 }
 
 #[tauri::command]
-fn load_data() -> Result<String, String> {
-    let path = data_path().join("data.json");
-    if path.exists() {
-        fs::read_to_string(&path).map_err(|e| e.to_string())
-    } else {
-        Ok("null".to_string())
-    }
+fn load_data() -> Result<LoadedData, String> {
+    let (data, generation) = encryption::read_versioned()?;
+    let data = data.unwrap_or_else(|| "null".to_string());
+    *WEBVIEW_BASE.lock().unwrap_or_else(|e| e.into_inner()) = (generation, data.clone());
+    Ok(LoadedData { data, generation })
 }
 
+/// Save the webview's data. If the backend wrote since `generation` (a sync
+/// merge, an API or CLI change), saving as is would undo that, so the
+/// webview's edits are replayed over it instead.
 #[tauri::command]
-fn save_data(data: String) -> Result<(), String> {
-    let dir = data_path();
-    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
-    fs::write(dir.join("data.json"), data).map_err(|e| e.to_string())
+fn save_data(app: tauri::AppHandle, data: String, generation: u64) -> Result<SavedData, String> {
+    fs::create_dir_all(data_path()).map_err(|e| e.to_string())?;
+    let mut base = WEBVIEW_BASE.lock().unwrap_or_else(|e| e.into_inner());
+    let ((text, rebased), saved) = encryption::update_versioned(|current, current_generation| {
+        let current = match current {
+            Some(current) if current_generation != generation => current,
+            _ => return Ok((Some(data.clone()), (data, false))),
+        };
+        let parse = |text: &str| serde_json::from_str::<serde_json::Value>(text).map_err(|e| e.to_string());
+        let edited_from = if base.0 == generation { parse(&base.1)? } else { serde_json::json!({}) };
+        let merged = sync::rebase(&edited_from, &parse(&data)?, &parse(current)?);
+        let text = serde_json::to_string_pretty(&merged).map_err(|e| e.to_string())?;
+        Ok((Some(text.clone()), (text, true)))
+    })?;
+    search::refresh_raw(&text);
+    *base = (saved, text);
+    tray::refresh(&app);
+    Ok(SavedData { generation: saved, rebased })
 }
 
 /// Configure macOS-specific window behavior: all spaces, high level, collection behavior
@@ -48,42 +109,175 @@ fn configure_macos_window(window: &tauri::WebviewWindow) {
                 | NSWindowCollectionBehavior::NSWindowCollectionBehaviorStationary
                 | NSWindowCollectionBehavior::NSWindowCollectionBehaviorFullScreenAuxiliary;
             ns_window.setCollectionBehavior_(behavior);
+        }
+    }
+}
 
-            // Set window level above menu bar (25 = NSMainMenuWindowLevel + 1)
-            ns_window.setLevel_(25);
+/// Set the macOS window level: above the menu bar (25 = NSMainMenuWindowLevel + 1)
+/// when covering it, otherwise floating (3 = NSFloatingWindowLevel).
+#[cfg(target_os = "macos")]
+fn set_macos_window_level(window: &tauri::WebviewWindow, cover_menu_bar: bool) {
+    unsafe {
+        if let Ok(ns_window) = window.ns_window() {
+            let ns_window = ns_window as cocoa::base::id;
+            ns_window.setLevel_(if cover_menu_bar { 25 } else { 3 });
         }
     }
 }
 
-/// Position window full-width at the top of the monitor where the cursor is.
-/// Uses work_area to respect menu bar and dock.
-fn position_on_active_monitor(window: &tauri::WebviewWindow) {
-    let monitor = window
+/// Monitor under the cursor, falling back to the window's current or primary monitor.
+fn active_monitor(window: &tauri::WebviewWindow) -> Option<Monitor> {
+    window
         .cursor_position()
         .ok()
         .and_then(|pos| window.monitor_from_point(pos.x, pos.y).ok().flatten())
         .or_else(|| window.current_monitor().ok().flatten())
-        .or_else(|| window.primary_monitor().ok().flatten());
+        .or_else(|| window.primary_monitor().ok().flatten())
+}
+
+/// Configure Linux window-manager hints: all workspaces, keep above (including
+/// fullscreen windows) and hidden from pagers. Honored by X11 window managers;
+/// Wayland compositors ignore them, see `prefer_x11_backend`.
+#[cfg(target_os = "linux")]
+fn configure_linux_window(window: &tauri::WebviewWindow, enabled: bool) {
+    let window = window.clone();
+    let _ = window.clone().run_on_main_thread(move || {
+        use gtk::prelude::GtkWindowExt;
+
+        if let Ok(gtk_window) = window.gtk_window() {
+            if enabled {
+                gtk_window.stick();
+            } else {
+                gtk_window.unstick();
+            }
+            gtk_window.set_keep_above(enabled);
+            gtk_window.set_skip_pager_hint(enabled);
+        }
+    });
+}
+
+/// Wayland has no client-side way to join all workspaces or stay above other
+/// windows, so when the hints are enabled in a Wayland session we run GTK
+/// through XWayland, where the window manager honors them. Must run before
+/// GTK is initialized; an explicit `GDK_BACKEND` always wins.
+#[cfg(target_os = "linux")]
+fn prefer_x11_backend(geometry: &VisorGeometry) {
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some();
+    if geometry.linux_window_hints && wayland && std::env::var_os("GDK_BACKEND").is_none() {
+        std::env::set_var("GDK_BACKEND", "x11");
+    }
+}
+
+fn monitor_info(monitor: &Monitor, primary: Option<&Monitor>) -> MonitorInfo {
+    let size = monitor.size();
+    let pos = monitor.position();
+    let work_area = monitor.work_area();
+    MonitorInfo {
+        name: monitor.name().cloned(),
+        rect: Rect {
+            x: pos.x,
+            y: pos.y,
+            width: size.width,
+            height: size.height,
+        },
+        work_area: Rect {
+            x: work_area.position.x,
+            y: work_area.position.y,
+            width: work_area.size.width,
+            height: work_area.size.height,
+        },
+        scale_factor: monitor.scale_factor(),
+        is_primary: primary
+            .is_some_and(|p| p.name() == monitor.name() && p.position() == monitor.position()),
+    }
+}
 
-    if let Some(monitor) = monitor {
-        let monitor_size = monitor.size();
-        let monitor_pos = monitor.position();
+/// Size and place the visor on the monitor chosen by the geometry settings.
+/// Lays out within the monitor's work area so panels, docks and the menu bar
+/// stay visible, unless `coverMenuBar` is set.
+fn position_on_active_monitor(window: &tauri::WebviewWindow) {
+    let geometry = window.state::<GeometryState>().0.lock().unwrap().clone();
+
+    let primary = window.primary_monitor().ok().flatten();
+    let monitors: Vec<MonitorInfo> = window
+        .available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(|m| monitor_info(m, primary.as_ref()))
+        .collect();
+    let cursor = window.cursor_position().ok().map(|p| (p.x, p.y));
 
-        // Use full monitor width, VISOR_HEIGHT_RATIO of height
-        let window_height = (monitor_size.height as f32 * VISOR_HEIGHT_RATIO) as u32;
+    if let Some(monitor) = geometry::select_monitor(&monitors, &geometry.monitor, cursor) {
+        let area = geometry::layout_area(monitor, &geometry);
+        let rect = geometry::visor_rect(area, monitor.scale_factor, &geometry);
 
         let _ = window.set_size(Size::Physical(PhysicalSize {
-            width: monitor_size.width,
-            height: window_height,
+            width: rect.width,
+            height: rect.height,
         }));
 
-        // Position at top-left of monitor. On macOS the menu bar is handled by
-        // the high window level (25) which places us above it.
         let _ = window.set_position(Position::Physical(PhysicalPosition {
-            x: monitor_pos.x,
-            y: monitor_pos.y,
+            x: rect.x,
+            y: rect.y,
         }));
+
+        #[cfg(target_os = "macos")]
+        set_macos_window_level(window, geometry.cover_menu_bar);
+
+        #[cfg(target_os = "linux")]
+        configure_linux_window(window, geometry.linux_window_hints);
+    }
+}
+
+/// Apply new geometry settings, repositioning the visor immediately.
+fn apply_geometry(app: &tauri::AppHandle, geometry: &VisorGeometry) {
+    {
+        let state = app.state::<GeometryState>();
+        let mut current = state.0.lock().unwrap();
+        if *current == *geometry {
+            return;
+        }
+        *current = geometry.clone();
+    }
+    if let Some(window) = app.get_webview_window("main") {
+        position_on_active_monitor(&window);
+    }
+}
+
+/// Register the configured global shortcuts in place of the current ones.
+/// A shortcut another app already holds is reported and skipped.
+#[cfg(desktop)]
+fn apply_keybindings(app: &tauri::AppHandle, keybindings: &settings::Keybindings) {
+    let (Ok(toggle), Ok(capture)) = (
+        keybindings.toggle_visor.parse::<Shortcut>(),
+        keybindings.quick_capture.parse::<Shortcut>(),
+    ) else {
+        return;
+    };
+
+    let state = app.state::<ShortcutsState>();
+    let mut current = state.0.lock().unwrap();
+    if *current == Some((toggle, capture)) {
+        return;
+    }
+    if let Some((old_toggle, old_capture)) = current.take() {
+        let _ = app.global_shortcut().unregister_multiple([old_toggle, old_capture]);
+    }
+    for (shortcut, keys) in [(toggle, &keybindings.toggle_visor), (capture, &keybindings.quick_capture)] {
+        if let Err(e) = app.global_shortcut().register(shortcut) {
+            eprintln!("Failed to register shortcut {}: {}", keys, e);
+        }
     }
+    *current = Some((toggle, capture));
+}
+
+/// Make saved settings take effect without a restart. The API port is only
+/// read at startup.
+fn apply_settings(app: &tauri::AppHandle, settings: &Settings) {
+    apply_geometry(app, &settings.window);
+
+    #[cfg(desktop)]
+    apply_keybindings(app, &settings.keybindings);
 }
 
 /// Activate window and bring to foreground (steals focus from other apps)
@@ -103,64 +297,191 @@ fn activate_window(window: &tauri::WebviewWindow) {
     }
 }
 
+/// Show the visor on the active monitor, or ask the webview to slide it away.
+fn toggle_visor(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        match window.is_visible() {
+            Ok(true) => {
+                let _ = window.emit("visor-hide", ());
+            }
+            Ok(false) => show_visor(app),
+            Err(e) => {
+                eprintln!("Error checking window visibility: {}", e);
+            }
+        }
+    }
+}
+
+/// Bring the visor up on the active monitor (a no-op re-focus if already shown).
+fn show_visor(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        if !window.is_visible().unwrap_or(false) {
+            position_on_active_monitor(&window);
+        }
+        activate_window(&window);
+        let _ = window.emit("visor-show", ());
+    }
+}
+
+/// `visor --headless`: the API server, data lock and background jobs only. No
+/// webview, tray or global shortcuts, so it runs without a display (dev
+/// containers, CI). Reminders live in the webview, so none are sent.
+fn run_headless() {
+    let _lock = match storage::acquire_lock() {
+        Ok(lock) => lock,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if !encryption::unlock_unattended() {
+        eprintln!(
+            "Data is encrypted: remember the key in the keyring from the app, or set VISOR_PASSPHRASE"
+        );
+        std::process::exit(1);
+    }
+
+    snapshots::start_scheduler();
+    history::start_watcher();
+    sync::start_watcher(None);
+    import::ics::start_watcher(None);
+
+    match api_server::start_api_server(Arc::new(api_server::ApiState::default()), None) {
+        Ok(server) => {
+            let _ = server.join();
+        }
+        Err(e) => {
+            eprintln!("Failed to start API server: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let launch_args = launch::LaunchArgs::from_env();
+    if launch_args.headless {
+        return run_headless();
+    }
+
+    let settings = settings::load();
+
+    #[cfg(target_os = "linux")]
+    prefer_x11_backend(&settings.window);
+
     tauri::Builder::default()
+        // Must be first: a second launch forwards its arguments here and exits
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            let args = launch::LaunchArgs::parse(argv.into_iter().skip(1));
+            launch::handle(app, &args, true);
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_notification::init())
-        .invoke_handler(tauri::generate_handler![load_data, save_data])
-        .setup(|app| {
+        .plugin(autostart::plugin())
+        .manage(GeometryState(Mutex::new(settings.window.clone())))
+        .manage(Arc::new(api_server::ApiState::default()))
+        .invoke_handler(tauri::generate_handler![
+            load_data,
+            save_data,
+            encryption::encryption_status,
+            encryption::unlock_data,
+            encryption::enable_encryption,
+            encryption::disable_encryption,
+            encryption::change_encryption_passphrase,
+            snapshots::list_snapshots,
+            snapshots::snapshot_diff,
+            snapshots::restore_snapshot,
+            history::list_history,
+            history::restore_history,
+            sync::sync_now,
+            settings::get_settings,
+            settings::update_settings,
+            autostart::get_autostart,
+            autostart::set_autostart,
+            import::import_tasks,
+            import::ics::sync_calendars,
+            export::export_tasks,
+            search::search,
+            templates::template_inputs,
+            templates::instantiate_template,
+            templates::get_templates,
+            templates::save_template,
+            templates::remove_template,
+            templates::apply_saved_template,
+            quick_capture::quick_capture_submit,
+            quick_capture::quick_capture_close
+        ])
+        .setup(move |app| {
+            // Backstop for the single-instance check: never share data.json
+            let lock = storage::acquire_lock()?;
+            app.manage(lock);
+
+            // Otherwise the webview asks for the passphrase
+            if encryption::unlock_unattended() {
+                snapshots::take_startup();
+            }
+            snapshots::start_scheduler();
+            history::start_watcher();
+
             if let Some(window) = app.get_webview_window("main") {
-                // Configure macOS window behavior (all spaces, high level)
+                // Configure macOS window behavior (all spaces, fullscreen auxiliary)
                 #[cfg(target_os = "macos")]
                 configure_macos_window(&window);
 
                 // Position on active monitor BEFORE showing (prevents flash at default size)
                 position_on_active_monitor(&window);
-                activate_window(&window);
+
+                // Launched at login: stay in the background until the shortcut is used
+                if !launch_args.stays_hidden() {
+                    activate_window(&window);
+                }
             }
 
-            api_server::start_api_server(app.handle().clone());
+            let api_state = app.state::<Arc<api_server::ApiState>>().inner().clone();
+            if let Err(e) = api_server::start_api_server(api_state, Some(app.handle().clone())) {
+                eprintln!("Failed to start API server: {}", e);
+            }
+
+            import::ics::start_watcher(Some(app.handle().clone()));
+            sync::start_watcher(Some(app.handle().clone()));
 
             #[cfg(desktop)]
-            {
-                use tauri_plugin_global_shortcut::{
-                    Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState,
-                };
+            tray::create(app.handle())?;
 
-                let toggle_shortcut = Shortcut::new(Some(Modifiers::CONTROL), Code::Backquote);
+            deep_link::register(app.handle());
+            launch::handle(app.handle(), &launch_args, false);
 
+            #[cfg(desktop)]
+            {
+                app.manage(ShortcutsState(Mutex::new(None)));
                 app.handle().plugin(
                     tauri_plugin_global_shortcut::Builder::new()
                         .with_handler(move |app, shortcut, event| {
-                            if shortcut == &toggle_shortcut
-                                && event.state() == ShortcutState::Pressed
-                            {
-                                if let Some(window) = app.get_webview_window("main") {
-                                    match window.is_visible() {
-                                        Ok(true) => {
-                                            let _ = window.emit("visor-hide", ());
-                                        }
-                                        Ok(false) => {
-                                            position_on_active_monitor(&window);
-                                            activate_window(&window);
-                                            let _ = window.emit("visor-show", ());
-                                        }
-                                        Err(e) => {
-                                            eprintln!(
-                                                "Error checking window visibility: {}",
-                                                e
-                                            );
-                                        }
-                                    }
-                                }
+                            if event.state() != ShortcutState::Pressed {
+                                return;
+                            }
+                            let current = *app.state::<ShortcutsState>().0.lock().unwrap();
+                            match current {
+                                Some((_, capture)) if *shortcut == capture => quick_capture::toggle(app),
+                                Some((toggle, _)) if *shortcut == toggle => toggle_visor(app),
+                                _ => {}
                             }
                         })
                         .build(),
                 )?;
-
-                app.global_shortcut().register(toggle_shortcut)?;
+                apply_keybindings(app.handle(), &settings.keybindings);
             }
+
+            // Sent by both the `update_settings` command and the HTTP API
+            let handle = app.handle().clone();
+            app.listen_any("settings-changed", move |event| {
+                match serde_json::from_str::<Settings>(event.payload()) {
+                    Ok(settings) => apply_settings(&handle, &settings),
+                    Err(e) => eprintln!("Invalid settings-changed payload: {}", e),
+                }
+            });
             Ok(())
         })
         .run(tauri::generate_context!())
@@ -0,0 +1,160 @@
+//! Typed view of the records in `data.json`, mirroring `src/store/types.ts`.
+//! Field defaults keep older files (missing `status`, `scheduled`, ...) readable.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TaskStatus {
+    #[default]
+    Todo,
+    Doing,
+    Done,
+    Cancelled,
+    Waiting,
+}
+
+impl TaskStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Todo => "TODO",
+            TaskStatus::Doing => "DOING",
+            TaskStatus::Done => "DONE",
+            TaskStatus::Cancelled => "CANCELLED",
+            TaskStatus::Waiting => "WAITING",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_uppercase().as_str() {
+            "TODO" => Some(TaskStatus::Todo),
+            "DOING" => Some(TaskStatus::Doing),
+            "DONE" => Some(TaskStatus::Done),
+            "CANCELLED" => Some(TaskStatus::Cancelled),
+            "WAITING" => Some(TaskStatus::Waiting),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecurrenceType {
+    Daily,
+    Weekly,
+    Monthly,
+    Weekdays,
+}
+
+/// `dayOfWeek` is 0=Sun..6=Sat, for weekly recurrence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recurrence {
+    #[serde(rename = "type")]
+    pub kind: RecurrenceType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub day_of_week: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    pub id: String,
+    pub content: String,
+    #[serde(default)]
+    pub completed: bool,
+    #[serde(default)]
+    pub status: TaskStatus,
+    #[serde(default)]
+    pub archived: bool,
+    pub project_id: String,
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    #[serde(default)]
+    pub indent: u32,
+    pub created_at: i64,
+    #[serde(default)]
+    pub completed_at: Option<i64>,
+    /// When the task last moved to DOING.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<i64>,
+    #[serde(default)]
+    pub due_at: Option<i64>,
+    #[serde(default)]
+    pub scheduled: Option<i64>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<TaskSource>,
+}
+
+/// Origin of a task kept in sync with an external source (a calendar event).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskSource {
+    /// Source type, e.g. `ics`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Item id within the source (event UID, plus the date for recurrences).
+    pub uid: String,
+    /// Configured path the task was synced from.
+    pub origin: String,
+    /// Hash of the fields sync last wrote; differs once the user edits them.
+    pub fingerprint: String,
+}
+
+impl Task {
+    /// Neither completed nor archived.
+    pub fn is_pending(&self) -> bool {
+        !self.completed && !self.archived
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    pub slug: String,
+    #[serde(default)]
+    pub color: String,
+    #[serde(default)]
+    pub task_order: Vec<String>,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub is_inbox: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    pub id: String,
+    pub content: String,
+    pub created_at: i64,
+    pub project_id: String,
+    /// Task the entry is about, shown in that task's detail view.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Template {
+    pub id: String,
+    pub name: String,
+    pub tasks: Vec<TemplateTask>,
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+/// A template line; `content` may use smart syntax, parsed when applied.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateTask {
+    pub content: String,
+    #[serde(default)]
+    pub indent: u32,
+}
@@ -0,0 +1,166 @@
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate, TimeZone, Weekday};
+
+use crate::model::{Recurrence, RecurrenceType};
+
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedTask {
+    pub content: String,
+    pub due_at: Option<i64>,
+    pub scheduled: Option<i64>,
+    pub recurrence: Option<Recurrence>,
+}
+
+/// Split a `{project}: {task}` prefix off raw input, as the visor input does.
+/// Returns the lowercased project slug (if any) and the remaining content.
+pub fn split_project_prefix(input: &str) -> (Option<String>, String) {
+    let trimmed = input.trim();
+    if let Some((slug, rest)) = trimmed.split_once(':') {
+        let is_word = !slug.is_empty()
+            && slug.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if is_word && rest.starts_with(char::is_whitespace) && !rest.trim().is_empty() {
+            return (Some(slug.to_lowercase()), rest.trim().to_string());
+        }
+    }
+    (None, trimmed.to_string())
+}
+
+/// Parse inline smart syntax from task content, matching `parseDueDate` in the frontend.
+/// Supports:
+///   !every day|weekday|week|month|mon-sun — recurrence
+///   !today, !tomorrow, !mon-!sun, !1/20  — DEADLINE (must be done by)
+///   @today, @tomorrow, @mon-@sun, @1/20  — SCHEDULED (start working on)
+pub fn parse_smart_syntax(content: &str, now: DateTime<Local>) -> ParsedTask {
+    let today = now.date_naive();
+    let words: Vec<&str> = content.split_whitespace().collect();
+
+    let mut kept = Vec::with_capacity(words.len());
+    let mut recurrence = None;
+    let mut due = None;
+    let mut scheduled = None;
+
+    let mut i = 0;
+    while i < words.len() {
+        let word = words[i];
+        let lower = word.to_lowercase();
+
+        if recurrence.is_none() && lower == "!every" {
+            if let Some(rec) = words.get(i + 1).and_then(|w| parse_recurrence(w)) {
+                recurrence = Some(rec);
+                i += 2;
+                continue;
+            }
+        }
+        if due.is_none() {
+            if let Some(date) = lower.strip_prefix('!').and_then(|t| resolve_date(t, today)) {
+                due = Some(date);
+                i += 1;
+                continue;
+            }
+        }
+        if scheduled.is_none() {
+            if let Some(date) = lower.strip_prefix('@').and_then(|t| resolve_date(t, today)) {
+                scheduled = Some(date);
+                i += 1;
+                continue;
+            }
+        }
+
+        kept.push(word);
+        i += 1;
+    }
+
+    // If recurrence set but no explicit deadline, auto-compute first occurrence
+    if due.is_none() {
+        if let Some(rec) = &recurrence {
+            due = Some(next_occurrence(rec, today));
+        }
+    }
+
+    ParsedTask {
+        content: kept.join(" "),
+        due_at: due.and_then(end_of_day_millis),
+        scheduled: scheduled.and_then(end_of_day_millis),
+        recurrence,
+    }
+}
+
+/// Recurrence from the word after `!every`: day, weekday, week, month or mon-sun.
+pub fn parse_recurrence(word: &str) -> Option<Recurrence> {
+    let pattern = word.to_lowercase();
+    let (kind, day_of_week) = match pattern.as_str() {
+        "day" => (RecurrenceType::Daily, None),
+        "weekday" => (RecurrenceType::Weekdays, None),
+        "week" => (RecurrenceType::Weekly, None),
+        "month" => (RecurrenceType::Monthly, None),
+        other => {
+            let day = WEEKDAYS.iter().position(|d| *d == other)?;
+            (RecurrenceType::Weekly, Some(day as u32))
+        }
+    };
+    Some(Recurrence { kind, day_of_week })
+}
+
+/// Resolve a date token (without its `!`/`@` sigil) relative to `today`.
+pub fn resolve_date(token: &str, today: NaiveDate) -> Option<NaiveDate> {
+    match token {
+        "today" => Some(today),
+        "tomorrow" | "tom" => today.checked_add_days(Days::new(1)),
+        _ => {
+            if let Some(day) = WEEKDAYS.iter().position(|d| *d == token) {
+                return Some(next_weekday(today, day as u32));
+            }
+            let (month, day) = token.split_once('/')?;
+            if month.is_empty() || month.len() > 2 || day.is_empty() || day.len() > 2 {
+                return None;
+            }
+            let month: u32 = month.parse().ok()?;
+            let day: u32 = day.parse().ok()?;
+            let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+            if date < today {
+                NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+            } else {
+                Some(date)
+            }
+        }
+    }
+}
+
+/// Next due date after `today` for a recurring task.
+pub fn next_occurrence(recurrence: &Recurrence, today: NaiveDate) -> NaiveDate {
+    match recurrence.kind {
+        RecurrenceType::Daily => today + Days::new(1),
+        RecurrenceType::Weekdays => {
+            let mut date = today + Days::new(1);
+            while matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+                date = date + Days::new(1);
+            }
+            date
+        }
+        RecurrenceType::Weekly => match recurrence.day_of_week {
+            Some(day) => next_weekday(today, day),
+            None => today + Days::new(7),
+        },
+        RecurrenceType::Monthly => today + Months::new(1),
+    }
+}
+
+/// Strictly-after `today` occurrence of `day` (0=Sun..6=Sat).
+fn next_weekday(today: NaiveDate, day: u32) -> NaiveDate {
+    let current = today.weekday().num_days_from_sunday() as i64;
+    let mut diff = day as i64 - current;
+    if diff <= 0 {
+        diff += 7;
+    }
+    today + Days::new(diff as u64)
+}
+
+/// Timestamp (ms) of 23:59:59 local time on `date`, the convention used for due dates.
+pub fn end_of_day_millis(date: NaiveDate) -> Option<i64> {
+    let naive = date.and_hms_opt(23, 59, 59)?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.timestamp_millis())
+}
@@ -0,0 +1,83 @@
+//! Quick-capture mini window: a single borderless input line for adding a task
+//! without opening the full visor. It never touches the main window's state;
+//! tasks go through the same path as `POST /api/tasks` and the visor picks them
+//! up via `data-changed`.
+
+use serde_json::Value;
+use tauri::{
+    AppHandle, Emitter, Manager, PhysicalPosition, Position, WebviewUrl, WebviewWindowBuilder,
+};
+
+use crate::{parser, storage, tasks};
+
+pub const QUICK_CAPTURE_LABEL: &str = "quick-capture";
+
+const WIDTH: f64 = 560.0;
+const HEIGHT: f64 = 56.0;
+
+/// Open the quick-capture window, or close it if it is already open.
+pub fn toggle(app: &AppHandle) {
+    if app.get_webview_window(QUICK_CAPTURE_LABEL).is_some() {
+        close(app);
+    } else {
+        open(app);
+    }
+}
+
+fn open(app: &AppHandle) {
+    let url = WebviewUrl::App("index.html?window=quick-capture".into());
+    let window = match WebviewWindowBuilder::new(app, QUICK_CAPTURE_LABEL, url)
+        .title("Visor Quick Capture")
+        .inner_size(WIDTH, HEIGHT)
+        .decorations(false)
+        .transparent(true)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .resizable(false)
+        .visible(false)
+        .build()
+    {
+        Ok(w) => w,
+        Err(e) => {
+            eprintln!("Failed to open quick capture window: {}", e);
+            return;
+        }
+    };
+
+    // Horizontally centered, a third of the way down the cursor's monitor
+    if let Some(monitor) = crate::active_monitor(&window) {
+        let scale = monitor.scale_factor();
+        let size = monitor.size();
+        let pos = monitor.position();
+        let width = (WIDTH * scale) as i32;
+        let _ = window.set_position(Position::Physical(PhysicalPosition {
+            x: pos.x + (size.width as i32 - width) / 2,
+            y: pos.y + size.height as i32 / 3,
+        }));
+    }
+
+    crate::activate_window(&window);
+}
+
+fn close(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(QUICK_CAPTURE_LABEL) {
+        let _ = window.close();
+    }
+}
+
+/// Create a task from the quick-capture line (`project: content !due @sched`) and close.
+#[tauri::command]
+pub fn quick_capture_submit(app: AppHandle, input: String) -> Result<Value, String> {
+    storage::ensure_unlocked()?;
+    let (project, content) = parser::split_project_prefix(&input);
+    let slug = project.as_deref().unwrap_or("inbox");
+    let task = storage::update_json(|data| tasks::create_task(data, &content, slug))?;
+    let _ = app.emit("data-changed", ());
+    close(&app);
+    Ok(task)
+}
+
+#[tauri::command]
+pub fn quick_capture_close(app: AppHandle) {
+    close(&app);
+}
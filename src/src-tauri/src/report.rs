@@ -0,0 +1,190 @@
+//! Standup reports, served as `GET /api/report`: per project, the tasks
+//! completed, started (moved to DOING) and created over a range, plus the
+//! journal entries written in it, with a Markdown rendering for pasting into
+//! chat.
+
+use chrono::{DateTime, Days, Local, NaiveDate, TimeZone};
+use serde_json::{json, Value};
+
+use crate::model::{LogEntry, Project, Task, TaskStatus};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportRange {
+    Today,
+    Yesterday,
+    /// Today and the six days before it.
+    Week,
+}
+
+impl ReportRange {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "today" => Some(ReportRange::Today),
+            "yesterday" => Some(ReportRange::Yesterday),
+            "week" => Some(ReportRange::Week),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportRange::Today => "today",
+            ReportRange::Yesterday => "yesterday",
+            ReportRange::Week => "week",
+        }
+    }
+
+    /// First and last local day covered.
+    fn days(&self, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            ReportRange::Today => (today, today),
+            ReportRange::Yesterday => {
+                let yesterday = today - Days::new(1);
+                (yesterday, yesterday)
+            }
+            ReportRange::Week => (today - Days::new(6), today),
+        }
+    }
+}
+
+/// Local midnight starting `day`, in ms.
+fn day_start(day: NaiveDate) -> i64 {
+    day.and_hms_opt(0, 0, 0)
+        .and_then(|t| Local.from_local_datetime(&t).earliest())
+        .map_or(0, |dt| dt.timestamp_millis())
+}
+
+fn local(ms: i64) -> Option<DateTime<Local>> {
+    Local.timestamp_millis_opt(ms).single()
+}
+
+/// One project's activity. Each task is listed once, under the furthest it got.
+struct ProjectReport {
+    project: Project,
+    completed: Vec<Task>,
+    started: Vec<Task>,
+    created: Vec<Task>,
+    journal: Vec<LogEntry>,
+}
+
+/// The report for `range` as of `now`, optionally for one project slug.
+pub fn report(data: &Value, range: ReportRange, project: Option<&str>, now: DateTime<Local>) -> Result<Value, String> {
+    let (first, last) = range.days(now.date_naive());
+    let (from, to) = (day_start(first), day_start(last + Days::new(1)));
+    let within = |ms: Option<i64>| ms.is_some_and(|ms| ms >= from && ms < to);
+
+    let mut projects: Vec<Project> = data
+        .get("projects")
+        .and_then(|p| p.as_object())
+        .map(|p| p.values().filter_map(|v| serde_json::from_value(v.clone()).ok()).collect())
+        .unwrap_or_default();
+    if let Some(slug) = project {
+        projects.retain(|p| p.slug == slug);
+        if projects.is_empty() {
+            return Err(format!("project not found: {}", slug));
+        }
+    }
+    projects.sort_by_key(|p| (!p.is_inbox, p.created_at));
+
+    let tasks: Vec<Task> = data
+        .get("tasks")
+        .and_then(|t| t.as_object())
+        .map(|t| t.values().filter_map(|v| serde_json::from_value(v.clone()).ok()).collect())
+        .unwrap_or_default();
+    let entries: Vec<LogEntry> = data
+        .get("logEntries")
+        .and_then(|l| l.as_array())
+        .map(|l| l.iter().filter_map(|v| serde_json::from_value(v.clone()).ok()).collect())
+        .unwrap_or_default();
+
+    let reports: Vec<ProjectReport> = projects
+        .into_iter()
+        .map(|project| {
+            let mut report = ProjectReport {
+                project,
+                completed: Vec::new(),
+                started: Vec::new(),
+                created: Vec::new(),
+                journal: Vec::new(),
+            };
+            for task in tasks.iter().filter(|t| t.project_id == report.project.id) {
+                if task.status == TaskStatus::Done && within(task.completed_at) {
+                    report.completed.push(task.clone());
+                } else if within(task.started_at) {
+                    report.started.push(task.clone());
+                } else if within(Some(task.created_at)) {
+                    report.created.push(task.clone());
+                }
+            }
+            report.completed.sort_by_key(|t| t.completed_at);
+            report.started.sort_by_key(|t| t.started_at);
+            report.created.sort_by_key(|t| t.created_at);
+            report.journal = entries
+                .iter()
+                .filter(|e| e.project_id == report.project.id && within(Some(e.created_at)))
+                .cloned()
+                .collect();
+            report.journal.sort_by_key(|e| e.created_at);
+            report
+        })
+        .filter(|r| !(r.completed.is_empty() && r.started.is_empty() && r.created.is_empty() && r.journal.is_empty()))
+        .collect();
+
+    let count = |f: fn(&ProjectReport) -> usize| reports.iter().map(f).sum::<usize>();
+    Ok(json!({
+        "range": range.as_str(),
+        "from": from,
+        "to": to,
+        "totals": {
+            "completed": count(|r| r.completed.len()),
+            "started": count(|r| r.started.len()),
+            "created": count(|r| r.created.len()),
+            "journal": count(|r| r.journal.len()),
+        },
+        "projects": reports.iter().map(|r| json!({
+            "id": r.project.id,
+            "name": r.project.name,
+            "slug": r.project.slug,
+            "color": r.project.color,
+            "completed": r.completed,
+            "started": r.started,
+            "created": r.created,
+            "journal": r.journal,
+        })).collect::<Vec<_>>(),
+        "markdown": markdown(range, first, last, &reports),
+    }))
+}
+
+fn markdown(range: ReportRange, first: NaiveDate, last: NaiveDate, reports: &[ProjectReport]) -> String {
+    let title = match range {
+        ReportRange::Today => format!("**Today** ({})", first.format("%a, %b %-d")),
+        ReportRange::Yesterday => format!("**Yesterday** ({})", first.format("%a, %b %-d")),
+        ReportRange::Week => format!("**Last 7 days** ({} – {})", first.format("%b %-d"), last.format("%b %-d")),
+    };
+    let mut out = vec![title];
+    if reports.is_empty() {
+        out.push(String::new());
+        out.push("Nothing recorded.".to_string());
+    }
+
+    for report in reports {
+        out.push(String::new());
+        out.push(format!("**{}**", report.project.name));
+        for task in &report.completed {
+            out.push(format!("- Done: {}", task.content));
+        }
+        for task in &report.started {
+            out.push(format!("- Started: {}", task.content));
+        }
+        for task in &report.created {
+            out.push(format!("- Added: {}", task.content));
+        }
+        for entry in &report.journal {
+            // Several days: keep the weekday so entries can be told apart
+            let format = if range == ReportRange::Week { "%a %H:%M" } else { "%H:%M" };
+            let time = local(entry.created_at).map(|dt| dt.format(format).to_string()).unwrap_or_default();
+            out.push(format!("- {} {}", time, entry.content));
+        }
+    }
+    out.join("\n") + "\n"
+}
@@ -0,0 +1,537 @@
+//! Full-text search over tasks (archived ones included) and journal entries,
+//! served by `GET /api/search` and the `search` command.
+//!
+//! The index is built from `data.json` on the first search, then kept current
+//! by `refresh`, which every write of `data.json` calls: only records that
+//! changed since the last refresh are re-tokenized. Query syntax:
+//!
+//!   reprt "weekly sync" project:work status:todo in:notes type:task is:archived
+//!
+//! Words match whole words, prefixes, substrings and small typos; quoted
+//! phrases match exactly. Every word and phrase must match.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::model::{LogEntry, Task, TaskStatus};
+use crate::storage;
+
+const DEFAULT_LIMIT: usize = 30;
+const MAX_LIMIT: usize = 200;
+
+/// `None` until the first search, so processes that never search (the CLI,
+/// a webview that never opens search) don't pay for indexing.
+static INDEX: Mutex<Option<SearchIndex>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Task,
+    Log,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Field {
+    Content,
+    Notes,
+}
+
+impl Field {
+    fn weight(&self) -> f32 {
+        match self {
+            Field::Content => 1.0,
+            Field::Notes => 0.7,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Query {
+    /// Normalized words.
+    terms: Vec<String>,
+    /// Quoted phrases, as normalized words.
+    phrases: Vec<Vec<String>>,
+    project: Option<String>,
+    status: Option<TaskStatus>,
+    field: Option<Field>,
+    kind: Option<Kind>,
+    archived: Option<bool>,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut query = Query::default();
+        let mut rest = input.trim();
+        while !rest.is_empty() {
+            if let Some(quoted) = rest.strip_prefix('"') {
+                let (phrase, after) = quoted.split_once('"').unwrap_or((quoted, ""));
+                let words: Vec<String> = tokenize(phrase).into_iter().map(|t| t.text).collect();
+                match words.len() {
+                    0 => {}
+                    1 => query.terms.extend(words),
+                    _ => query.phrases.push(words),
+                }
+                rest = after.trim_start();
+                continue;
+            }
+
+            let end = rest.find(|c: char| c.is_whitespace() || c == '"').unwrap_or(rest.len());
+            let word = &rest[..end];
+            rest = rest[end..].trim_start();
+            if !query.filter(word)? {
+                query.terms.extend(tokenize(word).into_iter().map(|t| t.text));
+            }
+        }
+        Ok(query)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+            && self.phrases.is_empty()
+            && self.project.is_none()
+            && self.status.is_none()
+            && self.field.is_none()
+            && self.kind.is_none()
+            && self.archived.is_none()
+    }
+
+    /// Apply `key:value` if it's a filter. Unknown keys are plain text.
+    fn filter(&mut self, word: &str) -> Result<bool, String> {
+        let Some((key, value)) = word.split_once(':') else { return Ok(false) };
+        let value = value.to_lowercase();
+        match key.to_lowercase().as_str() {
+            "project" => self.project = Some(value),
+            "status" => {
+                self.status = Some(TaskStatus::parse(&value).ok_or(format!("unknown status: {}", value))?);
+            }
+            "in" => {
+                self.field = Some(match value.as_str() {
+                    "content" => Field::Content,
+                    "notes" => Field::Notes,
+                    _ => return Err(format!("in: must be content or notes, not {}", value)),
+                })
+            }
+            "type" => {
+                self.kind = Some(match value.as_str() {
+                    "task" => Kind::Task,
+                    "log" | "journal" => Kind::Log,
+                    _ => return Err(format!("type: must be task or log, not {}", value)),
+                })
+            }
+            "is" => {
+                self.archived = Some(match value.as_str() {
+                    "archived" => true,
+                    "active" => false,
+                    _ => return Err(format!("is: must be archived or active, not {}", value)),
+                })
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    /// Lowercased.
+    text: String,
+    /// UTF-16 offsets into the original text.
+    start: usize,
+    end: usize,
+}
+
+/// Runs of letters and digits, lowercased, with their positions.
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut start = 0;
+    let mut offset = 0;
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            if current.is_empty() {
+                start = offset;
+            }
+            current.extend(c.to_lowercase());
+        } else if !current.is_empty() {
+            tokens.push(Token { text: std::mem::take(&mut current), start, end: offset });
+        }
+        offset += c.len_utf16();
+    }
+    if !current.is_empty() {
+        tokens.push(Token { text: current, start, end: offset });
+    }
+    tokens
+}
+
+/// Edit distance between `a` and `b`, if it's at most `max`.
+fn levenshtein(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        if current.iter().min().is_some_and(|m| *m > max) {
+            return None;
+        }
+        previous = current;
+    }
+    Some(previous[b.len()]).filter(|d| *d <= max)
+}
+
+/// How well query word `term` matches indexed word `token`, from 1.0 (the
+/// same word) down; `None` if it doesn't.
+fn match_quality(term: &str, token: &str) -> Option<f32> {
+    if term == token {
+        return Some(1.0);
+    }
+    let term_chars: Vec<char> = term.chars().collect();
+    let token_chars: Vec<char> = token.chars().collect();
+    if token.starts_with(term) {
+        return Some(0.6 + 0.3 * term_chars.len() as f32 / token_chars.len() as f32);
+    }
+    if term_chars.len() < 3 {
+        return None;
+    }
+    if token.contains(term) {
+        return Some(0.5);
+    }
+
+    let max = match term_chars.len() {
+        0..=3 => return None,
+        4..=7 => 1,
+        _ => 2,
+    };
+    if let Some(distance) = levenshtein(&term_chars, &token_chars, max) {
+        return Some(0.7 - 0.15 * distance as f32);
+    }
+    // A typo in a word that's still being typed
+    let prefix = &token_chars[..token_chars.len().min(term_chars.len())];
+    levenshtein(&term_chars, prefix, max).map(|distance| 0.45 - 0.1 * distance as f32)
+}
+
+struct Doc {
+    kind: Kind,
+    id: String,
+    project_id: String,
+    status: Option<TaskStatus>,
+    archived: bool,
+    created_at: i64,
+    /// Hash of the stored record, to skip unchanged ones on refresh.
+    fingerprint: u64,
+    fields: Vec<(Field, Vec<Token>)>,
+    record: Value,
+}
+
+impl Doc {
+    fn key(&self) -> (Kind, String) {
+        (self.kind, self.id.clone())
+    }
+
+    fn tokens(&self) -> impl Iterator<Item = &Token> {
+        self.fields.iter().flat_map(|(_, tokens)| tokens)
+    }
+
+    fn rank_factor(&self) -> f32 {
+        if self.archived {
+            0.6
+        } else if matches!(self.status, Some(TaskStatus::Done | TaskStatus::Cancelled)) {
+            0.8
+        } else if self.kind == Kind::Log {
+            0.9
+        } else {
+            1.0
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldMatch {
+    pub field: Field,
+    /// Sorted `[start, end)` offsets into the field's text, in UTF-16 code
+    /// units so they index JavaScript strings directly.
+    pub ranges: Vec<[usize; 2]>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    #[serde(rename = "type")]
+    pub kind: Kind,
+    pub id: String,
+    pub project_id: String,
+    pub score: f32,
+    /// The task or journal entry as stored.
+    pub item: Value,
+    pub matches: Vec<FieldMatch>,
+}
+
+#[derive(Default)]
+pub struct SearchIndex {
+    docs: HashMap<(Kind, String), Doc>,
+    /// Word → documents containing it.
+    postings: HashMap<String, HashSet<(Kind, String)>>,
+    /// Project id → slug, for `project:` filters.
+    project_slugs: HashMap<String, String>,
+}
+
+fn fingerprint(record: &Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    record.to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+impl SearchIndex {
+    /// Bring the index in line with `data`, re-indexing only changed records.
+    pub fn update(&mut self, data: &Value) {
+        self.project_slugs = data
+            .get("projects")
+            .and_then(|p| p.as_object())
+            .map(|projects| {
+                projects
+                    .iter()
+                    .filter_map(|(id, p)| Some((id.clone(), p.get("slug")?.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut live = HashSet::new();
+        let tasks = data.get("tasks").and_then(|t| t.as_object()).into_iter().flat_map(|t| t.values());
+        for record in tasks {
+            let Ok(task) = serde_json::from_value::<Task>(record.clone()) else { continue };
+            let key = (Kind::Task, task.id.clone());
+            let hash = fingerprint(record);
+            live.insert(key.clone());
+            if self.docs.get(&key).is_some_and(|d| d.fingerprint == hash) {
+                continue;
+            }
+            let mut fields = vec![(Field::Content, tokenize(&task.content))];
+            if let Some(notes) = &task.notes {
+                fields.push((Field::Notes, tokenize(notes)));
+            }
+            self.insert(Doc {
+                kind: Kind::Task,
+                id: task.id,
+                project_id: task.project_id,
+                status: Some(task.status),
+                archived: task.archived,
+                created_at: task.created_at,
+                fingerprint: hash,
+                fields,
+                record: record.clone(),
+            });
+        }
+
+        let entries = data.get("logEntries").and_then(|l| l.as_array()).into_iter().flatten();
+        for record in entries {
+            let Ok(entry) = serde_json::from_value::<LogEntry>(record.clone()) else { continue };
+            let key = (Kind::Log, entry.id.clone());
+            let hash = fingerprint(record);
+            live.insert(key.clone());
+            if self.docs.get(&key).is_some_and(|d| d.fingerprint == hash) {
+                continue;
+            }
+            self.insert(Doc {
+                kind: Kind::Log,
+                id: entry.id,
+                project_id: entry.project_id,
+                status: None,
+                archived: false,
+                created_at: entry.created_at,
+                fingerprint: hash,
+                fields: vec![(Field::Content, tokenize(&entry.content))],
+                record: record.clone(),
+            });
+        }
+
+        let removed: Vec<(Kind, String)> = self.docs.keys().filter(|k| !live.contains(*k)).cloned().collect();
+        for key in removed {
+            self.remove(&key);
+        }
+    }
+
+    fn insert(&mut self, doc: Doc) {
+        let key = doc.key();
+        self.remove(&key);
+        for token in doc.tokens() {
+            self.postings.entry(token.text.clone()).or_default().insert(key.clone());
+        }
+        self.docs.insert(key, doc);
+    }
+
+    fn remove(&mut self, key: &(Kind, String)) {
+        let Some(doc) = self.docs.remove(key) else { return };
+        for token in doc.tokens() {
+            if let Some(keys) = self.postings.get_mut(&token.text) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.postings.remove(&token.text);
+                }
+            }
+        }
+    }
+
+    fn passes_filters(&self, doc: &Doc, query: &Query) -> bool {
+        query.kind.is_none_or(|k| doc.kind == k)
+            && query.archived.is_none_or(|a| doc.archived == a)
+            && query.status.is_none_or(|s| doc.status == Some(s))
+            && query
+                .project
+                .as_ref()
+                .is_none_or(|slug| self.project_slugs.get(&doc.project_id) == Some(slug))
+    }
+
+    pub fn search(&self, query: &Query, limit: usize) -> Vec<SearchHit> {
+        // Each term's matching vocabulary, with its match quality
+        let vocabulary: Vec<HashMap<&str, f32>> = query
+            .terms
+            .iter()
+            .map(|term| {
+                self.postings
+                    .keys()
+                    .filter_map(|token| match_quality(term, token).map(|q| (token.as_str(), q)))
+                    .collect()
+            })
+            .collect();
+
+        // Documents containing something for every term and every phrase word
+        let mut candidates: Option<HashSet<&(Kind, String)>> = None;
+        let phrase_words = query.phrases.iter().flatten().map(|w| HashMap::from([(w.as_str(), 1.0)]));
+        for matches in vocabulary.iter().cloned().chain(phrase_words) {
+            let keys: HashSet<&(Kind, String)> = matches
+                .keys()
+                .filter_map(|token| self.postings.get(*token))
+                .flatten()
+                .collect();
+            candidates = Some(match candidates {
+                Some(previous) => previous.intersection(&keys).copied().collect(),
+                None => keys,
+            });
+        }
+        let candidates: Vec<&Doc> = match candidates {
+            Some(keys) => keys.into_iter().filter_map(|k| self.docs.get(k)).collect(),
+            None => self.docs.values().collect(),
+        };
+
+        let mut hits: Vec<(f32, i64, SearchHit)> = candidates
+            .into_iter()
+            .filter(|doc| self.passes_filters(doc, query))
+            .filter_map(|doc| score(doc, query, &vocabulary))
+            .collect();
+        hits.sort_by(|a, b| b.0.total_cmp(&a.0).then(b.1.cmp(&a.1)));
+        hits.into_iter().take(limit).map(|(_, _, hit)| hit).collect()
+    }
+}
+
+/// Score `doc` against every term and phrase (their mean match quality,
+/// weighted by field) and collect the matched ranges. `None` if anything
+/// doesn't match in the searched fields.
+fn score(doc: &Doc, query: &Query, vocabulary: &[HashMap<&str, f32>]) -> Option<(f32, i64, SearchHit)> {
+    let fields: Vec<&(Field, Vec<Token>)> =
+        doc.fields.iter().filter(|(f, _)| query.field.is_none_or(|q| *f == q)).collect();
+    let mut ranges: Vec<Vec<[usize; 2]>> = vec![Vec::new(); fields.len()];
+    let mut scores = Vec::new();
+
+    for matches in vocabulary {
+        let mut best: Option<f32> = None;
+        for (i, (field, tokens)) in fields.iter().enumerate() {
+            for token in tokens {
+                if let Some(quality) = matches.get(token.text.as_str()) {
+                    best = Some(best.unwrap_or(0.0).max(quality * field.weight()));
+                    ranges[i].push([token.start, token.end]);
+                }
+            }
+        }
+        scores.push(best?);
+    }
+
+    for phrase in &query.phrases {
+        let mut best: Option<f32> = None;
+        for (i, (field, tokens)) in fields.iter().enumerate() {
+            for window in tokens.windows(phrase.len()) {
+                if window.iter().zip(phrase).all(|(t, w)| &t.text == w) {
+                    best = Some(field.weight());
+                    ranges[i].push([window[0].start, window[window.len() - 1].end]);
+                }
+            }
+        }
+        scores.push(best?);
+    }
+
+    let relevance = if scores.is_empty() { 1.0 } else { scores.iter().sum::<f32>() / scores.len() as f32 };
+    let score = (relevance * doc.rank_factor() * 1000.0).round() / 1000.0;
+    let matches = fields
+        .iter()
+        .zip(ranges)
+        .filter(|(_, r)| !r.is_empty())
+        .map(|((field, _), mut ranges)| {
+            ranges.sort_unstable();
+            ranges.dedup();
+            FieldMatch { field: *field, ranges }
+        })
+        .collect();
+
+    Some((
+        score,
+        doc.created_at,
+        SearchHit {
+            kind: doc.kind,
+            id: doc.id.clone(),
+            project_id: doc.project_id.clone(),
+            score,
+            item: doc.record.clone(),
+            matches,
+        },
+    ))
+}
+
+/// Re-index after `data.json` was written with `data`. A no-op until the
+/// first search builds the index.
+pub fn refresh(data: &Value) {
+    let mut guard = INDEX.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(index) = guard.as_mut() {
+        index.update(data);
+    }
+}
+
+/// `refresh` for a `data.json` that's only at hand as text (the webview's saves).
+pub fn refresh_raw(raw: &str) {
+    let mut guard = INDEX.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(index) = guard.as_mut() {
+        if let Ok(data) = serde_json::from_str::<Value>(raw) {
+            index.update(&data);
+        }
+    }
+}
+
+/// Run `input` against the index, building it from `data.json` if needed.
+pub fn find(input: &str, limit: Option<usize>) -> Result<Vec<SearchHit>, String> {
+    let query = Query::parse(input)?;
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let mut guard = INDEX.lock().unwrap_or_else(|e| e.into_inner());
+    let index = guard.get_or_insert_with(|| {
+        let mut index = SearchIndex::default();
+        index.update(&storage::load_json());
+        index
+    });
+    Ok(index.search(&query, limit))
+}
+
+#[tauri::command]
+pub fn search(query: String, limit: Option<usize>) -> Result<Vec<SearchHit>, String> {
+    find(&query, limit)
+}
@@ -0,0 +1,413 @@
+//! User settings, stored in `~/.visor/settings.json` apart from the task data
+//! so API writes and UI writes don't overwrite each other.
+//!
+//! The file carries a schema `version`; missing or invalid values take their
+//! defaults. Settings saved inside `data.json` by older versions are moved
+//! here on first load. Every change goes through [`update`], which validates
+//! it, and is announced with a `settings-changed` event carrying the new
+//! settings.
+
+use std::fs;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_global_shortcut::Shortcut;
+
+use crate::geometry::VisorGeometry;
+use crate::import::ics::CalendarImport;
+use crate::storage;
+
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// Serializes [`update`], so concurrent patches don't lose each other's changes.
+static UPDATING: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    pub version: u32,
+    pub general: GeneralSettings,
+    pub keybindings: Keybindings,
+    pub window: VisorGeometry,
+    pub calendars: Vec<CalendarImport>,
+    pub api: ApiSettings,
+    pub snapshots: SnapshotSettings,
+    pub history: HistorySettings,
+    pub sync: SyncSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            general: GeneralSettings::default(),
+            keybindings: Keybindings::default(),
+            window: VisorGeometry::default(),
+            calendars: Vec::new(),
+            api: ApiSettings::default(),
+            snapshots: SnapshotSettings::default(),
+            history: HistorySettings::default(),
+            sync: SyncSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GeneralSettings {
+    pub show_welcome: bool,
+}
+
+impl Default for GeneralSettings {
+    fn default() -> Self {
+        Self { show_welcome: true }
+    }
+}
+
+/// Global shortcuts, as accepted by the global-shortcut plugin (`Ctrl+Shift+Space`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Keybindings {
+    pub toggle_visor: String,
+    pub quick_capture: String,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Self {
+            toggle_visor: "Ctrl+`".to_string(),
+            quick_capture: "Ctrl+Shift+Space".to_string(),
+        }
+    }
+}
+
+/// The local HTTP API. The port is read at startup.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ApiSettings {
+    pub port: u16,
+}
+
+impl Default for ApiSettings {
+    fn default() -> Self {
+        Self { port: 8745 }
+    }
+}
+
+/// Snapshot retention: the latest snapshot of each of the last `hourly`
+/// hours, `daily` days and `weekly` weeks that have one is kept.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SnapshotSettings {
+    pub hourly: u32,
+    pub daily: u32,
+    pub weekly: u32,
+}
+
+impl Default for SnapshotSettings {
+    fn default() -> Self {
+        Self { hourly: 24, daily: 7, weekly: 8 }
+    }
+}
+
+/// Git version history of the data directory, committed once writes have
+/// been quiet for `quiet_seconds`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistorySettings {
+    pub enabled: bool,
+    pub quiet_seconds: u64,
+}
+
+impl Default for HistorySettings {
+    fn default() -> Self {
+        Self { enabled: false, quiet_seconds: 30 }
+    }
+}
+
+/// Multi-device sync: the shared folder holding every device's change log,
+/// or empty when sync is off.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SyncSettings {
+    pub folder: String,
+}
+
+impl Settings {
+    /// Address the API server listens on (and `visor-cli` connects to).
+    pub fn api_addr(&self) -> String {
+        format!("127.0.0.1:{}", self.api.port)
+    }
+
+    /// Every problem found, as `field: message`.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        if let Err(e) = self.window.validate() {
+            errors.push(format!("window: {}", e));
+        }
+        let toggle = Shortcut::from_str(&self.keybindings.toggle_visor);
+        let capture = Shortcut::from_str(&self.keybindings.quick_capture);
+        for (field, keys, parsed) in [
+            ("toggleVisor", &self.keybindings.toggle_visor, &toggle),
+            ("quickCapture", &self.keybindings.quick_capture, &capture),
+        ] {
+            if parsed.is_err() {
+                errors.push(format!("keybindings.{}: not a valid shortcut: {}", field, keys));
+            }
+        }
+        if let (Ok(toggle), Ok(capture)) = (toggle, capture) {
+            if toggle == capture {
+                errors.push("keybindings: toggleVisor and quickCapture must differ".to_string());
+            }
+        }
+        if self.api.port == 0 {
+            errors.push("api.port must be between 1 and 65535".to_string());
+        }
+        if !(1..=3600).contains(&self.history.quiet_seconds) {
+            errors.push("history.quietSeconds must be between 1 and 3600".to_string());
+        }
+        if !self.sync.folder.is_empty() {
+            let folder = storage::expand_home(&self.sync.folder);
+            if !folder.is_absolute() {
+                errors.push("sync.folder must be an absolute path or start with ~/".to_string());
+            } else if folder.starts_with(storage::data_path()) {
+                errors.push("sync.folder must be outside ~/.visor".to_string());
+            }
+        }
+        for (i, calendar) in self.calendars.iter().enumerate() {
+            if !(1..=730).contains(&calendar.lookahead_days) {
+                errors.push(format!("calendars[{}].lookaheadDays must be between 1 and 730", i));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Bring an older file up to the current schema. Version 0 is the `settings`
+/// object that used to live in `data.json`.
+fn migrate(mut value: Value) -> Value {
+    if !value.is_object() {
+        return value;
+    }
+    let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+    if version > SETTINGS_VERSION as u64 {
+        eprintln!("settings.json is version {}, newer than this Visor ({})", version, SETTINGS_VERSION);
+        return value;
+    }
+    if version < 1 {
+        // Those keybindings were never read (the shortcuts were fixed) and only
+        // held a placeholder
+        if let Some(obj) = value.as_object_mut() {
+            obj.remove("keybindings");
+        }
+    }
+    value["version"] = json!(SETTINGS_VERSION);
+    value
+}
+
+/// Settings from a parsed file. An invalid value falls back to its default on
+/// its own: a section's fields and the calendars are taken one by one when the
+/// whole section doesn't pass, so one typo doesn't reset everything else.
+fn parse(value: Value) -> Settings {
+    let value = migrate(value);
+    let Some(file) = value.as_object() else {
+        eprintln!("Invalid settings.json, using defaults: not an object");
+        return Settings::default();
+    };
+    let mut accepted = serde_json::to_value(Settings::default()).unwrap_or_default();
+    let mut errors = Vec::new();
+    for (section, given) in file {
+        if accepted.get(section).is_none() || accept(&mut accepted, section, given.clone()).is_ok() {
+            continue;
+        }
+        match given {
+            Value::Object(fields) => {
+                for (field, value) in fields {
+                    let mut candidate = accepted[section].clone();
+                    candidate[field] = value.clone();
+                    if let Err(e) = accept(&mut accepted, section, candidate) {
+                        errors.push(e);
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    let mut candidate = accepted[section].clone();
+                    if let Some(list) = candidate.as_array_mut() {
+                        list.push(item.clone());
+                    }
+                    if let Err(e) = accept(&mut accepted, section, candidate) {
+                        errors.push(e);
+                    }
+                }
+            }
+            _ => errors.extend(accept(&mut accepted, section, given.clone()).err()),
+        }
+    }
+    if !errors.is_empty() {
+        eprintln!("Invalid settings.json, using defaults for: {}", errors.join("; "));
+    }
+    serde_json::from_value(accepted).unwrap_or_default()
+}
+
+/// Replace `section` in `accepted` with `value` if the result is still valid.
+fn accept(accepted: &mut Value, section: &str, value: Value) -> Result<(), String> {
+    let mut candidate = accepted.clone();
+    candidate[section] = value;
+    let settings: Settings =
+        serde_json::from_value(candidate.clone()).map_err(|e| format!("{}: {}", section, e))?;
+    settings.validate().map_err(|e| e.join("; "))?;
+    *accepted = candidate;
+    Ok(())
+}
+
+/// Current settings: `settings.json`, else those in `data.json` (moved into
+/// `settings.json`), else the defaults.
+pub fn load() -> Settings {
+    try_load().unwrap_or_else(|e| {
+        eprintln!("{}, using defaults", e);
+        Settings::default()
+    })
+}
+
+/// Like [`load`], but fails when `settings.json` exists and can't be read as
+/// JSON, so [`update`] doesn't save defaults over a file the user can still fix.
+fn try_load() -> Result<Settings, String> {
+    let path = storage::data_path().join("settings.json");
+    match fs::read_to_string(&path) {
+        Ok(raw) => {
+            return serde_json::from_str::<Value>(&raw)
+                .map(parse)
+                .map_err(|e| format!("settings.json is not valid JSON, fix or remove it: {}", e));
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("can't read settings.json: {}", e)),
+    }
+
+    Ok(match storage::load_json().get("settings").filter(|s| s.is_object()) {
+        Some(legacy) => {
+            let settings = parse(legacy.clone());
+            if let Err(e) = save(&settings) {
+                eprintln!("Failed to migrate settings: {}", e);
+            }
+            settings
+        }
+        None => Settings::default(),
+    })
+}
+
+fn save(settings: &Settings) -> Result<(), String> {
+    let dir = storage::data_path();
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    storage::write_atomic(&dir.join("settings.json"), json.as_bytes())
+}
+
+/// Apply `patch` to the current settings. Sections are merged: each field
+/// given replaces the current value, `null` resets it to its default, and
+/// `calendars` is replaced as a whole.
+pub fn update(patch: &Value) -> Result<Settings, Vec<String>> {
+    let patch = patch.as_object().ok_or(vec!["settings patch must be an object".to_string()])?;
+    let _guard = UPDATING.lock().unwrap_or_else(|e| e.into_inner());
+    let current = try_load().map_err(|e| vec![e])?;
+    let known = serde_json::to_value(Settings::default()).unwrap_or_default();
+    let mut merged = serde_json::to_value(&current).map_err(|e| vec![e.to_string()])?;
+
+    let mut errors = Vec::new();
+    for (section, value) in patch {
+        match (known.get(section), value) {
+            (None, _) => errors.push(format!("unknown setting: {}", section)),
+            (Some(_), _) if section == "version" => {
+                if value.as_u64() != Some(SETTINGS_VERSION as u64) {
+                    errors.push("version is read-only".to_string());
+                }
+            }
+            (Some(Value::Object(fields)), Value::Object(changes)) => {
+                for (field, change) in changes {
+                    if !fields.contains_key(field) {
+                        errors.push(format!("unknown setting: {}.{}", section, field));
+                    } else if change.is_null() {
+                        merged[section].as_object_mut().map(|s| s.remove(field));
+                    } else {
+                        merged[section][field] = change.clone();
+                    }
+                }
+            }
+            (Some(_), _) => merged[section] = value.clone(),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let settings: Settings = serde_json::from_value(merged).map_err(|e| vec![e.to_string()])?;
+    settings.validate()?;
+    if settings != current {
+        save(&settings).map_err(|e| vec![e])?;
+    }
+    Ok(settings)
+}
+
+#[tauri::command]
+pub fn get_settings() -> Settings {
+    load()
+}
+
+#[tauri::command]
+pub fn update_settings(app: AppHandle, patch: Value) -> Result<Settings, String> {
+    let settings = update(&patch).map_err(|e| e.join("; "))?;
+    let _ = app.emit("settings-changed", &settings);
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_values_fall_back_one_at_a_time() {
+        let settings = parse(json!({
+            "version": 1,
+            "general": {"showWelcome": false},
+            "keybindings": {"toggleVisor": "Hyper+Nope", "quickCapture": "Alt+Space"},
+            "window": {"heightRatio": 2, "maxWidth": 1200},
+            "api": {"port": 0},
+            "calendars": [
+                {"path": "~/a.ics", "lookaheadDays": 30},
+                {"path": "~/b.ics", "lookaheadDays": 0},
+                {"path": "~/c.ics"}
+            ],
+            "history": "yes"
+        }));
+
+        let defaults = Settings::default();
+        assert!(!settings.general.show_welcome);
+        assert_eq!(settings.keybindings.toggle_visor, defaults.keybindings.toggle_visor);
+        assert_eq!(settings.keybindings.quick_capture, "Alt+Space");
+        assert_eq!(settings.window.height_ratio, defaults.window.height_ratio);
+        assert_eq!(settings.window.max_width, Some(1200));
+        assert_eq!(settings.api, defaults.api);
+        let calendars: Vec<(&str, u32)> =
+            settings.calendars.iter().map(|c| (c.path.as_str(), c.lookahead_days)).collect();
+        assert_eq!(calendars, [("~/a.ics", 30), ("~/c.ics", 14)]);
+        assert_eq!(settings.history, defaults.history);
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn valid_files_load_unchanged() {
+        let mut settings = Settings::default();
+        settings.keybindings.toggle_visor = "Ctrl+Shift+Space".to_string();
+        settings.keybindings.quick_capture = "Ctrl+`".to_string();
+        settings.history.enabled = true;
+        assert_eq!(parse(serde_json::to_value(&settings).unwrap()), settings);
+        assert_eq!(parse(json!([1, 2])), Settings::default());
+    }
+}
@@ -0,0 +1,385 @@
+//! Rotating snapshots of the task data in `~/.visor/snapshots/`.
+//!
+//! A snapshot is taken when the app starts (before the webview loads and
+//! migrates the data), every hour while it runs, and before imports and
+//! restores; one identical to the newest snapshot is skipped. Old ones are
+//! pruned by the `snapshots` retention settings. Files are named
+//! `<UTC time>-<reason>.json` and stored like the data file, so they are
+//! encrypted when the data is.
+
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+use crate::settings::{self, SnapshotSettings};
+use crate::{encryption, search, storage};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const SNAPSHOT_INTERVAL_MS: i64 = 60 * 60 * 1000;
+const NAME_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+static STARTUP_TAKEN: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotInfo {
+    pub id: String,
+    pub created_at: i64,
+    /// `startup`, `hourly`, `manual`, `pre-import` or `pre-restore`.
+    pub reason: String,
+    pub size: u64,
+}
+
+/// A task that differs between a snapshot and the current data.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskChange {
+    pub id: String,
+    pub content: String,
+    /// Changed fields, for tasks in both.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Counts {
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// What changed from a snapshot to now: `added` tasks exist only now,
+/// `removed` ones only in the snapshot (restoring it brings them back).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotDiff {
+    pub snapshot: SnapshotInfo,
+    pub added: Vec<TaskChange>,
+    pub removed: Vec<TaskChange>,
+    pub changed: Vec<TaskChange>,
+    pub projects: Counts,
+    pub log_entries: Counts,
+}
+
+fn snapshot_dir() -> PathBuf {
+    storage::data_path().join("snapshots")
+}
+
+fn parse_name(name: &str) -> Option<(i64, String)> {
+    let stem = name.strip_suffix(".json")?;
+    let (time, reason) = stem.split_once('-')?;
+    let created = NaiveDateTime::parse_from_str(time, NAME_FORMAT).ok()?;
+    Some((created.and_utc().timestamp_millis(), reason.to_string()))
+}
+
+/// Snapshots on disk, newest first.
+pub fn list() -> Vec<SnapshotInfo> {
+    let Ok(entries) = fs::read_dir(snapshot_dir()) else {
+        return Vec::new();
+    };
+    let mut snapshots: Vec<SnapshotInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let (created_at, reason) = parse_name(&name)?;
+            Some(SnapshotInfo {
+                id: name.trim_end_matches(".json").to_string(),
+                created_at,
+                reason,
+                size: entry.metadata().map_or(0, |m| m.len()),
+            })
+        })
+        .collect();
+    snapshots.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+    snapshots
+}
+
+fn find(id: &str) -> Result<SnapshotInfo, String> {
+    list()
+        .into_iter()
+        .find(|s| s.id == id)
+        .ok_or(format!("snapshot not found: {}", id))
+}
+
+/// The data as saved in snapshot `id`.
+fn read(id: &str) -> Result<String, String> {
+    let bytes = fs::read(snapshot_dir().join(format!("{}.json", id))).map_err(|e| e.to_string())?;
+    encryption::open_raw(&bytes)
+}
+
+/// Snapshot the current data, unless there is none or it matches the newest
+/// snapshot. Prunes afterwards.
+pub fn take(reason: &str) -> Result<Option<SnapshotInfo>, String> {
+    let Some(text) = encryption::read_raw()? else {
+        return Ok(None);
+    };
+    // Compared as JSON: the webview and the backend format it differently
+    let parse = |text: &str| serde_json::from_str::<Value>(text).ok();
+    if let Some(newest) = list().first() {
+        if read(&newest.id).is_ok_and(|t| parse(&t) == parse(&text)) {
+            return Ok(None);
+        }
+    }
+
+    fs::create_dir_all(snapshot_dir()).map_err(|e| e.to_string())?;
+    let now = Utc::now();
+    let id = format!("{}-{}", now.format(NAME_FORMAT), reason);
+    let path = snapshot_dir().join(format!("{}.json", id));
+    let bytes = encryption::seal_raw(&text)?;
+    storage::write_atomic(&path, &bytes)?;
+    prune(&settings::load().snapshots);
+
+    Ok(Some(SnapshotInfo {
+        id,
+        created_at: now.timestamp_millis(),
+        reason: reason.to_string(),
+        size: bytes.len() as u64,
+    }))
+}
+
+/// `take`, reporting failures instead of returning them, for snapshots
+/// taken on the side of another operation.
+pub fn take_quietly(reason: &str) {
+    if let Err(e) = take(reason) {
+        eprintln!("Snapshot ({}): {}", reason, e);
+    }
+}
+
+/// The first snapshot of this run, taken once the data can be read.
+pub fn take_startup() {
+    if !encryption::is_locked() && !STARTUP_TAKEN.swap(true, Ordering::SeqCst) {
+        take_quietly("startup");
+    }
+}
+
+/// Ids to keep: those taken within an hour of the newest, so a restore can
+/// always be undone, plus the latest of each of the most recent `hourly`
+/// hours, `daily` days and `weekly` ISO weeks (local time) that have one.
+/// `snapshots` is newest first.
+fn retained(snapshots: &[SnapshotInfo], policy: &SnapshotSettings) -> HashSet<String> {
+    let newest = snapshots.first().map_or(0, |s| s.created_at);
+    let mut keep: HashSet<String> = snapshots
+        .iter()
+        .take_while(|s| newest - s.created_at < SNAPSHOT_INTERVAL_MS)
+        .map(|s| s.id.clone())
+        .collect();
+    for (count, bucket) in [(policy.hourly, "%Y-%m-%d %H"), (policy.daily, "%Y-%m-%d"), (policy.weekly, "%G-W%V")] {
+        let mut seen = BTreeSet::new();
+        for snapshot in snapshots {
+            let Some(time) = Local.timestamp_millis_opt(snapshot.created_at).single() else {
+                continue;
+            };
+            let key = time.format(bucket).to_string();
+            if seen.contains(&key) {
+                continue;
+            }
+            if seen.len() >= count as usize {
+                break;
+            }
+            seen.insert(key);
+            keep.insert(snapshot.id.clone());
+        }
+    }
+    keep
+}
+
+fn prune(policy: &SnapshotSettings) {
+    let snapshots = list();
+    let keep = retained(&snapshots, policy);
+    for snapshot in snapshots.iter().filter(|s| !keep.contains(&s.id)) {
+        if let Err(e) = fs::remove_file(snapshot_dir().join(format!("{}.json", snapshot.id))) {
+            eprintln!("Failed to prune snapshot {}: {}", snapshot.id, e);
+        }
+    }
+}
+
+/// Rewrite every snapshot with `f`, when encryption is turned on or off or
+/// the passphrase changes. A snapshot that can't be converted is left as is.
+pub(crate) fn convert(f: impl Fn(&[u8]) -> Result<Vec<u8>, String>) {
+    for snapshot in list() {
+        let path = snapshot_dir().join(format!("{}.json", snapshot.id));
+        let result = fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| f(&bytes))
+            .and_then(|bytes| storage::write_atomic(&path, &bytes));
+        if let Err(e) = result {
+            eprintln!("Failed to convert snapshot {}: {}", snapshot.id, e);
+        }
+    }
+}
+
+fn tasks_of(data: &Value) -> serde_json::Map<String, Value> {
+    data.get("tasks").and_then(|t| t.as_object()).cloned().unwrap_or_default()
+}
+
+fn task_change(id: &str, task: &Value, fields: Vec<String>) -> TaskChange {
+    TaskChange {
+        id: id.to_string(),
+        content: task.get("content").and_then(|c| c.as_str()).unwrap_or_default().to_string(),
+        fields,
+    }
+}
+
+/// Ids in `before` and `after`, as (added, removed) counts.
+fn count_ids(before: HashSet<String>, after: HashSet<String>) -> Counts {
+    Counts {
+        added: after.difference(&before).count(),
+        removed: before.difference(&after).count(),
+    }
+}
+
+fn compare(snapshot: &Value, current: &Value) -> (Vec<TaskChange>, Vec<TaskChange>, Vec<TaskChange>) {
+    let (before, after) = (tasks_of(snapshot), tasks_of(current));
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+    for (id, task) in &after {
+        match before.get(id) {
+            None => added.push(task_change(id, task, Vec::new())),
+            Some(old) if old != task => {
+                let empty = serde_json::Map::new();
+                let (old_fields, new_fields) = (old.as_object().unwrap_or(&empty), task.as_object().unwrap_or(&empty));
+                let fields: BTreeSet<String> = old_fields
+                    .keys()
+                    .chain(new_fields.keys())
+                    .filter(|k| old_fields.get(*k) != new_fields.get(*k))
+                    .cloned()
+                    .collect();
+                changed.push(task_change(id, task, fields.into_iter().collect()));
+            }
+            Some(_) => {}
+        }
+    }
+    let removed = before
+        .iter()
+        .filter(|(id, _)| !after.contains_key(*id))
+        .map(|(id, task)| task_change(id, task, Vec::new()))
+        .collect();
+    (added, removed, changed)
+}
+
+pub fn diff(id: &str) -> Result<SnapshotDiff, String> {
+    let snapshot = find(id)?;
+    let old: Value = serde_json::from_str(&read(id)?).map_err(|e| e.to_string())?;
+    let current = storage::load_json();
+    let (added, removed, changed) = compare(&old, &current);
+
+    let project_ids = |data: &Value| -> HashSet<String> {
+        data.get("projects").and_then(|p| p.as_object()).map(|p| p.keys().cloned().collect()).unwrap_or_default()
+    };
+    let entry_ids = |data: &Value| -> HashSet<String> {
+        data.get("logEntries")
+            .and_then(|l| l.as_array())
+            .map(|l| l.iter().filter_map(|e| e.get("id")?.as_str().map(String::from)).collect())
+            .unwrap_or_default()
+    };
+
+    Ok(SnapshotDiff {
+        snapshot,
+        added,
+        removed,
+        changed,
+        projects: count_ids(project_ids(&old), project_ids(&current)),
+        log_entries: count_ids(entry_ids(&old), entry_ids(&current)),
+    })
+}
+
+/// Replace the data with snapshot `id`, snapshotting the current data first
+/// so the restore can be undone.
+pub fn restore(id: &str) -> Result<Value, String> {
+    find(id)?;
+    let text = read(id)?;
+    let data: Value = serde_json::from_str(&text).map_err(|e| format!("snapshot is not valid JSON: {}", e))?;
+    take("pre-restore")?;
+    encryption::write_raw(&text)?;
+    search::refresh(&data);
+    Ok(data)
+}
+
+/// Take the hourly snapshots in the background.
+pub fn start_scheduler() {
+    thread::spawn(|| loop {
+        if !encryption::is_locked() {
+            take_startup();
+            let due = list()
+                .first()
+                .is_none_or(|s| Utc::now().timestamp_millis() - s.created_at >= SNAPSHOT_INTERVAL_MS);
+            if due {
+                take_quietly("hourly");
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub fn list_snapshots() -> Vec<SnapshotInfo> {
+    list()
+}
+
+#[tauri::command]
+pub fn snapshot_diff(id: String) -> Result<SnapshotDiff, String> {
+    diff(&id)
+}
+
+#[tauri::command]
+pub fn restore_snapshot(app: AppHandle, id: String) -> Result<(), String> {
+    restore(&id)?;
+    crate::tray::refresh(&app);
+    let _ = app.emit("data-changed", ());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Snapshot `id` taken at the local time `day` October 2026, `hh:mm`.
+    fn snapshot(id: &str, day: u32, hh: u32, mm: u32) -> SnapshotInfo {
+        let time = Local.with_ymd_and_hms(2026, 10, day, hh, mm, 0).unwrap();
+        SnapshotInfo { id: id.to_string(), created_at: time.timestamp_millis(), reason: "hourly".to_string(), size: 0 }
+    }
+
+    fn kept(snapshots: &[SnapshotInfo], hourly: u32, daily: u32, weekly: u32) -> Vec<String> {
+        let mut ids: Vec<String> = retained(snapshots, &SnapshotSettings { hourly, daily, weekly }).into_iter().collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn keeps_everything_from_the_last_hour() {
+        let snapshots = [snapshot("a", 19, 12, 0), snapshot("b", 19, 11, 50), snapshot("c", 19, 11, 1), snapshot("d", 19, 11, 0)];
+        assert_eq!(kept(&snapshots, 0, 0, 0), ["a", "b", "c"]);
+        assert!(kept(&[], 24, 7, 8).is_empty());
+    }
+
+    #[test]
+    fn keeps_the_latest_of_each_bucket_up_to_the_limits() {
+        // Monday the 19th is in ISO week 43, the 12th to 18th in 42, the 11th in 41
+        let snapshots = [
+            snapshot("a", 19, 12, 0),
+            snapshot("b", 19, 11, 30),
+            snapshot("c", 19, 10, 45),
+            snapshot("d", 19, 10, 10),
+            snapshot("e", 19, 9, 20),
+            snapshot("f", 18, 18, 0),
+            snapshot("g", 18, 8, 0),
+            snapshot("h", 17, 12, 0),
+            snapshot("i", 11, 12, 0),
+            snapshot("j", 4, 12, 0),
+        ];
+        // Hours 12, 11 and 10 (its latest, c); days 19, 18 (f) and 17; weeks 43, 42 and 41
+        assert_eq!(kept(&snapshots, 3, 3, 3), ["a", "b", "c", "f", "h", "i"]);
+        assert_eq!(kept(&snapshots, 1, 1, 1), ["a", "b"]);
+        assert_eq!(kept(&snapshots, 0, 0, 4), ["a", "b", "f", "i", "j"]);
+        // Limits count buckets that have a snapshot, however far back
+        assert_eq!(kept(&snapshots, 24, 7, 8), ["a", "b", "c", "e", "f", "g", "h", "i", "j"]);
+    }
+}
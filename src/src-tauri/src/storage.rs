@@ -0,0 +1,104 @@
+use std::fs::{self, File, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::encryption;
+
+pub fn data_path() -> PathBuf {
+    let home = dirs::home_dir().expect("Could not find home directory");
+    home.join(".visor")
+}
+
+/// `path` with a leading `~/` replaced by the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().map(|h| h.join(rest)).unwrap_or_else(|| PathBuf::from(path)),
+        None => PathBuf::from(path),
+    }
+}
+
+/// The task data, or an empty object when there is none (or it's locked).
+pub fn load_json() -> Value {
+    match encryption::read_raw() {
+        Ok(Some(raw)) => serde_json::from_str(&raw).unwrap_or(json!({})),
+        Ok(None) => json!({}),
+        Err(e) => {
+            eprintln!("Failed to load data: {}", e);
+            json!({})
+        }
+    }
+}
+
+/// Save the task data. Nothing is written while the data is locked.
+pub fn save_json(data: &Value) {
+    let _ = fs::create_dir_all(data_path());
+    if let Err(e) = encryption::write_raw(&serde_json::to_string_pretty(data).unwrap_or_default()) {
+        eprintln!("Failed to save data: {}", e);
+        return;
+    }
+    crate::search::refresh(data);
+}
+
+/// Load, change and save the task data with no other write in between, so
+/// concurrent writers (API, watchers, the webview) don't undo each other.
+/// Nothing is written when `f` leaves the data as it was.
+pub fn update_json<T>(f: impl FnOnce(&mut Value) -> Result<T, String>) -> Result<T, String> {
+    let _ = fs::create_dir_all(data_path());
+    let mut saved = None;
+    let result = encryption::update_raw(|raw| {
+        let mut data = match raw {
+            Some(raw) => serde_json::from_str(raw).map_err(|e| format!("data is not valid JSON: {}", e))?,
+            None => json!({}),
+        };
+        let before = data.clone();
+        let result = f(&mut data)?;
+        if data == before {
+            return Ok((None, result));
+        }
+        let text = serde_json::to_string_pretty(&data).map_err(|e| e.to_string())?;
+        saved = Some(data);
+        Ok((Some(text), result))
+    })?;
+    if let Some(data) = saved {
+        crate::search::refresh(&data);
+    }
+    Ok(result)
+}
+
+/// Refuse work that needs the data while it's encrypted and locked.
+pub fn ensure_unlocked() -> Result<(), String> {
+    if encryption::is_locked() {
+        return Err(encryption::LOCKED.to_string());
+    }
+    Ok(())
+}
+
+/// Replace `path` with `bytes` via a temporary file and a rename, so readers
+/// never see a partial write.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    let tmp = path.with_extension("tmp");
+    let mut file = File::create(&tmp).map_err(|e| e.to_string())?;
+    file.write_all(bytes).and_then(|_| file.sync_all()).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
+/// Exclusive lock on the data directory, held for the life of the process so
+/// two processes never write the same `data.json`. Released on drop/exit.
+pub struct DataLock(#[allow(dead_code)] File);
+
+pub fn acquire_lock() -> Result<DataLock, String> {
+    let dir = data_path();
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join("visor.lock");
+    let file = File::create(&path).map_err(|e| e.to_string())?;
+    match file.try_lock() {
+        Ok(()) => Ok(DataLock(file)),
+        Err(TryLockError::WouldBlock) => Err(format!(
+            "another Visor process is using {}",
+            dir.display()
+        )),
+        Err(TryLockError::Error(e)) => Err(e.to_string()),
+    }
+}
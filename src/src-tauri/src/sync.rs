@@ -0,0 +1,617 @@
+//! Multi-device sync through a shared folder (`sync.folder`), kept in step
+//! between machines by a file sync tool or a network share.
+//!
+//! Each device appends its changes to `<folder>/<device id>.jsonl` and only
+//! reads the other logs, so the sync tool never sees two writers of a file.
+//! A change sets one field of one task, project, journal entry or template,
+//! stamped with a hybrid logical clock (time, counter, device) that orders
+//! all changes the same way everywhere. Merging keeps the latest write of
+//! each field, so devices that have seen the same logs hold the same data,
+//! whatever order the logs arrived in. Deleting leaves a tombstone: the
+//! entity stays deleted unless one of its fields is written after it, so an
+//! offline edit brings a task back instead of losing it. `taskOrder` merges
+//! like any field and is then repaired to list exactly the project's tasks.
+//! The view stack stays per device.
+//!
+//! Local changes are found by comparing the data with the last merged
+//! version, kept in `~/.visor/sync-base.json`. Once a device's log is mostly
+//! writes that later ones override, it's rewritten to just the latest.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tauri::{AppHandle, Emitter};
+
+use crate::{encryption, search, settings, storage};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const RESYNC_INTERVAL: Duration = Duration::from_secs(60);
+/// Lines a device's log may have before it's compacted.
+const COMPACT_AFTER: usize = 1000;
+/// The synced parts of the data. Tasks and projects are keyed by id; journal
+/// entries and templates are lists of objects with an `id`.
+const COLLECTIONS: [&str; 4] = ["tasks", "projects", "logEntries", "templates"];
+const LISTS: [&str; 2] = ["logEntries", "templates"];
+
+/// Serializes sync runs.
+static RUNNING: Mutex<()> = Mutex::new(());
+
+/// A hybrid logical clock reading: milliseconds, a counter for changes within
+/// the same millisecond, and the device that made the change.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Stamp(pub i64, pub u32, pub String);
+
+/// One line of a device's log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    pub at: Stamp,
+    pub collection: String,
+    pub id: String,
+    /// The field set, or none for a deletion.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(default)]
+    pub value: Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct Clock {
+    pub last: (i64, u32),
+    pub device: String,
+}
+
+impl Clock {
+    /// A stamp later than every change made or seen so far.
+    pub fn tick(&mut self, now: i64) -> Stamp {
+        self.last = if now > self.last.0 { (now, 0) } else { (self.last.0, self.last.1 + 1) };
+        Stamp(self.last.0, self.last.1, self.device.clone())
+    }
+
+    pub fn observe(&mut self, at: &Stamp) {
+        self.last = self.last.max((at.0, at.1));
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct Base {
+    clock: (i64, u32),
+    data: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    /// Changes this device added to its log.
+    pub sent: usize,
+    /// Whether merging changed the local data.
+    pub updated: bool,
+    /// Devices that have logged changes, this one included.
+    pub devices: usize,
+}
+
+/// This device's id, created on first use. Kept out of `settings.json` so
+/// copying the settings to another machine doesn't copy it.
+pub fn device_id() -> Result<String, String> {
+    let path = storage::data_path().join("device-id");
+    if let Ok(id) = fs::read_to_string(&path) {
+        if !id.trim().is_empty() {
+            return Ok(id.trim().to_string());
+        }
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    fs::create_dir_all(storage::data_path()).map_err(|e| e.to_string())?;
+    fs::write(&path, &id).map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+fn base_path() -> PathBuf {
+    storage::data_path().join("sync-base.json")
+}
+
+/// Delete the last merged copy of the data, which is plain text, once the
+/// data is encrypted. Sync is off from then on; if encryption is turned off
+/// again, the next run logs every field anew, with no deletions.
+pub fn forget_base() -> Result<(), String> {
+    let _guard = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+    match fs::remove_file(base_path()) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// The entities of `collection` by id, each as its fields.
+fn entities(data: &Value, collection: &str) -> BTreeMap<String, Map<String, Value>> {
+    match data.get(collection) {
+        Some(Value::Object(map)) => map
+            .iter()
+            .filter_map(|(id, entity)| Some((id.clone(), entity.as_object()?.clone())))
+            .collect(),
+        Some(Value::Array(list)) => list
+            .iter()
+            .filter_map(|entity| {
+                let fields = entity.as_object()?;
+                Some((fields.get("id")?.as_str()?.to_string(), fields.clone()))
+            })
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+/// Changes that turn `base` into `current`, all stamped with one tick. A
+/// removed field is set to `null`.
+pub fn diff(base: &Value, current: &Value, clock: &mut Clock, now: i64) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let mut at = None;
+    let mut push = |collection: &str, id: &str, field: Option<&String>, value: Value| {
+        let at = at.get_or_insert_with(|| clock.tick(now)).clone();
+        changes.push(Change { at, collection: collection.to_string(), id: id.to_string(), field: field.cloned(), value });
+    };
+    for collection in COLLECTIONS {
+        let (before, after) = (entities(base, collection), entities(current, collection));
+        for (id, fields) in &after {
+            let old = before.get(id);
+            for (field, value) in fields {
+                if old.and_then(|o| o.get(field)) != Some(value) {
+                    push(collection, id, Some(field), value.clone());
+                }
+            }
+            for field in old.into_iter().flat_map(|o| o.keys()).filter(|f| !fields.contains_key(*f)) {
+                push(collection, id, Some(field), Value::Null);
+            }
+        }
+        for id in before.keys().filter(|id| !after.contains_key(*id)) {
+            push(collection, id, None, Value::Null);
+        }
+    }
+    changes
+}
+
+#[derive(Default)]
+struct Entity {
+    fields: BTreeMap<String, (Stamp, Value)>,
+    deleted: Option<Stamp>,
+}
+
+impl Entity {
+    fn apply(&mut self, change: Change) {
+        match change.field {
+            None => {
+                if self.deleted.as_ref().is_none_or(|d| change.at > *d) {
+                    self.deleted = Some(change.at);
+                }
+            }
+            Some(field) => {
+                if self.fields.get(&field).is_none_or(|(at, _)| change.at > *at) {
+                    self.fields.insert(field, (change.at, change.value));
+                }
+            }
+        }
+    }
+
+    fn alive(&self) -> bool {
+        match &self.deleted {
+            None => true,
+            Some(deleted) => self.fields.values().any(|(at, _)| at > deleted),
+        }
+    }
+}
+
+/// Make each project's `taskOrder` list its tasks exactly once: entries for
+/// deleted or moved tasks are dropped, and tasks missing from it are added
+/// after their parent's subtree (or at the end), oldest first.
+fn repair_task_order(data: &mut Value) {
+    let tasks = entities(data, "tasks");
+    let indent = |id: &str| tasks.get(id).and_then(|t| t.get("indent")).and_then(|i| i.as_i64()).unwrap_or(0);
+    let Some(projects) = data.get_mut("projects").and_then(|p| p.as_object_mut()) else {
+        return;
+    };
+    for (project_id, project) in projects.iter_mut() {
+        let belongs = |id: &str| {
+            tasks.get(id).and_then(|t| t.get("projectId")).and_then(|p| p.as_str()) == Some(project_id.as_str())
+        };
+        let mut seen = HashSet::new();
+        let mut order: Vec<String> = project
+            .get("taskOrder")
+            .and_then(|o| o.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|id| id.as_str())
+            .filter(|id| belongs(id) && seen.insert(id.to_string()))
+            .map(String::from)
+            .collect();
+
+        let mut missing: Vec<(&String, &Map<String, Value>)> =
+            tasks.iter().filter(|(id, _)| belongs(id) && !seen.contains(*id)).collect();
+        missing.sort_by_key(|(id, task)| (task.get("createdAt").and_then(|c| c.as_i64()).unwrap_or(0), id.to_string()));
+        for (id, task) in missing {
+            let parent = task.get("parentId").and_then(|p| p.as_str());
+            let position = match parent.and_then(|p| order.iter().position(|o| o == p)) {
+                Some(at) => {
+                    let parent_indent = indent(&order[at]);
+                    at + 1 + order[at + 1..].iter().take_while(|o| indent(o) > parent_indent).count()
+                }
+                None => order.len(),
+            };
+            order.insert(position, id.clone());
+        }
+        if let Some(fields) = project.as_object_mut() {
+            fields.insert("taskOrder".to_string(), json!(order));
+        }
+    }
+}
+
+/// Replay `changes` over `local`: the synced collections are rebuilt from the
+/// changes, everything else is kept.
+pub fn merge(changes: impl IntoIterator<Item = Change>, local: &Value) -> Value {
+    let mut state: BTreeMap<(String, String), Entity> = BTreeMap::new();
+    for change in changes {
+        state.entry((change.collection.clone(), change.id.clone())).or_default().apply(change);
+    }
+
+    let mut data = if local.is_object() { local.clone() } else { json!({}) };
+    for collection in COLLECTIONS {
+        let mut merged: Vec<(String, Map<String, Value>)> = state
+            .iter()
+            .filter(|((c, _), entity)| c == collection && entity.alive())
+            .map(|((_, id), entity)| {
+                let fields = entity.fields.iter().map(|(f, (_, v))| (f.clone(), v.clone())).collect();
+                (id.clone(), fields)
+            })
+            .collect();
+        data[collection] = if LISTS.contains(&collection) {
+            merged.sort_by_key(|(id, fields)| (fields.get("createdAt").and_then(|c| c.as_i64()).unwrap_or(0), id.clone()));
+            Value::Array(merged.into_iter().map(|(_, fields)| Value::Object(fields)).collect())
+        } else {
+            Value::Object(merged.into_iter().map(|(id, fields)| (id, Value::Object(fields))).collect())
+        };
+    }
+    repair_task_order(&mut data);
+    data
+}
+
+/// `mine`, an edit of `base`, replayed over `current`, which another writer
+/// saved since: the fields `mine` changed win and the rest of `current` is
+/// kept, so neither side's new or edited tasks are lost.
+pub fn rebase(base: &Value, mine: &Value, current: &Value) -> Value {
+    let mut clock = Clock { last: (0, 0), device: String::new() };
+    let mut changes = diff(&json!({}), current, &mut clock, 0);
+    changes.extend(diff(base, mine, &mut clock, 0));
+    merge(changes, mine)
+}
+
+/// Every change in the folder's logs. Lines that don't parse (one still
+/// being copied by the sync tool) are skipped and read next time.
+fn read_logs(folder: &Path) -> Result<Vec<Change>, String> {
+    let mut changes = Vec::new();
+    for entry in fs::read_dir(folder).map_err(|e| e.to_string())?.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("jsonl") {
+            continue;
+        }
+        let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        changes.extend(text.lines().filter_map(|line| serde_json::from_str::<Change>(line).ok()));
+    }
+    Ok(changes)
+}
+
+fn to_lines(changes: &[Change]) -> Result<String, String> {
+    let mut text = String::new();
+    for change in changes {
+        text.push_str(&serde_json::to_string(change).map_err(|e| e.to_string())?);
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+fn append(path: &Path, changes: &[Change]) -> Result<(), String> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    let text = to_lines(changes)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path).map_err(|e| e.to_string())?;
+    file.write_all(text.as_bytes()).and_then(|_| file.sync_all()).map_err(|e| e.to_string())
+}
+
+/// The latest write of each field and the latest deletion of each entity,
+/// in log order. Merging these gives the same data as merging all of them.
+fn latest<'a>(changes: impl IntoIterator<Item = &'a Change>) -> Vec<Change> {
+    let mut kept: BTreeMap<(&str, &str, Option<&str>), &Change> = BTreeMap::new();
+    for change in changes {
+        let key = (change.collection.as_str(), change.id.as_str(), change.field.as_deref());
+        if kept.get(&key).is_none_or(|k| change.at > k.at) {
+            kept.insert(key, change);
+        }
+    }
+    let mut kept: Vec<Change> = kept.into_values().cloned().collect();
+    kept.sort_by(|a, b| a.at.cmp(&b.at));
+    kept
+}
+
+/// Rewrite this device's log to its latest writes once it has grown past
+/// [`COMPACT_AFTER`] lines, at least half of them overridden.
+fn compact(path: &Path, own: &[&Change]) -> Result<(), String> {
+    if own.len() <= COMPACT_AFTER {
+        return Ok(());
+    }
+    let kept = latest(own.iter().copied());
+    if kept.len() * 2 > own.len() {
+        return Ok(());
+    }
+    storage::write_atomic(path, to_lines(&kept)?.as_bytes())
+}
+
+/// Log local changes, merge every device's log and save the result.
+pub fn sync_once() -> Result<SyncReport, String> {
+    let folder = settings::load().sync.folder;
+    if folder.is_empty() {
+        return Err("no sync folder is set".to_string());
+    }
+    if encryption::enabled() {
+        return Err("sync is not available while the data is encrypted".to_string());
+    }
+    let _guard = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+    let folder = storage::expand_home(&folder);
+    fs::create_dir_all(&folder).map_err(|e| e.to_string())?;
+    let device = device_id()?;
+    let base: Base = fs::read_to_string(base_path())
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+    let mut clock = Clock { last: base.clock, device: device.clone() };
+
+    let (merged, report) = encryption::update_raw(|raw| {
+        let current: Value = match raw {
+            Some(raw) => serde_json::from_str(raw).map_err(|e| format!("data is not valid JSON: {}", e))?,
+            None => json!({}),
+        };
+        // Seen first, so local changes are stamped after everything seen
+        let mut changes = read_logs(&folder)?;
+        for change in &changes {
+            clock.observe(&change.at);
+        }
+        let mine = diff(&base.data, &current, &mut clock, Utc::now().timestamp_millis());
+        let log = folder.join(format!("{}.jsonl", device));
+        append(&log, &mine)?;
+
+        let sent = mine.len();
+        changes.extend(mine);
+        compact(&log, &changes.iter().filter(|c| c.at.2 == device).collect::<Vec<_>>())?;
+        let devices = changes.iter().map(|c| &c.at.2).collect::<HashSet<_>>().len();
+        let merged = merge(changes, &current);
+        let updated = merged != current;
+        let text = updated.then(|| serde_json::to_string_pretty(&merged).unwrap_or_default());
+        Ok((text, (merged, SyncReport { sent, updated, devices })))
+    })?;
+
+    // Only once the data is saved, or the next run would undo the merge
+    let base = Base { clock: clock.last, data: merged };
+    let json = serde_json::to_string(&base).map_err(|e| e.to_string())?;
+    storage::write_atomic(&base_path(), json.as_bytes())?;
+    if report.updated {
+        search::refresh(&base.data);
+    }
+    Ok(report)
+}
+
+fn run_sync(app_handle: &Option<AppHandle>) -> Result<SyncReport, String> {
+    let report = sync_once()?;
+    if report.updated {
+        if let Some(app) = app_handle {
+            crate::tray::refresh(app);
+            let _ = app.emit("data-changed", ());
+        }
+    }
+    Ok(report)
+}
+
+/// Sizes and modification times of the data file and the logs.
+fn signature(folder: &Path) -> Vec<(PathBuf, u64, Option<SystemTime>)> {
+    let mut files = vec![storage::data_path().join("data.json")];
+    if let Ok(entries) = fs::read_dir(folder) {
+        files.extend(entries.flatten().map(|e| e.path()));
+    }
+    files.sort();
+    files
+        .into_iter()
+        .map(|file| {
+            let meta = fs::metadata(&file).ok();
+            let (len, modified) = meta.map_or((0, None), |m| (m.len(), m.modified().ok()));
+            (file, len, modified)
+        })
+        .collect()
+}
+
+/// Sync whenever the data or a log changes, and every minute.
+pub fn start_watcher(app_handle: Option<AppHandle>) {
+    thread::spawn(move || {
+        let mut last_signature = Vec::new();
+        let mut last_sync: Option<Instant> = None;
+        let mut last_error = String::new();
+        loop {
+            let folder = settings::load().sync.folder;
+            if !folder.is_empty() && !encryption::enabled() {
+                let current = signature(&storage::expand_home(&folder));
+                if current != last_signature || last_sync.is_none_or(|t| t.elapsed() >= RESYNC_INTERVAL) {
+                    match run_sync(&app_handle) {
+                        Ok(_) => last_error.clear(),
+                        Err(e) if e != last_error => {
+                            eprintln!("Sync: {}", e);
+                            last_error = e;
+                        }
+                        Err(_) => {}
+                    }
+                    // Taken after syncing, which may have written both
+                    last_signature = signature(&storage::expand_home(&folder));
+                    last_sync = Some(Instant::now());
+                }
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn sync_now(app: AppHandle) -> Result<SyncReport, String> {
+    run_sync(&Some(app))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(project: &str, content: &str, created: i64) -> Value {
+        json!({"content": content, "projectId": project, "status": "TODO", "createdAt": created, "indent": 0, "parentId": null})
+    }
+
+    fn clock(device: &str) -> Clock {
+        Clock { last: (0, 0), device: device.to_string() }
+    }
+
+    #[test]
+    fn converges_whatever_the_log_order() {
+        let start = json!({
+            "projects": {"inbox": {"slug": "inbox", "taskOrder": ["a", "b"]}},
+            "tasks": {"a": task("inbox", "one", 1), "b": task("inbox", "two", 2)},
+            "logEntries": [], "viewStack": [{"type": "home"}]});
+        let (mut ca, mut cb) = (clock("A"), clock("B"));
+        let seed = diff(&json!({}), &start, &mut ca, 100);
+        for change in &seed {
+            cb.observe(&change.at);
+        }
+
+        // A completes a and adds c; B, offline, edits a, deletes b, adds d and a journal entry
+        let mut a = start.clone();
+        a["tasks"]["a"]["status"] = json!("DONE");
+        a["tasks"]["c"] = task("inbox", "three", 3);
+        a["projects"]["inbox"]["taskOrder"] = json!(["a", "b", "c"]);
+        let mut b = start.clone();
+        b["tasks"]["a"]["content"] = json!("one!");
+        b["tasks"].as_object_mut().unwrap().remove("b");
+        b["tasks"]["d"] = task("inbox", "four", 4);
+        b["projects"]["inbox"]["taskOrder"] = json!(["d", "a"]);
+        b["logEntries"] = json!([{"id": "l1", "content": "hi", "createdAt": 5, "projectId": "inbox"}]);
+        b["viewStack"] = json!([{"type": "journal"}]);
+
+        let mut all: Vec<Change> = seed.clone();
+        all.extend(diff(&start, &a, &mut ca, 200));
+        all.extend(diff(&start, &b, &mut cb, 150));
+        let on_a = merge(all.clone(), &a);
+        all.reverse();
+        let on_b = merge(all, &b);
+
+        for key in ["tasks", "projects", "logEntries"] {
+            assert_eq!(on_a[key], on_b[key], "{}", key);
+        }
+        assert_eq!(on_a["tasks"]["a"]["status"], "DONE");
+        assert_eq!(on_a["tasks"]["a"]["content"], "one!");
+        assert!(on_a["tasks"].get("b").is_none());
+        assert_eq!(on_a["logEntries"][0]["id"], "l1");
+        // The view stack stays per device
+        assert_eq!(on_b["viewStack"], json!([{"type": "journal"}]));
+    }
+
+    #[test]
+    fn tombstone_loses_to_a_later_write() {
+        let start = json!({"projects": {"inbox": {"taskOrder": ["a"]}}, "tasks": {"a": task("inbox", "one", 1)}});
+        let (mut ca, mut cb) = (clock("A"), clock("B"));
+        let seed = diff(&json!({}), &start, &mut ca, 100);
+        let mut deleted = start.clone();
+        deleted["tasks"].as_object_mut().unwrap().remove("a");
+        let mut edited = start.clone();
+        edited["tasks"]["a"]["content"] = json!("one!");
+
+        // Edited after the delete: the task comes back with all its fields
+        let mut changes: Vec<Change> = seed.iter().cloned().chain(diff(&start, &deleted, &mut ca, 200)).collect();
+        changes.extend(diff(&start, &edited, &mut cb, 300));
+        let merged = merge(changes, &deleted);
+        assert_eq!(merged["tasks"]["a"]["content"], "one!");
+        assert_eq!(merged["tasks"]["a"]["createdAt"], 1);
+        assert_eq!(merged["projects"]["inbox"]["taskOrder"], json!(["a"]));
+
+        // Edited before it: the delete wins
+        let (mut ca, mut cb) = (clock("A"), clock("B"));
+        let mut changes: Vec<Change> = seed.iter().cloned().chain(diff(&start, &edited, &mut cb, 150)).collect();
+        changes.extend(diff(&start, &deleted, &mut ca, 200));
+        let merged = merge(changes, &edited);
+        assert!(merged["tasks"].get("a").is_none());
+        assert_eq!(merged["projects"]["inbox"]["taskOrder"], json!([]));
+    }
+
+    #[test]
+    fn task_order_lists_each_task_once() {
+        let mut sub = task("inbox", "sub", 9);
+        sub["parentId"] = json!("a");
+        sub["indent"] = json!(1);
+        let mut child = task("inbox", "child", 0);
+        child["parentId"] = json!("a");
+        child["indent"] = json!(1);
+        let data = json!({
+            "projects": {"inbox": {"taskOrder": ["a", "x", "gone", "a", "b", "m"]}, "work": {"taskOrder": []}},
+            "tasks": {"a": task("inbox", "a", 1), "x": child, "b": task("inbox", "b", 2), "s": sub,
+                      "n": task("inbox", "new", 10), "m": task("work", "moved", 3)}});
+        let merged = merge(diff(&json!({}), &data, &mut clock("A"), 1), &data);
+        // Duplicates, deleted and moved tasks dropped; a subtask missing from
+        // the order goes after its parent's subtree, others at the end
+        assert_eq!(merged["projects"]["inbox"]["taskOrder"], json!(["a", "x", "s", "b", "n"]));
+        assert_eq!(merged["projects"]["work"]["taskOrder"], json!(["m"]));
+    }
+
+    #[test]
+    fn compacting_keeps_the_merge() {
+        let mut c = clock("A");
+        let mut data = json!({"projects": {"inbox": {"taskOrder": []}}, "tasks": {}});
+        let mut log = diff(&json!({}), &data, &mut c, 1);
+        for i in 0..50 {
+            let before = data.clone();
+            let id = format!("t{}", i % 5);
+            if i % 7 == 6 {
+                data["tasks"].as_object_mut().unwrap().remove(&id);
+            } else {
+                data["tasks"][&id] = task("inbox", &format!("edit {}", i), i);
+            }
+            log.extend(diff(&before, &data, &mut c, 10 + i));
+        }
+        let kept = latest(&log);
+        assert!(kept.len() < log.len() / 2);
+        assert_eq!(merge(kept, &json!({})), merge(log, &json!({})));
+    }
+
+    #[test]
+    fn stale_save_is_replayed_over_the_merge() {
+        let base = json!({"projects": {"inbox": {"taskOrder": ["a"]}}, "tasks": {"a": task("inbox", "one", 1)},
+                          "viewStack": [{"type": "home"}]});
+        // A merge adds b while the window, still showing base, edits a and adds c
+        let mut current = base.clone();
+        current["tasks"]["b"] = task("inbox", "two", 2);
+        current["projects"]["inbox"]["taskOrder"] = json!(["a", "b"]);
+        let mut mine = base.clone();
+        mine["tasks"]["a"]["status"] = json!("DONE");
+        mine["tasks"]["c"] = task("inbox", "three", 3);
+        mine["projects"]["inbox"]["taskOrder"] = json!(["a", "c"]);
+        mine["viewStack"] = json!([{"type": "project", "projectId": "inbox"}]);
+
+        let saved = rebase(&base, &mine, &current);
+        assert_eq!(saved["tasks"]["a"]["status"], "DONE");
+        assert_eq!(saved["tasks"]["b"]["content"], "two");
+        assert_eq!(saved["tasks"]["c"]["content"], "three");
+        assert_eq!(saved["projects"]["inbox"]["taskOrder"], json!(["a", "c", "b"]));
+        assert_eq!(saved["viewStack"], mine["viewStack"]);
+        // So the next sync logs no tombstones
+        let logged = diff(&current, &saved, &mut clock("A"), 1);
+        assert!(!logged.is_empty() && logged.iter().all(|c| c.field.is_some()));
+
+        // A task the window deleted stays deleted
+        let mut deleted = base.clone();
+        deleted["tasks"].as_object_mut().unwrap().remove("a");
+        assert!(rebase(&base, &deleted, &current)["tasks"].get("a").is_none());
+    }
+}
@@ -0,0 +1,158 @@
+//! Task operations on the raw `data.json` value, shared by the HTTP API, quick
+//! capture, launch arguments and `visor-cli`'s offline mode.
+
+use serde_json::{json, Value};
+
+use crate::model::{Task, TaskStatus};
+use crate::parser;
+
+/// Tasks that are neither completed nor archived, as reported by `/api/status`.
+pub fn pending_count(data: &Value) -> usize {
+    data.get("tasks").and_then(|t| t.as_object()).map_or(0, |t| {
+        t.values()
+            .filter(|v| {
+                !v.get("archived").and_then(|a| a.as_bool()).unwrap_or(false)
+                    && !v.get("completed").and_then(|c| c.as_bool()).unwrap_or(false)
+            })
+            .count()
+    })
+}
+
+/// Body of `GET /api/status`.
+pub fn status_summary(data: &Value) -> Value {
+    let task_count = data.get("tasks").and_then(|t| t.as_object()).map_or(0, |t| t.len());
+    let project_count = data.get("projects").and_then(|p| p.as_object()).map_or(0, |p| p.len());
+    json!({
+        "tasks": task_count,
+        "projects": project_count,
+        "pending": pending_count(data)
+    })
+}
+
+/// Look up a project id by slug, falling back to the inbox.
+pub fn project_id_for_slug(data: &Value, slug: &str) -> String {
+    data.get("projects")
+        .and_then(|p| p.as_object())
+        .and_then(|p| {
+            p.iter()
+                .find(|(_, v)| v.get("slug").and_then(|s| s.as_str()) == Some(slug))
+        })
+        .map(|(k, _)| k.clone())
+        .unwrap_or_else(|| "inbox".to_string())
+}
+
+/// Tasks filtered by project slug and status (`pending` or a `TaskStatus`),
+/// as served by `GET /api/tasks`.
+pub fn list_tasks(data: &Value, project: Option<&str>, status: Option<&str>) -> Vec<Value> {
+    let projects = data.get("projects").cloned().unwrap_or(json!({}));
+
+    data.get("tasks")
+        .and_then(|t| t.as_object())
+        .map(|t| {
+            t.values()
+                .filter(|task| {
+                    // Filter by project slug
+                    if let Some(slug) = project {
+                        let project_id = task.get("projectId").and_then(|p| p.as_str()).unwrap_or("");
+                        let project_slug = projects
+                            .get(project_id)
+                            .and_then(|p| p.get("slug"))
+                            .and_then(|s| s.as_str())
+                            .unwrap_or("");
+                        if project_slug != slug {
+                            return false;
+                        }
+                    }
+                    // Filter by status
+                    if let Some(status) = status {
+                        match status {
+                            "pending" => {
+                                let archived = task.get("archived").and_then(|a| a.as_bool()).unwrap_or(false);
+                                let completed = task.get("completed").and_then(|c| c.as_bool()).unwrap_or(false);
+                                if archived || completed {
+                                    return false;
+                                }
+                            }
+                            other => {
+                                let task_status = task.get("status").and_then(|s| s.as_str()).unwrap_or("TODO");
+                                if task_status != other.to_uppercase() {
+                                    return false;
+                                }
+                            }
+                        }
+                    }
+                    true
+                })
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Create a task from raw content (smart syntax included) and append it to the
+/// project's `taskOrder`. Shared by `POST /api/tasks` and quick capture.
+pub fn create_task(data: &mut Value, content: &str, project_slug: &str) -> Result<Value, String> {
+    let parsed = parser::parse_smart_syntax(content, chrono::Local::now());
+    if parsed.content.is_empty() {
+        return Err("content required".to_string());
+    }
+
+    let project_id = project_id_for_slug(data, project_slug);
+    let task = Task {
+        id: uuid::Uuid::new_v4().to_string(),
+        content: parsed.content,
+        completed: false,
+        status: TaskStatus::Todo,
+        archived: false,
+        project_id,
+        parent_id: None,
+        indent: 0,
+        created_at: chrono::Utc::now().timestamp_millis(),
+        completed_at: None,
+        started_at: None,
+        due_at: parsed.due_at,
+        scheduled: parsed.scheduled,
+        notes: None,
+        recurrence: parsed.recurrence,
+        source: None,
+    };
+    insert_task(data, &task)
+}
+
+/// Store a task and append it to its project's `taskOrder`.
+pub fn insert_task(data: &mut Value, task: &Task) -> Result<Value, String> {
+    let value = serde_json::to_value(task).map_err(|e| e.to_string())?;
+
+    if data.get("tasks").is_none() {
+        data["tasks"] = json!({});
+    }
+    data["tasks"][&task.id] = value.clone();
+
+    // Add to project taskOrder
+    if let Some(arr) = data
+        .get_mut("projects")
+        .and_then(|p| p.get_mut(&task.project_id))
+        .and_then(|p| p.get_mut("taskOrder"))
+        .and_then(|o| o.as_array_mut())
+    {
+        arr.push(json!(task.id));
+    }
+
+    Ok(value)
+}
+
+/// Mark a task DONE. Returns the updated task, or `None` if it doesn't exist.
+pub fn complete_task(data: &mut Value, task_id: &str) -> Option<Value> {
+    let task = data.get_mut("tasks").and_then(|t| t.get_mut(task_id))?;
+    task["status"] = json!("DONE");
+    task["completed"] = json!(true);
+    task["completedAt"] = json!(chrono::Utc::now().timestamp_millis());
+    Some(task.clone())
+}
+
+/// Archive a task. Returns the updated task, or `None` if it doesn't exist.
+pub fn archive_task(data: &mut Value, task_id: &str) -> Option<Value> {
+    let task = data.get_mut("tasks").and_then(|t| t.get_mut(task_id))?;
+    task["archived"] = json!(true);
+    Some(task.clone())
+}
@@ -0,0 +1,429 @@
+//! Task templates (`templates` in `data.json`): checklists saved from a project
+//! and instantiated into one, shared by the HTTP API and the frontend.
+//!
+//! Lines may be parameterized: `{{name}}` placeholders are filled from
+//! [`TemplateParams::values`], and `!+3d` / `@-1w` set the deadline or
+//! scheduled day relative to the anchor date (`d`, `w` or `m` units).
+
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{DateTime, Days, Local, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter};
+
+use crate::model::{Task, TaskStatus, Template, TemplateTask};
+use crate::{parser, storage, tasks};
+
+/// Body of `POST /api/templates`: either explicit `tasks`, or the `project`
+/// (slug) to copy them from.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub tasks: Option<Vec<TemplateTask>>,
+    #[serde(default)]
+    pub project: Option<String>,
+}
+
+/// Values supplied when applying a template. Blank values count as missing.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TemplateParams {
+    #[serde(default)]
+    pub values: HashMap<String, String>,
+    /// Day relative dates count from: `YYYY-MM-DD` or a smart-syntax date
+    /// (`fri`, `11/2`). Today when absent.
+    #[serde(default)]
+    pub anchor: Option<String>,
+}
+
+/// What a template asks for before it can be applied.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateInputs {
+    /// Placeholder names, in order of first use.
+    pub variables: Vec<String>,
+    pub relative_dates: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApplyError {
+    /// The template or project doesn't exist.
+    NotFound(String),
+    /// Placeholders without a value.
+    MissingVariables(Vec<String>),
+    Invalid(String),
+}
+
+impl fmt::Display for ApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApplyError::NotFound(e) | ApplyError::Invalid(e) => write!(f, "{}", e),
+            ApplyError::MissingVariables(names) => write!(f, "missing variables: {}", names.join(", ")),
+        }
+    }
+}
+
+/// `{{name}}` placeholders in `text`, as (byte range, trimmed name).
+fn placeholders(text: &str) -> Vec<(std::ops::Range<usize>, &str)> {
+    let mut found = Vec::new();
+    let mut from = 0;
+    while let Some(start) = text[from..].find("{{").map(|i| from + i) {
+        let Some(end) = text[start + 2..].find("}}").map(|i| start + 2 + i) else {
+            break;
+        };
+        let name = text[start + 2..end].trim();
+        if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+            found.push((start..end + 2, name));
+        }
+        from = end + 2;
+    }
+    found
+}
+
+/// A `!+3d` / `@-1w` word: the sigil and the offset to apply.
+fn relative_date(word: &str) -> Option<(char, i64, char)> {
+    let mut chars = word.chars();
+    let sigil = chars.next().filter(|c| matches!(c, '!' | '@'))?;
+    let rest = chars.as_str();
+    let sign = match rest.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let unit = rest.chars().last().filter(|c| matches!(c, 'd' | 'w' | 'm'))?;
+    let amount: i64 = rest[1..rest.len() - 1].parse().ok()?;
+    Some((sigil, amount.checked_mul(sign)?, unit))
+}
+
+fn offset_date(anchor: NaiveDate, amount: i64, unit: char) -> Option<NaiveDate> {
+    let n = amount.unsigned_abs();
+    match (unit, amount >= 0) {
+        ('d', true) => anchor.checked_add_days(Days::new(n)),
+        ('d', false) => anchor.checked_sub_days(Days::new(n)),
+        ('w', true) => anchor.checked_add_days(Days::new(n.checked_mul(7)?)),
+        ('w', false) => anchor.checked_sub_days(Days::new(n.checked_mul(7)?)),
+        (_, true) => anchor.checked_add_months(Months::new(u32::try_from(n).ok()?)),
+        (_, false) => anchor.checked_sub_months(Months::new(u32::try_from(n).ok()?)),
+    }
+}
+
+pub fn inputs(template: &Template) -> TemplateInputs {
+    let mut variables: Vec<String> = Vec::new();
+    for line in &template.tasks {
+        for (_, name) in placeholders(&line.content) {
+            if !variables.iter().any(|v| v == name) {
+                variables.push(name.to_string());
+            }
+        }
+    }
+    let relative_dates = template
+        .tasks
+        .iter()
+        .any(|l| l.content.split_whitespace().any(|w| relative_date(w).is_some()));
+    TemplateInputs { variables, relative_dates }
+}
+
+fn parse_anchor(value: &str, today: NaiveDate) -> Result<NaiveDate, ApplyError> {
+    let value = value.trim();
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .or_else(|| parser::resolve_date(&value.to_lowercase(), today))
+        .ok_or(ApplyError::Invalid(format!("invalid anchor date: {}", value)))
+}
+
+pub fn list_templates(data: &Value) -> Value {
+    data.get("templates").cloned().unwrap_or(json!([]))
+}
+
+fn find_template(data: &Value, id: &str) -> Option<Template> {
+    data.get("templates")
+        .and_then(|t| t.as_array())
+        .and_then(|t| t.iter().find(|t| t.get("id").and_then(|i| i.as_str()) == Some(id)))
+        .and_then(|t| serde_json::from_value(t.clone()).ok())
+}
+
+fn find_project_id(data: &Value, slug: &str) -> Result<String, String> {
+    data.get("projects")
+        .and_then(|p| p.as_object())
+        .and_then(|p| p.iter().find(|(_, v)| v.get("slug").and_then(|s| s.as_str()) == Some(slug)))
+        .map(|(id, _)| id.clone())
+        .ok_or(format!("project not found: {}", slug))
+}
+
+/// The project's unarchived tasks in `taskOrder`, keeping their indent.
+fn project_lines(data: &Value, slug: &str) -> Result<Vec<TemplateTask>, String> {
+    let project_id = find_project_id(data, slug)?;
+    let order = data["projects"][&project_id]["taskOrder"].as_array().cloned().unwrap_or_default();
+    Ok(order
+        .iter()
+        .filter_map(|id| data.get("tasks")?.get(id.as_str()?))
+        .filter_map(|t| serde_json::from_value::<Task>(t.clone()).ok())
+        .filter(|t| !t.archived)
+        .map(|t| TemplateTask { content: t.content, indent: t.indent })
+        .collect())
+}
+
+pub fn create_template(data: &mut Value, new: &NewTemplate) -> Result<Value, String> {
+    let name = new.name.trim();
+    if name.is_empty() {
+        return Err("name required".to_string());
+    }
+    let exists = list_templates(data)
+        .as_array()
+        .is_some_and(|t| t.iter().any(|t| t.get("name").and_then(|n| n.as_str()).is_some_and(|n| n.eq_ignore_ascii_case(name))));
+    if exists {
+        return Err(format!("template already exists: {}", name));
+    }
+
+    let lines = match (&new.tasks, &new.project) {
+        (Some(tasks), _) => tasks.clone(),
+        (None, Some(slug)) => project_lines(data, slug)?,
+        (None, None) => return Err("tasks or project required".to_string()),
+    };
+    if lines.iter().all(|l| l.content.trim().is_empty()) {
+        return Err("template has no tasks".to_string());
+    }
+
+    let template = Template {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        tasks: lines,
+        created_at: chrono::Utc::now().timestamp_millis(),
+    };
+    let value = serde_json::to_value(&template).map_err(|e| e.to_string())?;
+    if data.get("templates").is_none() {
+        data["templates"] = json!([]);
+    }
+    if let Some(arr) = data["templates"].as_array_mut() {
+        arr.push(value.clone());
+    }
+    Ok(value)
+}
+
+/// Remove the template with `id`, returning it.
+pub fn delete_template(data: &mut Value, id: &str) -> Option<Value> {
+    let templates = data.get_mut("templates")?.as_array_mut()?;
+    let index = templates.iter().position(|t| t.get("id").and_then(|i| i.as_str()) == Some(id))?;
+    Some(templates.remove(index))
+}
+
+/// New tasks for `template` in `project_id`. Placeholders and relative dates
+/// are resolved first, then smart syntax is parsed; each line becomes a
+/// subtask of the closest line above it with a smaller indent (too-deep
+/// indents are clamped to one level below it).
+pub fn instantiate(
+    template: &Template,
+    project_id: &str,
+    params: &TemplateParams,
+    now: DateTime<Local>,
+) -> Result<Vec<Task>, ApplyError> {
+    let missing: Vec<String> = inputs(template)
+        .variables
+        .into_iter()
+        .filter(|v| params.values.get(v).is_none_or(|value| value.trim().is_empty()))
+        .collect();
+    if !missing.is_empty() {
+        return Err(ApplyError::MissingVariables(missing));
+    }
+    let anchor = match &params.anchor {
+        Some(value) => parse_anchor(value, now.date_naive())?,
+        None => now.date_naive(),
+    };
+
+    // Last task created at each depth
+    let mut parents: Vec<String> = Vec::new();
+    let mut created = Vec::new();
+
+    for line in &template.tasks {
+        let mut content = line.content.clone();
+        for (range, name) in placeholders(&line.content).into_iter().rev() {
+            content.replace_range(range, &params.values[name]);
+        }
+
+        // Only the first of each, like the smart syntax
+        let (mut due, mut scheduled) = (None, None);
+        let mut words = Vec::new();
+        for word in content.split_whitespace() {
+            // Offsets past the calendar's range are left as text
+            let date = relative_date(word).and_then(|(sigil, amount, unit)| Some((sigil, offset_date(anchor, amount, unit)?)));
+            match date {
+                Some(('!', date)) if due.is_none() => due = Some(date),
+                Some(('@', date)) if scheduled.is_none() => scheduled = Some(date),
+                _ => words.push(word),
+            }
+        }
+
+        let parsed = parser::parse_smart_syntax(&words.join(" "), now);
+        if parsed.content.is_empty() {
+            continue;
+        }
+        let indent = (line.indent as usize).min(parents.len());
+        parents.truncate(indent);
+
+        let task = Task {
+            id: uuid::Uuid::new_v4().to_string(),
+            content: parsed.content,
+            completed: false,
+            status: TaskStatus::Todo,
+            archived: false,
+            project_id: project_id.to_string(),
+            parent_id: parents.last().cloned(),
+            indent: indent as u32,
+            created_at: now.timestamp_millis(),
+            completed_at: None,
+            started_at: None,
+            due_at: due.and_then(parser::end_of_day_millis).or(parsed.due_at),
+            scheduled: scheduled.and_then(parser::end_of_day_millis).or(parsed.scheduled),
+            notes: None,
+            recurrence: parsed.recurrence,
+            source: None,
+        };
+        parents.push(task.id.clone());
+        created.push(task);
+    }
+    Ok(created)
+}
+
+/// Instantiate template `id` into the project with slug `project` (the inbox
+/// by default), returning the created tasks.
+pub fn apply_template(
+    data: &mut Value,
+    id: &str,
+    project: Option<&str>,
+    params: &TemplateParams,
+) -> Result<Vec<Value>, ApplyError> {
+    let template = find_template(data, id).ok_or(ApplyError::NotFound(format!("template not found: {}", id)))?;
+    let project_id = find_project_id(data, project.unwrap_or("inbox")).map_err(ApplyError::NotFound)?;
+
+    instantiate(&template, &project_id, params, Local::now())?
+        .iter()
+        .map(|task| tasks::insert_task(data, task).map_err(ApplyError::Invalid))
+        .collect()
+}
+
+#[tauri::command]
+pub fn template_inputs(template: Template) -> TemplateInputs {
+    inputs(&template)
+}
+
+/// Tasks for the frontend to add to its own state, so applying a template
+/// doesn't race its pending save of `data.json`.
+#[tauri::command]
+pub fn instantiate_template(template: Template, project_id: String, params: TemplateParams) -> Result<Vec<Task>, String> {
+    instantiate(&template, &project_id, &params, Local::now()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_templates() -> Result<Value, String> {
+    storage::ensure_unlocked()?;
+    Ok(list_templates(&storage::load_json()))
+}
+
+/// Save a template from the given lines or from a project's tasks.
+#[tauri::command]
+pub fn save_template(app: AppHandle, template: NewTemplate) -> Result<Value, String> {
+    let created = storage::update_json(|data| create_template(data, &template))?;
+    let _ = app.emit("data-changed", ());
+    Ok(created)
+}
+
+#[tauri::command]
+pub fn remove_template(app: AppHandle, id: String) -> Result<Value, String> {
+    let deleted = storage::update_json(|data| delete_template(data, &id).ok_or(format!("template not found: {}", id)))?;
+    let _ = app.emit("data-changed", ());
+    Ok(deleted)
+}
+
+/// [`apply_template`], saved; `project` is a slug (the inbox by default).
+#[tauri::command]
+pub fn apply_saved_template(
+    app: AppHandle,
+    id: String,
+    project: Option<String>,
+    params: TemplateParams,
+) -> Result<Vec<Value>, String> {
+    let created = storage::update_json(|data| {
+        apply_template(data, &id, project.as_deref(), &params).map_err(|e| e.to_string())
+    })?;
+    let _ = app.emit("data-changed", ());
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn template(lines: &[(&str, u32)]) -> Template {
+        Template {
+            id: "t".to_string(),
+            name: "Launch".to_string(),
+            tasks: lines.iter().map(|(content, indent)| TemplateTask { content: content.to_string(), indent: *indent }).collect(),
+            created_at: 0,
+        }
+    }
+
+    fn params(values: &[(&str, &str)], anchor: Option<&str>) -> TemplateParams {
+        TemplateParams {
+            values: values.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            anchor: anchor.map(String::from),
+        }
+    }
+
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2026, 10, 19, 9, 0, 0).unwrap()
+    }
+
+    fn day(y: i32, m: u32, d: u32) -> Option<i64> {
+        parser::end_of_day_millis(NaiveDate::from_ymd_opt(y, m, d).unwrap())
+    }
+
+    #[test]
+    fn fills_placeholders_and_nests_lines() {
+        let t = template(&[("Release {{ version }}", 0), ("Tag {{version}} for {{team}}", 3), ("", 1), ("Announce", 1)]);
+        let created = instantiate(&t, "p", &params(&[("version", "2.0"), ("team", "web")], None), now()).unwrap();
+        let contents: Vec<&str> = created.iter().map(|t| t.content.as_str()).collect();
+        assert_eq!(contents, ["Release 2.0", "Tag 2.0 for web", "Announce"]);
+        // Too deep an indent is clamped to one below the line above
+        assert_eq!((created[1].indent, created[1].parent_id.as_ref()), (1, Some(&created[0].id)));
+        assert_eq!(created[2].parent_id.as_ref(), Some(&created[0].id));
+        assert!(created.iter().all(|t| t.project_id == "p" && t.status == TaskStatus::Todo));
+    }
+
+    #[test]
+    fn reports_missing_variables_in_order() {
+        let t = template(&[("Call {{who}} about {{what}}", 0), ("Email {{who}} {{cc}}", 0)]);
+        let error = instantiate(&t, "p", &params(&[("what", "x"), ("cc", "  ")], None), now()).unwrap_err();
+        assert_eq!(error, ApplyError::MissingVariables(vec!["who".to_string(), "cc".to_string()]));
+        assert_eq!(inputs(&t).variables, ["who", "what", "cc"]);
+    }
+
+    #[test]
+    fn resolves_relative_dates_from_the_anchor() {
+        let t = template(&[("Ship !+1w @-2d !+5d", 0), ("Review !-1m", 0), ("Plan !tomorrow", 0)]);
+        assert!(inputs(&t).relative_dates);
+        let created = instantiate(&t, "p", &params(&[], Some("2026-11-02")), now()).unwrap();
+        assert_eq!(created[0].content, "Ship !+5d");
+        assert_eq!(created[0].due_at, day(2026, 11, 9));
+        assert_eq!(created[0].scheduled, day(2026, 10, 31));
+        assert_eq!(created[1].due_at, day(2026, 10, 2));
+        // Smart syntax still counts from today
+        assert_eq!(created[2].due_at, day(2026, 10, 20));
+
+        let error = instantiate(&t, "p", &params(&[], Some("someday")), now()).unwrap_err();
+        assert!(matches!(error, ApplyError::Invalid(_)));
+    }
+
+    #[test]
+    fn overflowing_offsets_are_not_relative_dates() {
+        for word in ["!--9223372036854775808d", "!+9223372036854775807w", "@+99999999999m", "!+99999999999d"] {
+            let created = instantiate(&template(&[(&format!("Odd {}", word), 0)]), "p", &params(&[], None), now()).unwrap();
+            assert_eq!(created[0].due_at, None, "{}", word);
+            assert_eq!(created[0].scheduled, None, "{}", word);
+        }
+        assert_eq!(relative_date("!--9223372036854775808d"), None);
+        assert_eq!(offset_date(NaiveDate::MIN, i64::MAX, 'w'), None);
+    }
+}
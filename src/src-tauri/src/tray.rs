@@ -0,0 +1,88 @@
+//! System tray icon. Shows the pending-task count (as in `/api/status`) and
+//! offers the visor's main actions without opening it.
+
+use std::sync::Arc;
+
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Listener, Manager};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::api_server::ApiState;
+use crate::{quick_capture, storage, tasks};
+
+const TRAY_ID: &str = "main";
+const FOCUS_MINUTES: u32 = 25;
+
+pub fn create(app: &AppHandle) -> tauri::Result<()> {
+    let api_item = MenuItem::with_id(app, "api", "Pause API Server", true, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &MenuItem::with_id(app, "toggle", "Toggle Visor", true, None::<&str>)?,
+            &MenuItem::with_id(app, "quick-add", "Quick Add…", true, None::<&str>)?,
+            &MenuItem::with_id(
+                app,
+                "focus",
+                format!("Start Focus ({} min)", FOCUS_MINUTES),
+                true,
+                None::<&str>,
+            )?,
+            &PredefinedMenuItem::separator(app)?,
+            &api_item,
+            &MenuItem::with_id(app, "data-folder", "Open Data Folder", true, None::<&str>)?,
+            &PredefinedMenuItem::separator(app)?,
+            &MenuItem::with_id(app, "quit", "Quit Visor", true, None::<&str>)?,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&menu)
+        .show_menu_on_left_click(true)
+        .on_menu_event(move |app, event| match event.id().as_ref() {
+            "toggle" => crate::toggle_visor(app),
+            "quick-add" => quick_capture::toggle(app),
+            // The timer lives in the webview store
+            "focus" => {
+                let _ = app.emit("focus-start", FOCUS_MINUTES);
+            }
+            "api" => {
+                let state = app.state::<Arc<ApiState>>();
+                let paused = !state.is_paused();
+                state.set_paused(paused);
+                let label = if paused {
+                    "Resume API Server"
+                } else {
+                    "Pause API Server"
+                };
+                let _ = api_item.set_text(label);
+            }
+            "data-folder" => {
+                let path = storage::data_path().to_string_lossy().to_string();
+                if let Err(e) = app.opener().open_path(path, None::<&str>) {
+                    eprintln!("Failed to open data folder: {}", e);
+                }
+            }
+            "quit" => app.exit(0),
+            _ => {}
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    refresh(app);
+    let handle = app.clone();
+    app.listen_any("data-changed", move |_| refresh(&handle));
+    Ok(())
+}
+
+/// Recompute the pending count shown in the tooltip and next to the icon.
+pub fn refresh(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let pending = tasks::pending_count(&storage::load_json());
+    let _ = tray.set_tooltip(Some(format!("Visor — {} pending", pending)));
+    let _ = tray.set_title((pending > 0).then(|| pending.to_string()));
+}
@@ -2,10 +2,14 @@ import './index.css';
 import { WindowContainer } from './components/layout/WindowContainer';
 import { useVisorToggle } from './hooks/useVisorToggle';
 import { usePersistence } from './hooks/usePersistence';
+import { useBackendEvents } from './hooks/useBackendEvents';
+import { useWindowGeometry } from './hooks/useWindowGeometry';
 
 function App() {
   const { state } = useVisorToggle();
   usePersistence();
+  useBackendEvents();
+  const { edge } = useWindowGeometry();
 
   const animClass = state === 'visible'
     ? 'animate-slide-down'
@@ -14,7 +18,7 @@ function App() {
       : '';
 
   return (
-    <div className={`visor-root ${animClass}`}>
+    <div className={`visor-root ${animClass}`} data-edge={edge}>
       <WindowContainer />
     </div>
   );
@@ -1,8 +1,8 @@
+import { useEffect, useRef } from 'react';
 import { useStore } from '../../store';
-import { ViewEntry, Task, Project, TASK_STATUS_CONFIG } from '../../store/types';
+import { ViewEntry, Task, Project, SearchHit, TASK_STATUS_CONFIG } from '../../store/types';
 import { TaskItem } from '../tasks/TaskItem';
 import { Kbd } from '../common/Kbd';
-import { fuzzySearchTasks } from '../../lib/search';
 
 // --- Date helpers ---
 
@@ -337,21 +337,21 @@ function TemplatesView() {
 
 // --- Journal View ---
 
+function formatLogTime(ts: number) {
+    const d = new Date(ts);
+    const now = new Date();
+    const isToday = d.toDateString() === now.toDateString();
+    const time = d.toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' });
+    if (isToday) return time;
+    return `${d.toLocaleDateString([], { month: 'short', day: 'numeric' })} ${time}`;
+}
+
 function JournalView({ projectId }: { projectId: string }) {
     const { logEntries, projects } = useStore();
     const project = projects[projectId];
 
     const entries = [...logEntries].filter(e => e.projectId === projectId).reverse();
 
-    const formatTime = (ts: number) => {
-        const d = new Date(ts);
-        const now = new Date();
-        const isToday = d.toDateString() === now.toDateString();
-        const time = d.toLocaleTimeString([], { hour: '2-digit', minute: '2-digit' });
-        if (isToday) return time;
-        return `${d.toLocaleDateString([], { month: 'short', day: 'numeric' })} ${time}`;
-    };
-
     return (
         <div className="journal-view">
             <h3 className="journal-title">Journal \u2014 {project?.name || 'Inbox'}</h3>
@@ -364,7 +364,7 @@ function JournalView({ projectId }: { projectId: string }) {
                 <div className="journal-entries">
                     {entries.map(entry => (
                         <div key={entry.id} className="journal-entry">
-                            <span className="journal-time">{formatTime(entry.createdAt)}</span>
+                            <span className="journal-time">{formatLogTime(entry.createdAt)}</span>
                             <span className="journal-content">{entry.content}</span>
                         </div>
                     ))}
@@ -424,25 +424,32 @@ function HelpView() {
 // --- Search View ---
 
 function SearchView({ query }: { query: string }) {
-    const { tasks, projects, selectedItemIndex, cycleTaskStatus } = useStore();
+    const { tasks, projects, searchResults, runSearch, selectedItemIndex, navigateToTask, pushView } = useStore();
+
+    useEffect(() => {
+        runSearch(query);
+    }, [query, runSearch]);
 
-    const activeTasks = Object.values(tasks).filter(t => !t.archived);
-    const results = fuzzySearchTasks(activeTasks, query).slice(0, 30);
+    // Same order as getViewItems: hits for tasks deleted since the search are dropped
+    const hits = searchResults.filter(hit => hit.type === 'log' || tasks[hit.id]);
 
     return (
         <div className="search-view">
             <h3 className="search-title">Search: &ldquo;{query}&rdquo;</h3>
-            {results.length === 0 ? (
-                <div className="search-empty">No tasks match &ldquo;{query}&rdquo;</div>
+            {hits.length === 0 ? (
+                <div className="search-empty">Nothing matches &ldquo;{query}&rdquo;</div>
             ) : (
                 <div className="search-results">
-                    {results.map((task, i) => (
-                        <TaskItem
-                            key={task.id}
-                            task={task}
+                    {hits.map((hit, i) => (
+                        <SearchResultRow
+                            key={`${hit.type}-${hit.id}`}
+                            hit={hit}
+                            task={hit.type === 'task' ? tasks[hit.id] : undefined}
+                            project={projects[hit.projectId]}
                             isSelected={i === selectedItemIndex}
-                            onComplete={() => cycleTaskStatus(task.id)}
-                            projectSlug={projects[task.projectId]?.slug}
+                            onOpen={() => hit.type === 'task'
+                                ? navigateToTask(hit.id)
+                                : pushView({ type: 'journal', projectId: hit.projectId })}
                         />
                     ))}
                 </div>
@@ -451,6 +458,77 @@ function SearchView({ query }: { query: string }) {
     );
 }
 
+/** `text` with the ranges wrapped in <mark>. */
+function Highlighted({ text, ranges }: { text: string; ranges: [number, number][] }) {
+    const parts: React.ReactNode[] = [];
+    let pos = 0;
+    for (const [start, end] of ranges) {
+        if (start < pos) continue;
+        if (start > pos) parts.push(text.slice(pos, start));
+        parts.push(<mark key={start}>{text.slice(start, end)}</mark>);
+        pos = end;
+    }
+    parts.push(text.slice(pos));
+    return <>{parts}</>;
+}
+
+/** The matched line of a multi-line field, with ranges shifted to it. */
+function matchedLine(text: string, ranges: [number, number][]): { line: string; ranges: [number, number][] } {
+    const first = ranges[0]?.[0] ?? 0;
+    const lineStart = text.lastIndexOf('\n', first - 1) + 1;
+    const newline = text.indexOf('\n', first);
+    const lineEnd = newline === -1 ? text.length : newline;
+    return {
+        line: text.slice(lineStart, lineEnd),
+        ranges: ranges
+            .filter(([start, end]) => start >= lineStart && end <= lineEnd)
+            .map(([start, end]) => [start - lineStart, end - lineStart]),
+    };
+}
+
+function SearchResultRow({ hit, task, project, isSelected, onOpen }: {
+    hit: SearchHit;
+    task?: Task;
+    project?: Project;
+    isSelected: boolean;
+    onOpen: () => void;
+}) {
+    const indexed = hit.item as Task;
+    const rangesFor = (field: 'content' | 'notes') => hit.matches.find(m => m.field === field)?.ranges ?? [];
+    // Highlights are only valid for the text that was indexed
+    const content = task?.content ?? indexed.content;
+    const contentRanges = content === indexed.content ? rangesFor('content') : [];
+    const notesRanges = rangesFor('notes');
+    const notes = notesRanges.length > 0 && indexed.notes ? matchedLine(indexed.notes, notesRanges) : null;
+    const status = task ? TASK_STATUS_CONFIG[task.status || 'TODO'] : null;
+    const ref = useRef<HTMLDivElement>(null);
+
+    useEffect(() => {
+        if (isSelected && ref.current) {
+            ref.current.scrollIntoView({ block: 'nearest', behavior: 'smooth' });
+        }
+    }, [isSelected]);
+
+    return (
+        <div ref={ref} className={`search-row ${isSelected ? 'selected' : ''} ${task?.archived ? 'archived' : ''}`} onClick={onOpen}>
+            <span className="search-row-icon" style={status ? { color: status.color } : undefined}>
+                {status ? status.icon : '\u270E'}
+            </span>
+            <span className="search-row-body">
+                <span className="search-row-content"><Highlighted text={content} ranges={contentRanges} /></span>
+                {notes && (
+                    <span className="search-row-notes"><Highlighted text={notes.line} ranges={notes.ranges} /></span>
+                )}
+            </span>
+            <span className="search-row-meta">
+                {task?.archived && <span className="search-row-tag">archived</span>}
+                {hit.type === 'log' && <span className="search-row-tag">journal</span>}
+                {project && <span className="home-task-project" style={{ borderColor: project.color, color: project.color }}>{project.slug}</span>}
+            </span>
+        </div>
+    );
+}
+
 // --- Project Settings View ---
 
 function ProjectSettingsView({ projectId }: { projectId: string }) {
@@ -493,8 +571,9 @@ function ProjectSettingsView({ projectId }: { projectId: string }) {
 // --- Detail View ---
 
 function DetailView({ taskId }: { taskId: string }) {
-    const { tasks, projects } = useStore();
+    const { tasks, projects, logEntries } = useStore();
     const task = tasks[taskId];
+    const entries = logEntries.filter(e => e.taskId === taskId).reverse();
 
     if (!task) return <div className="view-empty">Task not found</div>;
 
@@ -566,6 +645,21 @@ function DetailView({ taskId }: { taskId: string }) {
                     <p className="detail-notes-empty">No notes. Press <Kbd keys="n" size="sm" /> to add.</p>
                 )}
             </div>
+            <div className="detail-notes-section">
+                <span className="detail-label">Journal</span>
+                {entries.length > 0 ? (
+                    <div className="journal-entries detail-journal">
+                        {entries.map(entry => (
+                            <div key={entry.id} className="journal-entry">
+                                <span className="journal-time">{formatLogTime(entry.createdAt)}</span>
+                                <span className="journal-content">{entry.content}</span>
+                            </div>
+                        ))}
+                    </div>
+                ) : (
+                    <p className="detail-notes-empty">No entries. Press <Kbd keys=":" size="sm" /> to log one.</p>
+                )}
+            </div>
             <div className="detail-hints">
                 <p><Kbd keys="e" size="sm" /> edit &nbsp; <Kbd keys="n" size="sm" /> notes &nbsp; <Kbd keys="Space" size="sm" /> status &nbsp; <Kbd keys="Shift+Enter" size="sm" /> subtask &nbsp; <Kbd keys="h" size="sm" /> back</p>
             </div>
@@ -5,6 +5,7 @@ import { BrowseHints } from './BrowseHints';
 import { FocusPill } from './FocusPill';
 import { SettingsModal } from '../settings/SettingsModal';
 import { WelcomeOverlay } from '../onboarding/WelcomeOverlay';
+import { UnlockOverlay } from '../onboarding/UnlockOverlay';
 import { useKeyboard } from '../../hooks/useKeyboard';
 import { useStore } from '../../store';
 
@@ -36,6 +37,7 @@ export function WindowContainer() {
 
             <SettingsModal />
             <WelcomeOverlay />
+            <UnlockOverlay />
         </div>
     );
 }
@@ -0,0 +1,132 @@
+import { useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { useStore } from '../../store';
+
+/** Passphrase prompt shown while the encrypted data is locked. */
+export function UnlockOverlay() {
+    const locked = useStore(s => s.locked);
+    const [passphrase, setPassphrase] = useState('');
+    const [remember, setRemember] = useState(false);
+    const [error, setError] = useState<string | null>(null);
+    const [busy, setBusy] = useState(false);
+
+    if (!locked) return null;
+
+    const submit = (e: React.FormEvent) => {
+        e.preventDefault();
+        if (!passphrase || busy) return;
+        setBusy(true);
+        invoke('unlock_data', { passphrase, remember })
+            .then(() => {
+                setPassphrase('');
+                setError(null);
+                useStore.setState({ locked: false });
+            })
+            .catch((e) => setError(String(e)))
+            .finally(() => setBusy(false));
+    };
+
+    return (
+        <div className="unlock-overlay">
+            <form className="unlock-card" onSubmit={submit}>
+                <h1 className="unlock-title">Visor is locked</h1>
+                <p className="unlock-subtitle">Enter your passphrase to decrypt your tasks</p>
+                <input
+                    className="unlock-input"
+                    type="password"
+                    autoFocus
+                    value={passphrase}
+                    disabled={busy}
+                    onChange={(e) => setPassphrase(e.target.value)}
+                />
+                <label className="unlock-remember">
+                    <input type="checkbox" checked={remember} onChange={(e) => setRemember(e.target.checked)} />
+                    <span>Remember in the system keyring</span>
+                </label>
+                {error && <div className="unlock-error">{error}</div>}
+                <button className="unlock-submit" type="submit" disabled={busy || !passphrase}>
+                    {busy ? 'Unlocking…' : 'Unlock'}
+                </button>
+            </form>
+
+            <style>{`
+        .unlock-overlay {
+          position: fixed;
+          inset: 0;
+          background: rgba(13, 17, 23, 0.95);
+          backdrop-filter: blur(8px);
+          display: flex;
+          align-items: center;
+          justify-content: center;
+          z-index: 300;
+        }
+
+        .unlock-card {
+          width: 320px;
+          padding: 32px;
+          display: flex;
+          flex-direction: column;
+          gap: 12px;
+          text-align: center;
+        }
+
+        .unlock-title {
+          font-size: 22px;
+          color: var(--fg);
+          margin: 0;
+        }
+
+        .unlock-subtitle {
+          font-size: 13px;
+          color: var(--fg4);
+          margin: 0 0 8px 0;
+        }
+
+        .unlock-input {
+          padding: 8px 10px;
+          font-family: var(--font-mono);
+          font-size: 14px;
+          color: var(--fg);
+          background: var(--bg1);
+          border: 1px solid var(--bg2);
+          border-radius: 6px;
+        }
+
+        .unlock-remember {
+          display: flex;
+          align-items: center;
+          gap: 8px;
+          font-size: 12px;
+          color: var(--fg3);
+          cursor: pointer;
+        }
+
+        .unlock-remember input {
+          accent-color: var(--accent);
+        }
+
+        .unlock-error {
+          font-size: 12px;
+          color: var(--red);
+        }
+
+        .unlock-submit {
+          background: var(--accent);
+          color: var(--bg-hard);
+          border: none;
+          padding: 10px 24px;
+          border-radius: 6px;
+          font-size: 14px;
+          font-weight: 600;
+          font-family: var(--font-mono);
+          cursor: pointer;
+        }
+
+        .unlock-submit:disabled {
+          opacity: 0.5;
+          cursor: default;
+        }
+      `}</style>
+        </div>
+    );
+}
@@ -0,0 +1,91 @@
+import { useEffect, useRef, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * Single-line capture window. Task creation (including smart syntax and the
+ * `project: task` prefix) happens in Rust; this component never touches the
+ * visor store.
+ */
+export function QuickCapture() {
+    const inputRef = useRef<HTMLInputElement>(null);
+    const [value, setValue] = useState('');
+    const [error, setError] = useState<string | null>(null);
+
+    useEffect(() => {
+        inputRef.current?.focus();
+
+        // Dismiss when focus moves elsewhere, like the visor does
+        const handleBlur = () => { invoke('quick_capture_close'); };
+        window.addEventListener('blur', handleBlur);
+        return () => window.removeEventListener('blur', handleBlur);
+    }, []);
+
+    const handleSubmit = async (e: React.FormEvent) => {
+        e.preventDefault();
+        if (!value.trim()) return;
+        try {
+            await invoke('quick_capture_submit', { input: value });
+        } catch (err) {
+            setError(String(err));
+        }
+    };
+
+    const handleKeyDown = (e: React.KeyboardEvent) => {
+        if (e.key === 'Escape') {
+            e.preventDefault();
+            invoke('quick_capture_close');
+        }
+    };
+
+    return (
+        <form className="quick-capture glass" onSubmit={handleSubmit}>
+            <span className="quick-capture-badge">TASK</span>
+            <input
+                ref={inputRef}
+                type="text"
+                value={value}
+                onChange={(e) => { setValue(e.target.value); setError(null); }}
+                onKeyDown={handleKeyDown}
+                placeholder="project: task !due @scheduled"
+                className="quick-capture-field"
+                autoFocus
+            />
+            {error
+                ? <span className="quick-capture-ghost quick-capture-error">{error}</span>
+                : value.trim() && <span className="quick-capture-ghost">{'\u21B5 add'}</span>}
+
+            <style>{`
+        .quick-capture {
+          height: 100%;
+          display: flex;
+          align-items: center;
+          gap: 10px;
+          padding: 0 14px;
+          border: 1px solid var(--bg2);
+          border-radius: 8px;
+        }
+
+        .quick-capture-badge {
+          font-size: 11px;
+          font-weight: 600;
+          letter-spacing: 1px;
+          color: var(--mode-task);
+        }
+
+        .quick-capture-field {
+          flex: 1;
+          font-size: 15px;
+        }
+
+        .quick-capture-ghost {
+          font-size: 11px;
+          color: var(--fg4);
+        }
+
+        .quick-capture-error {
+          color: var(--error);
+        }
+      `}</style>
+        </form>
+    );
+}
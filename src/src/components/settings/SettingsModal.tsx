@@ -1,9 +1,16 @@
-import { useState } from 'react';
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import { useStore } from '../../store';
 import { Kbd } from '../common/Kbd';
+import { CalendarImport, EncryptionStatus, HistoryEntry, Insets, MonitorPolicy, Platform, Settings, SnapshotDiff, SnapshotInfo, VisorEdge, WindowSettings } from '../../store/types';
+
+// Global shortcuts, editable; the rest are fixed
+const GLOBAL_SHORTCUTS: Array<{ action: string; key: keyof Settings['keybindings'] }> = [
+    { action: 'Toggle Visor', key: 'toggleVisor' },
+    { action: 'Quick capture', key: 'quickCapture' },
+];
 
 const KEYBINDINGS = [
-    { action: 'Toggle Visor', keys: 'Ctrl+`' },
     { action: 'Normal mode', keys: 'ESC' },
     { action: 'Insert mode', keys: 'i' },
     { action: 'Move up', keys: 'k' },
@@ -17,14 +24,184 @@ const KEYBINDINGS = [
     { action: 'Hide visor', keys: 'ESC' },
 ];
 
-type SettingsSection = 'general' | 'keybindings' | 'about';
+type SettingsSection = 'general' | 'window' | 'calendars' | 'sync' | 'keybindings' | 'security' | 'history' | 'about';
+
+const EDGES: VisorEdge[] = ['top', 'bottom', 'left', 'right'];
+
+const INSET_SIDES: Array<keyof Insets> = ['top', 'right', 'bottom', 'left'];
+
+const RETENTION: Array<{ label: string; key: keyof Settings['snapshots'] }> = [
+    { label: 'Hourly', key: 'hourly' },
+    { label: 'Daily', key: 'daily' },
+    { label: 'Weekly', key: 'weekly' },
+];
+
+const NEW_CALENDAR: CalendarImport = { path: '', project: 'inbox', titlePattern: '', calendar: '', lookaheadDays: 14 };
+
+// Mirrors SyncReport in src-tauri/src/import/ics.rs
+interface SyncReport {
+    created: number;
+    updated: number;
+    archived: number;
+    skippedEdited: number;
+    errors: string[];
+}
+
+// Mirrors SyncReport in src-tauri/src/sync.rs
+interface FolderSyncReport {
+    sent: number;
+    updated: boolean;
+    devices: number;
+}
+
+const PLATFORM: Platform = navigator.userAgent.includes('Mac')
+    ? 'macos'
+    : navigator.userAgent.includes('Windows') ? 'windows' : 'linux';
 
 export function SettingsModal() {
-    const { settingsOpen, toggleSettings, settings, updateSettings } = useStore();
+    const { settingsOpen, toggleSettings, settings, updateSettings, showToast } = useStore();
     const [activeSection, setActiveSection] = useState<SettingsSection>('general');
+    // Launch-at-login is OS state owned by Rust, not part of persisted settings
+    const [autostart, setAutostart] = useState<boolean | null>(null);
+    const [encryption, setEncryption] = useState<EncryptionStatus | null>(null);
+    const [passphrase, setPassphrase] = useState({ current: '', next: '', confirm: '', remember: false });
+    const [snapshots, setSnapshots] = useState<SnapshotInfo[]>([]);
+    const [snapshotDiff, setSnapshotDiff] = useState<SnapshotDiff | null>(null);
+    const [versions, setVersions] = useState<HistoryEntry[]>([]);
+
+    useEffect(() => {
+        if (!settingsOpen) return;
+        invoke<boolean>('get_autostart')
+            .then(setAutostart)
+            .catch((e) => console.error('Failed to read autostart state:', e));
+        invoke<EncryptionStatus>('encryption_status')
+            .then(setEncryption)
+            .catch((e) => console.error('Failed to read encryption status:', e));
+    }, [settingsOpen]);
+
+    const loadSnapshots = () => {
+        invoke<SnapshotInfo[]>('list_snapshots')
+            .then(setSnapshots)
+            .catch((e) => console.error('Failed to list snapshots:', e));
+    };
+
+    const loadVersions = () => {
+        invoke<HistoryEntry[]>('list_history', { limit: 50 })
+            .then(setVersions)
+            .catch((e) => console.error('Failed to read version history:', e));
+    };
+
+    useEffect(() => {
+        if (settingsOpen && activeSection === 'history') {
+            loadSnapshots();
+            loadVersions();
+        }
+    }, [settingsOpen, activeSection]);
+
+    const compareSnapshot = (id: string) => {
+        if (snapshotDiff?.snapshot.id === id) {
+            setSnapshotDiff(null);
+            return;
+        }
+        invoke<SnapshotDiff>('snapshot_diff', { id })
+            .then(setSnapshotDiff)
+            .catch((e) => showToast(`Snapshot: ${e}`));
+    };
+
+    // The current data is snapshotted first in Rust, so a restore can be undone
+    const restoreSnapshot = (snapshot: SnapshotInfo) => {
+        invoke('restore_snapshot', { id: snapshot.id })
+            .then(() => {
+                setSnapshotDiff(null);
+                loadSnapshots();
+                showToast(`Restored snapshot from ${new Date(snapshot.createdAt).toLocaleString()}`);
+            })
+            .catch((e) => showToast(`Restore failed: ${e}`));
+    };
+
+    const toggleAutostart = (enabled: boolean) => {
+        invoke('set_autostart', { enabled })
+            .then(() => setAutostart(enabled))
+            .catch((e) => console.error('Failed to update autostart:', e));
+    };
+
+    const syncCalendars = () => {
+        invoke<SyncReport>('sync_calendars')
+            .then((r) => {
+                const summary = `Calendars: ${r.created} new, ${r.updated} updated, ${r.archived} archived`;
+                showToast(r.errors.length > 0 ? `${summary} (${r.errors[0]})` : summary);
+            })
+            .catch((e) => showToast(`Calendar sync failed: ${e}`));
+    };
+
+    const syncFolder = () => {
+        invoke<FolderSyncReport>('sync_now')
+            .then((r) => showToast(`Synced with ${r.devices} device${r.devices === 1 ? '' : 's'}: ${r.sent} changes sent${r.updated ? ', data updated' : ''}`))
+            .catch((e) => showToast(`Sync failed: ${e}`));
+    };
+
+    // Enable, disable or change the passphrase; each re-encrypts the data file in Rust
+    const runEncryption = (command: string, args: Record<string, unknown>, done: string, confirm = true) => {
+        if (confirm && passphrase.next !== passphrase.confirm) {
+            showToast('Passphrases do not match');
+            return;
+        }
+        invoke<EncryptionStatus>(command, args)
+            .then((status) => {
+                setEncryption(status);
+                setPassphrase({ current: '', next: '', confirm: '', remember: false });
+                showToast(done);
+            })
+            .catch((e) => showToast(`Encryption: ${e}`));
+    };
 
     if (!settingsOpen) return null;
 
+    const updateWindow = (partial: Partial<WindowSettings>) =>
+        updateSettings({ window: { ...settings.window, ...partial } });
+
+    const setMonitorType = (type: MonitorPolicy['type']) =>
+        updateWindow({ monitor: type === 'pinned' ? { type, name: '' } : { type } });
+
+    const setInset = (side: keyof Insets, value: number) =>
+        updateWindow({
+            insets: {
+                ...settings.window.insets,
+                [PLATFORM]: { ...settings.window.insets[PLATFORM], [side]: value },
+            },
+        });
+
+    const updateCalendar = (index: number, partial: Partial<CalendarImport>) =>
+        updateSettings({
+            calendars: settings.calendars.map((c, i) => (i === index ? { ...c, ...partial } : c)),
+        });
+
+    // Half-typed shortcuts and ports don't validate, so these save on blur
+    const setShortcut = (key: keyof Settings['keybindings'], value: string) => {
+        if (value.trim() && value.trim() !== settings.keybindings[key]) {
+            updateSettings({ keybindings: { ...settings.keybindings, [key]: value.trim() } });
+        }
+    };
+
+    const setPort = (value: string) => {
+        const port = Number(value);
+        if (value && port !== settings.api.port) updateSettings({ api: { port } });
+    };
+
+    // Pending changes and the restore itself become commits, so it can be undone
+    const restoreVersion = (version: HistoryEntry) => {
+        invoke('restore_history', { id: version.id })
+            .then(() => {
+                loadVersions();
+                showToast(`Restored version from ${new Date(version.createdAt).toLocaleString()}`);
+            })
+            .catch((e) => showToast(`Restore failed: ${e}`));
+    };
+
+    const setSyncFolder = (value: string) => {
+        if (value.trim() !== settings.sync.folder) updateSettings({ sync: { folder: value.trim() } });
+    };
+
     const handleBackdrop = (e: React.MouseEvent) => {
         if (e.target === e.currentTarget) toggleSettings();
     };
@@ -40,7 +217,7 @@ export function SettingsModal() {
                 <div className="settings-layout">
                     {/* Sidebar */}
                     <nav className="settings-nav">
-                        {(['general', 'keybindings', 'about'] as SettingsSection[]).map(section => (
+                        {(['general', 'window', 'calendars', 'sync', 'keybindings', 'security', 'history', 'about'] as SettingsSection[]).map(section => (
                             <button
                                 key={section}
                                 className={`settings-nav-item ${activeSection === section ? 'active' : ''}`}
@@ -64,12 +241,236 @@ export function SettingsModal() {
                                     />
                                     <span>Show welcome on launch</span>
                                 </label>
+                                <label className="settings-toggle">
+                                    <input
+                                        type="checkbox"
+                                        checked={autostart ?? false}
+                                        disabled={autostart === null}
+                                        onChange={(e) => toggleAutostart(e.target.checked)}
+                                    />
+                                    <span>Launch at login (hidden)</span>
+                                </label>
+                                <label className="settings-field">
+                                    <span>API port (after restart)</span>
+                                    <input
+                                        key={settings.api.port}
+                                        type="number"
+                                        min={1}
+                                        max={65535}
+                                        defaultValue={settings.api.port}
+                                        onBlur={(e) => setPort(e.target.value)}
+                                    />
+                                </label>
+                            </div>
+                        )}
+
+                        {activeSection === 'window' && (
+                            <div className="settings-section">
+                                <h3>Window</h3>
+                                <label className="settings-field">
+                                    <span>Size ({Math.round(settings.window.heightRatio * 100)}% of screen)</span>
+                                    <input
+                                        type="range"
+                                        min={10}
+                                        max={100}
+                                        value={Math.round(settings.window.heightRatio * 100)}
+                                        onChange={(e) => updateWindow({ heightRatio: Number(e.target.value) / 100 })}
+                                    />
+                                </label>
+                                <label className="settings-field">
+                                    <span>Max width (px, empty for full)</span>
+                                    <input
+                                        type="number"
+                                        min={200}
+                                        value={settings.window.maxWidth ?? ''}
+                                        placeholder="full"
+                                        onChange={(e) => updateWindow({ maxWidth: e.target.value ? Number(e.target.value) : null })}
+                                    />
+                                </label>
+                                <label className="settings-field">
+                                    <span>Screen edge</span>
+                                    <select
+                                        value={settings.window.edge}
+                                        onChange={(e) => updateWindow({ edge: e.target.value as VisorEdge })}
+                                    >
+                                        {EDGES.map(edge => <option key={edge} value={edge}>{edge}</option>)}
+                                    </select>
+                                </label>
+                                <label className="settings-field">
+                                    <span>Monitor</span>
+                                    <select
+                                        value={settings.window.monitor.type}
+                                        onChange={(e) => setMonitorType(e.target.value as MonitorPolicy['type'])}
+                                    >
+                                        <option value="follow-cursor">follow cursor</option>
+                                        <option value="primary">primary</option>
+                                        <option value="pinned">pinned</option>
+                                    </select>
+                                </label>
+                                {settings.window.monitor.type === 'pinned' && (
+                                    <label className="settings-field">
+                                        <span>Monitor name</span>
+                                        <input
+                                            type="text"
+                                            value={settings.window.monitor.name}
+                                            onChange={(e) => updateWindow({ monitor: { type: 'pinned', name: e.target.value } })}
+                                        />
+                                    </label>
+                                )}
+                                <label className="settings-toggle">
+                                    <input
+                                        type="checkbox"
+                                        checked={settings.window.coverMenuBar}
+                                        onChange={(e) => updateWindow({ coverMenuBar: e.target.checked })}
+                                    />
+                                    <span>Cover menu bar, docks and panels</span>
+                                </label>
+                                {PLATFORM === 'linux' && (
+                                    <label className="settings-toggle">
+                                        <input
+                                            type="checkbox"
+                                            checked={settings.window.linuxWindowHints}
+                                            onChange={(e) => updateWindow({ linuxWindowHints: e.target.checked })}
+                                        />
+                                        <span>Show on all workspaces, above fullscreen windows</span>
+                                    </label>
+                                )}
+                                {INSET_SIDES.map(side => (
+                                    <label key={side} className="settings-field">
+                                        <span>{side} offset ({PLATFORM}, px)</span>
+                                        <input
+                                            type="number"
+                                            value={settings.window.insets[PLATFORM][side]}
+                                            onChange={(e) => setInset(side, Number(e.target.value) || 0)}
+                                        />
+                                    </label>
+                                ))}
+                            </div>
+                        )}
+
+                        {activeSection === 'calendars' && (
+                            <div className="settings-section">
+                                <h3>Calendars</h3>
+                                <p className="settings-hint">
+                                    Events from local .ics files become tasks scheduled on their day. Changes to the files are picked up automatically.
+                                </p>
+                                {settings.calendars.map((calendar, index) => (
+                                    <div key={index} className="calendar-entry">
+                                        <label className="settings-field">
+                                            <span>File or folder</span>
+                                            <input
+                                                type="text"
+                                                value={calendar.path}
+                                                placeholder="~/calendars/work.ics"
+                                                onChange={(e) => updateCalendar(index, { path: e.target.value })}
+                                            />
+                                        </label>
+                                        <label className="settings-field">
+                                            <span>Project</span>
+                                            <input
+                                                type="text"
+                                                value={calendar.project}
+                                                onChange={(e) => updateCalendar(index, { project: e.target.value })}
+                                            />
+                                        </label>
+                                        <label className="settings-field">
+                                            <span>Title contains (a|b)</span>
+                                            <input
+                                                type="text"
+                                                value={calendar.titlePattern}
+                                                placeholder="any"
+                                                onChange={(e) => updateCalendar(index, { titlePattern: e.target.value })}
+                                            />
+                                        </label>
+                                        <label className="settings-field">
+                                            <span>Calendar name</span>
+                                            <input
+                                                type="text"
+                                                value={calendar.calendar}
+                                                placeholder="any"
+                                                onChange={(e) => updateCalendar(index, { calendar: e.target.value })}
+                                            />
+                                        </label>
+                                        <label className="settings-field">
+                                            <span>Days ahead</span>
+                                            <input
+                                                type="number"
+                                                min={1}
+                                                value={calendar.lookaheadDays}
+                                                onChange={(e) => updateCalendar(index, { lookaheadDays: Math.max(1, Number(e.target.value) || 1) })}
+                                            />
+                                        </label>
+                                        <button
+                                            className="settings-button"
+                                            onClick={() => updateSettings({ calendars: settings.calendars.filter((_, i) => i !== index) })}
+                                        >
+                                            Remove
+                                        </button>
+                                    </div>
+                                ))}
+                                <div className="calendar-actions">
+                                    <button
+                                        className="settings-button"
+                                        onClick={() => updateSettings({ calendars: [...settings.calendars, NEW_CALENDAR] })}
+                                    >
+                                        Add calendar
+                                    </button>
+                                    <button
+                                        className="settings-button"
+                                        disabled={settings.calendars.length === 0}
+                                        onClick={syncCalendars}
+                                    >
+                                        Sync now
+                                    </button>
+                                </div>
+                            </div>
+                        )}
+
+                        {activeSection === 'sync' && (
+                            <div className="settings-section">
+                                <h3>Sync</h3>
+                                <p className="settings-hint">
+                                    Keep several devices in step through a folder shared by a sync tool or network drive. Each device writes its own change log there and merges the others, so edits made offline on both sides are kept. Share this folder instead of ~/.visor. Not available while the data is encrypted.
+                                </p>
+                                <label className="settings-field">
+                                    <span>Shared folder</span>
+                                    <input
+                                        key={settings.sync.folder}
+                                        type="text"
+                                        defaultValue={settings.sync.folder}
+                                        placeholder="~/Dropbox/visor-sync"
+                                        onBlur={(e) => setSyncFolder(e.target.value)}
+                                        onKeyDown={(e) => { if (e.key === 'Enter') e.currentTarget.blur(); }}
+                                    />
+                                </label>
+                                <div className="calendar-actions">
+                                    <button
+                                        className="settings-button"
+                                        disabled={!settings.sync.folder}
+                                        onClick={syncFolder}
+                                    >
+                                        Sync now
+                                    </button>
+                                </div>
                             </div>
                         )}
 
                         {activeSection === 'keybindings' && (
                             <div className="settings-section">
                                 <h3>Keybindings</h3>
+                                {GLOBAL_SHORTCUTS.map(({ action, key }) => (
+                                    <label key={key} className="settings-field">
+                                        <span>{action}</span>
+                                        <input
+                                            key={settings.keybindings[key]}
+                                            type="text"
+                                            defaultValue={settings.keybindings[key]}
+                                            placeholder="Ctrl+Shift+Space"
+                                            onBlur={(e) => setShortcut(key, e.target.value)}
+                                            onKeyDown={(e) => { if (e.key === 'Enter') e.currentTarget.blur(); }}
+                                        />
+                                    </label>
+                                ))}
                                 <div className="keybindings-list">
                                     {KEYBINDINGS.map((kb) => (
                                         <div key={kb.action} className="keybinding-row">
@@ -81,6 +482,171 @@ export function SettingsModal() {
                             </div>
                         )}
 
+                        {activeSection === 'security' && encryption && (
+                            <div className="settings-section">
+                                <h3>Security</h3>
+                                <p className="settings-hint">
+                                    {encryption.enabled
+                                        ? `Your data is encrypted${encryption.remembered ? '; the key is kept in the system keyring' : ' and needs the passphrase at startup'}.`
+                                        : 'Encrypt your data with a passphrase. It cannot be recovered if you forget it.'}
+                                </p>
+                                {!encryption.enabled && settings.history.enabled && (
+                                    <p className="settings-hint">
+                                        The version history holds your data unencrypted, so encrypting deletes it. New versions are kept encrypted.
+                                    </p>
+                                )}
+                                {encryption.enabled && (
+                                    <label className="settings-field">
+                                        <span>Current passphrase</span>
+                                        <input
+                                            type="password"
+                                            value={passphrase.current}
+                                            onChange={(e) => setPassphrase({ ...passphrase, current: e.target.value })}
+                                        />
+                                    </label>
+                                )}
+                                <label className="settings-field">
+                                    <span>New passphrase</span>
+                                    <input
+                                        type="password"
+                                        value={passphrase.next}
+                                        onChange={(e) => setPassphrase({ ...passphrase, next: e.target.value })}
+                                    />
+                                </label>
+                                <label className="settings-field">
+                                    <span>Confirm</span>
+                                    <input
+                                        type="password"
+                                        value={passphrase.confirm}
+                                        onChange={(e) => setPassphrase({ ...passphrase, confirm: e.target.value })}
+                                    />
+                                </label>
+                                <label className="settings-toggle">
+                                    <input
+                                        type="checkbox"
+                                        checked={passphrase.remember}
+                                        onChange={(e) => setPassphrase({ ...passphrase, remember: e.target.checked })}
+                                    />
+                                    <span>Remember in the system keyring</span>
+                                </label>
+                                <div className="calendar-actions">
+                                    {encryption.enabled ? (
+                                        <>
+                                            <button
+                                                className="settings-button"
+                                                disabled={!passphrase.current || !passphrase.next}
+                                                onClick={() => runEncryption('change_encryption_passphrase', {
+                                                    current: passphrase.current,
+                                                    new: passphrase.next,
+                                                    remember: passphrase.remember,
+                                                }, 'Passphrase changed')}
+                                            >
+                                                Change passphrase
+                                            </button>
+                                            <button
+                                                className="settings-button"
+                                                disabled={!passphrase.current}
+                                                onClick={() => runEncryption('disable_encryption', {
+                                                    passphrase: passphrase.current,
+                                                }, 'Encryption disabled', false)}
+                                            >
+                                                Disable encryption
+                                            </button>
+                                        </>
+                                    ) : (
+                                        <button
+                                            className="settings-button"
+                                            disabled={!passphrase.next}
+                                            onClick={() => runEncryption('enable_encryption', {
+                                                passphrase: passphrase.next,
+                                                remember: passphrase.remember,
+                                            }, 'Data encrypted')}
+                                        >
+                                            Encrypt data
+                                        </button>
+                                    )}
+                                </div>
+                            </div>
+                        )}
+
+                        {activeSection === 'history' && (
+                            <div className="settings-section">
+                                <h3>History</h3>
+                                <p className="settings-hint">
+                                    Snapshots of your data are taken at startup, every hour and before imports and restores. The latest of each period below is kept.
+                                </p>
+                                {RETENTION.map(({ label, key }) => (
+                                    <label key={key} className="settings-field">
+                                        <span>{label}</span>
+                                        <input
+                                            type="number"
+                                            min={0}
+                                            value={settings.snapshots[key]}
+                                            onChange={(e) => updateSettings({ snapshots: { ...settings.snapshots, [key]: Math.max(0, Number(e.target.value) || 0) } })}
+                                        />
+                                    </label>
+                                ))}
+                                {snapshots.length === 0 && <p className="settings-hint">No snapshots yet.</p>}
+                                {snapshots.map((snapshot) => (
+                                    <div key={snapshot.id} className="snapshot-entry">
+                                        <div className="snapshot-row">
+                                            <span className="snapshot-date">{new Date(snapshot.createdAt).toLocaleString()}</span>
+                                            <span className="snapshot-reason">{snapshot.reason}</span>
+                                            <button className="settings-button" onClick={() => compareSnapshot(snapshot.id)}>
+                                                {snapshotDiff?.snapshot.id === snapshot.id ? 'Hide' : 'Compare'}
+                                            </button>
+                                            <button className="settings-button" onClick={() => restoreSnapshot(snapshot)}>
+                                                Restore
+                                            </button>
+                                        </div>
+                                        {snapshotDiff?.snapshot.id === snapshot.id && (
+                                            <div className="snapshot-diff">
+                                                {snapshotDiff.added.length + snapshotDiff.removed.length + snapshotDiff.changed.length === 0 && (
+                                                    <div>No task changes since this snapshot</div>
+                                                )}
+                                                {snapshotDiff.added.map((t) => <div key={t.id} className="diff-added">+ {t.content}</div>)}
+                                                {snapshotDiff.removed.map((t) => <div key={t.id} className="diff-removed">− {t.content}</div>)}
+                                                {snapshotDiff.changed.map((t) => (
+                                                    <div key={t.id} className="diff-changed">~ {t.content} ({t.fields?.join(', ')})</div>
+                                                ))}
+                                            </div>
+                                        )}
+                                    </div>
+                                ))}
+
+                                <h3>Version history</h3>
+                                <label className="settings-toggle">
+                                    <input
+                                        type="checkbox"
+                                        checked={settings.history.enabled}
+                                        onChange={(e) => updateSettings({ history: { ...settings.history, enabled: e.target.checked } })}
+                                    />
+                                    <span>Keep a git history of ~/.visor</span>
+                                </label>
+                                <label className="settings-field">
+                                    <span>Commit after (seconds idle)</span>
+                                    <input
+                                        type="number"
+                                        min={1}
+                                        max={3600}
+                                        value={settings.history.quietSeconds}
+                                        onChange={(e) => updateSettings({ history: { ...settings.history, quietSeconds: Math.min(3600, Math.max(1, Number(e.target.value) || 1)) } })}
+                                    />
+                                </label>
+                                {versions.map((version) => (
+                                    <div key={version.id} className="snapshot-entry">
+                                        <div className="snapshot-row">
+                                            <span className="snapshot-date" title={version.message}>{version.summary}</span>
+                                            <span className="snapshot-reason">{new Date(version.createdAt).toLocaleString()}</span>
+                                            <button className="settings-button" onClick={() => restoreVersion(version)}>
+                                                Restore
+                                            </button>
+                                        </div>
+                                    </div>
+                                ))}
+                            </div>
+                        )}
+
                         {activeSection === 'about' && (
                             <div className="settings-section">
                                 <h3>About</h3>
@@ -201,6 +767,108 @@ export function SettingsModal() {
           accent-color: var(--accent);
         }
 
+        .settings-field {
+          display: flex;
+          justify-content: space-between;
+          align-items: center;
+          gap: 12px;
+          font-size: 13px;
+          color: var(--fg);
+          padding: 6px 0;
+        }
+
+        .settings-field input[type="number"],
+        .settings-field input[type="text"],
+        .settings-field input[type="password"],
+        .settings-field select {
+          width: 140px;
+          padding: 2px 6px;
+          font-family: var(--font-mono);
+          font-size: 12px;
+          color: var(--fg);
+          background: var(--bg1);
+          border: 1px solid var(--bg2);
+          border-radius: 4px;
+        }
+
+        .settings-field input[type="range"] {
+          width: 140px;
+          accent-color: var(--accent);
+        }
+
+        .settings-hint {
+          font-size: 12px;
+          color: var(--fg4);
+          margin: 0 0 8px;
+        }
+
+        .snapshot-entry {
+          padding: 6px 0;
+          border-bottom: 1px solid var(--bg1);
+        }
+
+        .snapshot-row {
+          display: flex;
+          align-items: center;
+          gap: 8px;
+          font-size: 12px;
+        }
+
+        .snapshot-date {
+          flex: 1;
+          color: var(--fg);
+        }
+
+        .snapshot-reason {
+          color: var(--fg4);
+        }
+
+        .snapshot-diff {
+          margin-top: 6px;
+          font-size: 12px;
+          color: var(--fg4);
+        }
+
+        .diff-added {
+          color: var(--green);
+        }
+
+        .diff-removed {
+          color: var(--red);
+        }
+
+        .diff-changed {
+          color: var(--yellow);
+        }
+
+        .calendar-entry {
+          padding: 6px 8px;
+          margin-bottom: 8px;
+          border: 1px solid var(--bg2);
+          border-radius: 4px;
+        }
+
+        .calendar-actions {
+          display: flex;
+          gap: 8px;
+        }
+
+        .settings-button {
+          padding: 2px 10px;
+          font-family: var(--font-mono);
+          font-size: 12px;
+          color: var(--fg);
+          background: var(--bg1);
+          border: 1px solid var(--bg2);
+          border-radius: 4px;
+          cursor: pointer;
+        }
+
+        .settings-button:disabled {
+          opacity: 0.5;
+          cursor: default;
+        }
+
         .keybindings-list {
           display: flex;
           flex-direction: column;
@@ -0,0 +1,38 @@
+import { useEffect } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { useStore } from '../store';
+
+// Payload of the `navigate` event
+type NavigateRequest =
+    | { type: 'project'; slug: string }
+    | { type: 'task'; id: string };
+
+/**
+ * Actions requested from outside the webview (tray menu, command-line
+ * arguments, visor:// links) that need store state the Rust side doesn't own.
+ */
+export function useBackendEvents() {
+    useEffect(() => {
+        const unlistenFocus = listen<number>('focus-start', (event) => {
+            useStore.getState().startFocus(event.payload);
+        });
+
+        const unlistenNavigate = listen<NavigateRequest>('navigate', (event) => {
+            const state = useStore.getState();
+            const request = event.payload;
+            if (request.type === 'project') {
+                const project = Object.values(state.projects).find(p => p.slug === request.slug);
+                if (project) state.pushView({ type: 'project', projectId: project.id });
+                else state.showToast(`Project "${request.slug}" not found`);
+            } else if (request.type === 'task') {
+                if (state.tasks[request.id]) state.navigateToTask(request.id);
+                else state.showToast('Task not found');
+            }
+        });
+
+        return () => {
+            unlistenFocus.then(fn => fn());
+            unlistenNavigate.then(fn => fn());
+        };
+    }, []);
+}
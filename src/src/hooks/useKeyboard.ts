@@ -6,6 +6,7 @@ export function useKeyboard() {
     const {
         inputVisible,
         settingsOpen,
+        locked,
         toggleSettings,
         moveSelection,
         cycleTaskStatus,
@@ -25,6 +26,9 @@ export function useKeyboard() {
     } = useStore();
 
     const handleKeyDown = useCallback((e: KeyboardEvent) => {
+        // The unlock prompt takes every key
+        if (locked) return;
+
         // --- Global keys (always active) ---
 
         // Cmd+Shift+Z for redo
@@ -125,6 +129,8 @@ export function useKeyboard() {
                     }
                 } else if (selectedItem.type === 'template') {
                     useStore.getState().applyTemplate(selectedItem.data.id);
+                } else if (selectedItem.type === 'log') {
+                    pushView({ type: 'journal', projectId: selectedItem.data.projectId });
                 }
                 break;
             }
@@ -249,7 +255,7 @@ export function useKeyboard() {
                 }
                 break;
         }
-    }, [inputVisible, settingsOpen, toggleSettings, moveSelection, cycleTaskStatus, archiveTask, undo, redo, popView, pushView, getCurrentView, getViewItems, selectedItemIndex, showInput, hideInput, navigateToTask, moveTaskOrder, tasks]);
+    }, [inputVisible, settingsOpen, locked, toggleSettings, moveSelection, cycleTaskStatus, archiveTask, undo, redo, popView, pushView, getCurrentView, getViewItems, selectedItemIndex, showInput, hideInput, navigateToTask, moveTaskOrder, tasks]);
 
     useEffect(() => {
         window.addEventListener('keydown', handleKeyDown);
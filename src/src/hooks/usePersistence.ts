@@ -2,13 +2,12 @@ import { useEffect, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
 import { useStore } from '../store';
-import { Settings, DEFAULT_SETTINGS, ViewEntry, Template } from '../store/types';
+import { EncryptionStatus, Settings, ViewEntry, Template } from '../store/types';
 
 interface PersistedData {
     tasks: Record<string, any>;
     projects: Record<string, any>;
     logEntries: any[];
-    settings?: Settings;
     templates?: Template[];
     viewStack?: ViewEntry[];
     // V1 compat fields
@@ -16,16 +15,51 @@ interface PersistedData {
     contextStack?: string[];
 }
 
+interface LoadedData {
+    data: string;
+    generation: number;
+}
+
+interface SavedData {
+    generation: number;
+    // The backend wrote since our load, so our edits were replayed over it
+    rebased: boolean;
+}
+
 export function usePersistence() {
+    const locked = useStore(s => s.locked);
     const hasLoaded = useRef(false);
     const saveTimer = useRef<ReturnType<typeof setTimeout> | null>(null);
-    const lastExternalUpdate = useRef(0);
+    // Set while applying reloaded data, which needn't be saved back
+    const reloading = useRef(false);
+    // Generation of the data last loaded or saved, so the backend can tell a save made over a newer write
+    const generation = useRef(0);
 
-    // Load data on mount
+    // Load data on mount, or once the unlock prompt succeeds
     useEffect(() => {
+        if (locked || hasLoaded.current) return;
         async function load() {
+            // Settings live in settings.json, owned by Rust
             try {
-                const raw = await invoke<string>('load_data');
+                useStore.setState({ settings: await invoke<Settings>('get_settings') });
+            } catch (e) {
+                console.error('Failed to load settings:', e);
+            }
+
+            try {
+                const encryption = await invoke<EncryptionStatus>('encryption_status');
+                if (!encryption.unlocked) {
+                    useStore.setState({ locked: true });
+                    return;
+                }
+            } catch (e) {
+                console.error('Failed to read encryption status:', e);
+            }
+
+            try {
+                const loaded = await invoke<LoadedData>('load_data');
+                generation.current = loaded.generation;
+                const raw = loaded.data;
                 if (raw && raw !== 'null') {
                     const data: PersistedData = JSON.parse(raw);
                     const store = useStore.getState();
@@ -79,7 +113,6 @@ export function usePersistence() {
                         tasks: migratedTasks,
                         projects: cleanProjects,
                         logEntries: data.logEntries || store.logEntries,
-                        settings: data.settings || DEFAULT_SETTINGS,
                         templates: data.templates || [],
                         viewStack,
                         dataLoaded: true,
@@ -94,84 +127,111 @@ export function usePersistence() {
             hasLoaded.current = true;
         }
         load();
-    }, []);
+    }, [locked]);
+
+    // Reload the data written by the backend (HTTP API, sync, a rebased save)
+    async function reload() {
+        try {
+            const loaded = await invoke<LoadedData>('load_data');
+            generation.current = loaded.generation;
+            const raw = loaded.data;
+            if (raw && raw !== 'null') {
+                const data: PersistedData = JSON.parse(raw);
+                const store = useStore.getState();
+
+                // Migrate tasks
+                const migratedTasks: Record<string, any> = {};
+                for (const [id, task] of Object.entries(data.tasks || {})) {
+                    const t = task as any;
+                    migratedTasks[id] = {
+                        ...t,
+                        status: t.status || (t.completed ? 'DONE' : 'TODO'),
+                        scheduled: t.scheduled ?? null,
+                        recurrence: t.recurrence ?? null,
+                    };
+                }
+
+                // Only update data, not UI state (viewStack, selectedItemIndex, etc.)
+                reloading.current = true;
+                useStore.setState({
+                    tasks: migratedTasks,
+                    projects: data.projects || store.projects,
+                    logEntries: data.logEntries || store.logEntries,
+                    templates: data.templates || store.templates,
+                });
+                reloading.current = false;
+            }
+        } catch (e) {
+            console.error('Failed to reload data after external change:', e);
+        }
+    }
+
+    async function save() {
+        const s = useStore.getState();
+        const data: PersistedData = {
+            tasks: s.tasks,
+            projects: s.projects,
+            logEntries: s.logEntries,
+            templates: s.templates,
+            viewStack: s.viewStack,
+        };
+        try {
+            const saved = await invoke<SavedData>('save_data', { data: JSON.stringify(data), generation: generation.current });
+            generation.current = saved.generation;
+            if (saved.rebased) await reload();
+        } catch (e) {
+            console.error('Failed to save data:', e);
+            s.showToast('Failed to save changes');
+        }
+    }
 
     // Listen for external data changes (from HTTP API server)
     useEffect(() => {
         let unlisten: (() => void) | null = null;
         (async () => {
-            unlisten = await listen('data-changed', async () => {
-                lastExternalUpdate.current = Date.now();
-                try {
-                    const raw = await invoke<string>('load_data');
-                    if (raw && raw !== 'null') {
-                        const data: PersistedData = JSON.parse(raw);
-                        const store = useStore.getState();
-
-                        // Migrate tasks
-                        const migratedTasks: Record<string, any> = {};
-                        for (const [id, task] of Object.entries(data.tasks || {})) {
-                            const t = task as any;
-                            migratedTasks[id] = {
-                                ...t,
-                                status: t.status || (t.completed ? 'DONE' : 'TODO'),
-                                scheduled: t.scheduled ?? null,
-                                recurrence: t.recurrence ?? null,
-                            };
-                        }
-
-                        // Only update data, not UI state (viewStack, selectedItemIndex, etc.)
-                        useStore.setState({
-                            tasks: migratedTasks,
-                            projects: data.projects || store.projects,
-                            logEntries: data.logEntries || store.logEntries,
-                            templates: data.templates || store.templates,
-                        });
-                    }
-                } catch (e) {
-                    console.error('Failed to reload data after external change:', e);
+            unlisten = await listen('data-changed', () => {
+                // Save pending edits first, or reloading would drop them from the store
+                if (saveTimer.current) {
+                    clearTimeout(saveTimer.current);
+                    saveTimer.current = null;
+                    save();
+                } else {
+                    reload();
                 }
             });
         })();
         return () => { unlisten?.(); };
     }, []);
 
+    // Settings saved through the HTTP API (or this window's updateSettings)
+    useEffect(() => {
+        let unlisten: (() => void) | null = null;
+        (async () => {
+            unlisten = await listen<Settings>('settings-changed', (event) => {
+                useStore.getState().receiveSettings(event.payload);
+            });
+        })();
+        return () => { unlisten?.(); };
+    }, []);
+
     // Save on changes (debounced 500ms)
     useEffect(() => {
         const unsub = useStore.subscribe((state, prev) => {
-            if (!hasLoaded.current) return;
-
-            // Skip save if we just loaded from external update (prevents write-back race)
-            if (Date.now() - lastExternalUpdate.current < 1000) return;
+            if (!hasLoaded.current || reloading.current) return;
 
             // Only save when persistable data changed
             if (
                 state.tasks === prev.tasks &&
                 state.projects === prev.projects &&
                 state.logEntries === prev.logEntries &&
-                state.settings === prev.settings &&
                 state.templates === prev.templates &&
                 state.viewStack === prev.viewStack
             ) return;
 
             if (saveTimer.current) clearTimeout(saveTimer.current);
             saveTimer.current = setTimeout(() => {
-                // Re-check to prevent race
-                if (Date.now() - lastExternalUpdate.current < 1000) return;
-
-                const s = useStore.getState();
-                const data: PersistedData = {
-                    tasks: s.tasks,
-                    projects: s.projects,
-                    logEntries: s.logEntries,
-                    settings: s.settings,
-                    templates: s.templates,
-                    viewStack: s.viewStack,
-                };
-
-                invoke('save_data', { data: JSON.stringify(data) }).catch((e) =>
-                    console.error('Failed to save data:', e)
-                );
+                saveTimer.current = null;
+                save();
             }, 500);
         });
 
@@ -0,0 +1,9 @@
+import { useStore } from '../store';
+
+/**
+ * The window geometry settings. Rust applies them itself when the settings
+ * change, resizing and repositioning the visor immediately.
+ */
+export function useWindowGeometry() {
+    return useStore(s => s.settings.window);
+}
@@ -13,7 +13,7 @@ export const COMMAND_REGISTRY: CommandDef[] = [
     { name: 'use', description: 'Switch to project context', category: 'navigation' },
     { name: 'templates', description: 'Manage task templates', category: 'view' },
     { name: 'template save', description: 'Save current tasks as template', category: 'action' },
-    { name: 'template apply', description: 'Apply a template', category: 'action' },
+    { name: 'template apply', description: 'Apply a template (> template apply name key=value anchor=date)', category: 'action' },
     { name: 'rename', description: 'Rename a project (> rename slug New Name)', category: 'action' },
     { name: 'color', description: 'Set project color (> color slug #hex)', category: 'action' },
     { name: 'delete', description: 'Delete a project (> delete slug)', aliases: ['rm'], category: 'action' },
@@ -32,3 +32,17 @@ export function filterCommands(query: string): CommandDef[] {
         return false;
     });
 }
+
+/**
+ * Split `name key=value key="two words"` (the arguments of `template apply`)
+ * into the template name and its values. `anchor` is the date relative dates
+ * count from.
+ */
+export function parseTemplateArgs(text: string): { name: string; values: Record<string, string> } {
+    const values: Record<string, string> = {};
+    const name = text.replace(/([\w-]+)=(?:"([^"]*)"|(\S*))/g, (_, key: string, quoted?: string, bare?: string) => {
+        values[key] = quoted ?? bare ?? '';
+        return '';
+    });
+    return { name: name.replace(/\s+/g, ' ').trim(), values };
+}
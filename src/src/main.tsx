@@ -1,9 +1,13 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
 import App from "./App";
+import { QuickCapture } from "./components/quick-capture/QuickCapture";
+
+// The quick-capture mini window loads the same bundle with ?window=quick-capture
+const isQuickCapture = new URLSearchParams(window.location.search).get("window") === "quick-capture";
 
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   <React.StrictMode>
-    <App />
+    {isQuickCapture ? <QuickCapture /> : <App />}
   </React.StrictMode>,
 );
@@ -1,4 +1,5 @@
 import { create } from 'zustand';
+import { invoke } from '@tauri-apps/api/core';
 import { v4 as uuidv4 } from 'uuid';
 import {
     Task,
@@ -11,15 +12,16 @@ import {
     UndoAction,
     ViewEntry,
     Template,
+    TemplateInputs,
     FocusTimer,
     Toast,
     Settings,
+    SearchHit,
     DEFAULT_SETTINGS,
     INBOX_PROJECT
 } from './types';
-import { CommandDef, filterCommands } from '../lib/commands';
+import { CommandDef, filterCommands, parseTemplateArgs } from '../lib/commands';
 import { parseDueDate, computeNextOccurrence } from '../lib/parser';
-import { fuzzySearchTasks } from '../lib/search';
 
 interface VisorStore {
     // Data
@@ -29,6 +31,8 @@ interface VisorStore {
     templates: Template[];
     settings: Settings;
     dataLoaded: boolean;
+    /** Encrypted data waiting for its passphrase. */
+    locked: boolean;
 
     // View stack navigation
     viewStack: ViewEntry[];
@@ -57,6 +61,9 @@ interface VisorStore {
     // Toast
     toast: Toast | null;
 
+    // Search
+    searchResults: SearchHit[];
+
     // Focus
     focusTimer: FocusTimer | null;
 
@@ -83,7 +90,12 @@ interface VisorStore {
 
     // --- Selection ---
     moveSelection: (direction: 'up' | 'down') => void;
-    getViewItems: () => Array<{ type: 'project'; data: Project } | { type: 'task'; data: Task } | { type: 'template'; data: Template }>;
+    getViewItems: () => Array<
+        | { type: 'project'; data: Project }
+        | { type: 'task'; data: Task }
+        | { type: 'template'; data: Template }
+        | { type: 'log'; data: LogEntry }
+    >;
 
     // --- Task Actions ---
     addTask: (content: string, projectId?: string) => void;
@@ -103,6 +115,7 @@ interface VisorStore {
     // --- Commands ---
     executeCommand: (command: string) => void;
     searchTasks: (query: string) => void;
+    runSearch: (query: string) => void;
 
     // --- Focus ---
     startFocus: (minutes: number, taskId?: string) => void;
@@ -111,7 +124,7 @@ interface VisorStore {
 
     // --- Templates ---
     saveTemplate: (name: string) => void;
-    applyTemplate: (templateId: string) => void;
+    applyTemplate: (templateId: string, args?: Record<string, string>) => void;
     deleteTemplate: (templateId: string) => void;
 
     // --- Project ---
@@ -132,12 +145,19 @@ interface VisorStore {
 
     // --- Settings ---
     toggleSettings: () => void;
+    /** Saved and validated by Rust; rolled back with a toast when rejected. */
     updateSettings: (partial: Partial<Settings>) => void;
+    /** Settings changed elsewhere (`settings-changed`). */
+    receiveSettings: (settings: Settings) => void;
 
     // --- Visibility ---
     toggleVisibility: () => void;
 }
 
+// Sequence number of the latest updateSettings call, and how many are unanswered
+let settingsRequest = 0;
+let settingsPending = 0;
+
 export const useStore = create<VisorStore>((set, get) => ({
     // --- Initial State ---
     tasks: {},
@@ -146,6 +166,7 @@ export const useStore = create<VisorStore>((set, get) => ({
     templates: [],
     settings: DEFAULT_SETTINGS,
     dataLoaded: false,
+    locked: false,
 
     viewStack: [{ type: 'home' }],
     lastNavDirection: 'push',
@@ -164,6 +185,7 @@ export const useStore = create<VisorStore>((set, get) => ({
     redoStack: [],
 
     toast: null,
+    searchResults: [],
     focusTimer: null,
     settingsOpen: false,
     isVisible: true,
@@ -292,9 +314,12 @@ export const useStore = create<VisorStore>((set, get) => ({
                 return state.templates.map(t => ({ type: 'template' as const, data: t }));
             }
             case 'search': {
-                const activeTasks = Object.values(state.tasks).filter(t => !t.archived);
-                const results = fuzzySearchTasks(activeTasks, view.query).slice(0, 30);
-                return results.map(t => ({ type: 'task' as const, data: t }));
+                const items: Array<{ type: 'task'; data: Task } | { type: 'log'; data: LogEntry }> = [];
+                for (const hit of state.searchResults) {
+                    if (hit.type === 'log') items.push({ type: 'log', data: hit.item as LogEntry });
+                    else if (state.tasks[hit.id]) items.push({ type: 'task', data: state.tasks[hit.id] });
+                }
+                return items;
             }
             case 'detail':
                 return [];
@@ -436,6 +461,7 @@ export const useStore = create<VisorStore>((set, get) => ({
                     status: newStatus,
                     completed: isCompleted || isCancelled,
                     completedAt: isCompleted ? Date.now() : task.completedAt,
+                    startedAt: newStatus === 'DOING' ? Date.now() : task.startedAt,
                 },
             },
             undoStack: [...state.undoStack.slice(-19), { type: 'STATUS_CHANGE', taskId, previousStatus }],
@@ -523,6 +549,7 @@ export const useStore = create<VisorStore>((set, get) => ({
         if (updates.status) {
             updatedTask.completed = updates.status === 'DONE' || updates.status === 'CANCELLED';
             if (updates.status === 'DONE') updatedTask.completedAt = Date.now();
+            if (updates.status === 'DOING' && task.status !== 'DOING') updatedTask.startedAt = Date.now();
         }
 
         return { tasks: { ...state.tasks, [taskId]: updatedTask } };
@@ -631,12 +658,15 @@ export const useStore = create<VisorStore>((set, get) => ({
 
     // --- Journal ---
     addLogEntry: (content) => {
-        const projectId = get().getCurrentProjectId();
+        // Written from a task's detail view: attach it to that task
+        const view = get().viewStack[get().viewStack.length - 1];
+        const task = view?.type === 'detail' ? get().tasks[view.taskId] : undefined;
         const entry: LogEntry = {
             id: uuidv4(),
             content,
             createdAt: Date.now(),
-            projectId,
+            projectId: task ? task.projectId : get().getCurrentProjectId(),
+            ...(task && { taskId: task.id }),
         };
         set(state => ({
             logEntries: [...state.logEntries, entry],
@@ -668,9 +698,10 @@ export const useStore = create<VisorStore>((set, get) => ({
                 const name = args.slice(1).join(' ');
                 if (subCmd === 'save' && name) get().saveTemplate(name);
                 else if (subCmd === 'apply' && name) {
-                    const tmpl = get().templates.find(t => t.name.toLowerCase() === name.toLowerCase());
-                    if (tmpl) get().applyTemplate(tmpl.id);
-                    else get().showToast(`Template "${name}" not found`);
+                    const { name: tmplName, values } = parseTemplateArgs(name);
+                    const tmpl = get().templates.find(t => t.name.toLowerCase() === tmplName.toLowerCase());
+                    if (tmpl) get().applyTemplate(tmpl.id, values);
+                    else get().showToast(`Template "${tmplName}" not found`);
                 } else if (subCmd === 'delete' && name) {
                     const tmpl = get().templates.find(t => t.name.toLowerCase() === name.toLowerCase());
                     if (tmpl) get().deleteTemplate(tmpl.id);
@@ -745,9 +776,23 @@ export const useStore = create<VisorStore>((set, get) => ({
     },
 
     searchTasks: (query) => {
+        set({ searchResults: [] });
         get().pushView({ type: 'search', query });
     },
 
+    // Ranked by the backend index, which also covers notes, archived tasks and journal entries
+    runSearch: (query) => {
+        invoke<SearchHit[]>('search', { query, limit: 30 })
+            .then((hits) => {
+                const view = get().getCurrentView();
+                if (view.type === 'search' && view.query === query) set({ searchResults: hits });
+            })
+            .catch((e) => {
+                set({ searchResults: [] });
+                get().showToast(`Search: ${e}`);
+            });
+    },
+
     // --- Focus ---
     startFocus: (minutes, taskId) => {
         set({
@@ -778,7 +823,7 @@ export const useStore = create<VisorStore>((set, get) => ({
     saveTemplate: (name) => {
         const state = get();
         const projectId = state.getCurrentProjectId();
-        const tasks = state.getProjectTasks(projectId, null);
+        const tasks = state.getProjectTasks(projectId);
         if (tasks.length === 0) {
             get().showToast('No tasks to save as template');
             return;
@@ -793,16 +838,47 @@ export const useStore = create<VisorStore>((set, get) => ({
         get().showToast(`Template "${name}" saved (${tasks.length} tasks)`);
     },
 
-    applyTemplate: (templateId) => {
+    applyTemplate: (templateId, args = {}) => {
         const state = get();
         const template = state.templates.find(t => t.id === templateId);
         if (!template) return;
 
+        // Placeholders, relative dates and nesting are resolved by the backend, shared with the API
         const projectId = state.getCurrentProjectId();
-        for (const tmplTask of template.tasks) {
-            get().addTask(tmplTask.content, projectId);
-        }
-        get().showToast(`Applied template "${template.name}"`);
+        const { anchor, ...values } = args;
+        invoke<TemplateInputs>('template_inputs', { template })
+            .then((inputs) => {
+                // Prompt for what's missing by reopening the command with empty fields
+                const missing = inputs.variables.filter(v => !values[v]?.trim());
+                if (missing.length > 0 || (inputs.relativeDates && !anchor)) {
+                    const today = new Date();
+                    const field = (key: string, value: string) => `${key}=${/\s/.test(value) ? `"${value}"` : value}`;
+                    const fields = inputs.variables.map(v => field(v, values[v] ?? ''));
+                    if (inputs.relativeDates) {
+                        fields.push(field('anchor', anchor || `${today.getFullYear()}-${String(today.getMonth() + 1).padStart(2, '0')}-${String(today.getDate()).padStart(2, '0')}`));
+                    }
+                    get().showInput('command', `> template apply ${template.name} ${fields.join(' ')}`);
+                    get().showToast(missing.length > 0 ? `Fill in ${missing.join(', ')}` : 'Confirm the anchor date');
+                    return;
+                }
+                return invoke<Task[]>('instantiate_template', { template, projectId, params: { values, anchor } });
+            })
+            .then((created) => {
+                if (!created) return;
+                set(s => {
+                    const project = s.projects[projectId];
+                    if (!project) return s;
+                    return {
+                        tasks: { ...s.tasks, ...Object.fromEntries(created.map(t => [t.id, t])) },
+                        projects: {
+                            ...s.projects,
+                            [projectId]: { ...project, taskOrder: [...project.taskOrder, ...created.map(t => t.id)] },
+                        },
+                    };
+                });
+                get().showToast(`Applied template "${template.name}"`);
+            })
+            .catch((e) => get().showToast(`Template: ${e}`));
     },
 
     deleteTemplate: (templateId) => {
@@ -990,12 +1066,39 @@ export const useStore = create<VisorStore>((set, get) => ({
 
     // --- Settings ---
     toggleSettings: () => set(state => ({ settingsOpen: !state.settingsOpen })),
-    updateSettings: (partial) => set(state => ({
-        settings: {
-            general: { ...state.settings.general, ...partial.general },
-            keybindings: { ...state.settings.keybindings, ...partial.keybindings },
-        },
-    })),
+    updateSettings: (partial) => {
+        // Applied locally first so text inputs stay responsive; only the
+        // latest request's answer is kept
+        const request = ++settingsRequest;
+        settingsPending++;
+        set(state => ({
+            settings: {
+                ...state.settings,
+                general: { ...state.settings.general, ...partial.general },
+                keybindings: { ...state.settings.keybindings, ...partial.keybindings },
+                window: { ...state.settings.window, ...partial.window },
+                calendars: partial.calendars ?? state.settings.calendars,
+                api: { ...state.settings.api, ...partial.api },
+                snapshots: { ...state.settings.snapshots, ...partial.snapshots },
+                history: { ...state.settings.history, ...partial.history },
+                sync: { ...state.settings.sync, ...partial.sync },
+            },
+        }));
+        invoke<Settings>('update_settings', { patch: partial })
+            .catch(async (e) => {
+                get().showToast(`Settings not saved: ${e}`);
+                return invoke<Settings>('get_settings');
+            })
+            .then((settings) => {
+                if (request === settingsRequest) set({ settings });
+            })
+            .catch((e) => console.error('Failed to reload settings:', e))
+            .finally(() => settingsPending--);
+    },
+    // Our own updates echo back as events too; their answers are newer
+    receiveSettings: (settings) => {
+        if (settingsPending === 0) set({ settings });
+    },
 
     // --- Visibility ---
     toggleVisibility: () => set(state => ({ isVisible: !state.isVisible })),
@@ -5,6 +5,8 @@ export interface LogEntry {
     content: string;
     createdAt: number;
     projectId: string;
+    /** Task the entry is about, shown in that task's detail view. */
+    taskId?: string;
 }
 
 export type TaskStatus = 'TODO' | 'DOING' | 'DONE' | 'CANCELLED' | 'WAITING';
@@ -35,10 +37,21 @@ export interface Task {
     indent: number;
     createdAt: number;
     completedAt: number | null;
+    /** When the task last moved to DOING. */
+    startedAt?: number | null;
     dueAt: number | null;
     scheduled: number | null;
     notes: string | null;
     recurrence: Recurrence | null;
+    source?: TaskSource | null;
+}
+
+// Mirrors TaskSource in src-tauri/src/model.rs
+export interface TaskSource {
+    type: string;
+    uid: string;
+    origin: string;
+    fingerprint: string;
 }
 
 export interface Project {
@@ -65,6 +78,17 @@ export type ViewEntry =
     | { type: 'project-settings'; projectId: string };
 
 // V2 Input overlay purpose
+// Mirrors SearchHit in src-tauri/src/search.rs
+export interface SearchHit {
+    type: 'task' | 'log';
+    id: string;
+    projectId: string;
+    score: number;
+    item: Task | LogEntry;
+    // [start, end) offsets into the field's text
+    matches: { field: 'content' | 'notes'; ranges: [number, number][] }[];
+}
+
 export type InputPurpose = 'task' | 'command' | 'search' | 'journal' | 'edit' | 'notes';
 
 // V2 Template
@@ -75,6 +99,12 @@ export interface Template {
     createdAt: number;
 }
 
+// Mirrors TemplateInputs in src-tauri/src/templates.rs
+export interface TemplateInputs {
+    variables: string[];
+    relativeDates: boolean;
+}
+
 // V2 Focus timer
 export interface FocusTimer {
     minutes: number;
@@ -102,16 +132,115 @@ export interface Toast {
     timestamp: number;
 }
 
+export type VisorEdge = 'top' | 'bottom' | 'left' | 'right';
+
+export type MonitorPolicy =
+    | { type: 'follow-cursor' }
+    | { type: 'primary' }
+    | { type: 'pinned'; name: string };
+
+export interface Insets {
+    top: number;
+    right: number;
+    bottom: number;
+    left: number;
+}
+
+export type Platform = 'macos' | 'linux' | 'windows';
+
+// Mirrors VisorGeometry in src-tauri/src/geometry.rs
+export interface WindowSettings {
+    heightRatio: number;
+    maxWidth: number | null;
+    edge: VisorEdge;
+    monitor: MonitorPolicy;
+    coverMenuBar: boolean;
+    insets: Record<Platform, Insets>;
+    linuxWindowHints: boolean;
+}
+
+const NO_INSETS: Insets = { top: 0, right: 0, bottom: 0, left: 0 };
+
+// Mirrors CalendarImport in src-tauri/src/import/ics.rs
+export interface CalendarImport {
+    path: string;
+    project: string;
+    titlePattern: string;
+    calendar: string;
+    lookaheadDays: number;
+}
+
+// Mirrors Settings in src-tauri/src/settings.rs (stored in settings.json)
 export interface Settings {
+    version: number;
     general: { showWelcome: boolean };
-    keybindings: { toggleVisor: string };
+    /** Global shortcuts, e.g. 'Ctrl+Shift+Space'. */
+    keybindings: { toggleVisor: string; quickCapture: string };
+    window: WindowSettings;
+    calendars: CalendarImport[];
+    /** Port of the local HTTP API; takes effect after a restart. */
+    api: { port: number };
+    /** How many hourly, daily and weekly snapshots to keep. */
+    snapshots: { hourly: number; daily: number; weekly: number };
+    /** Git version history of ~/.visor, committed after `quietSeconds` without writes. */
+    history: { enabled: boolean; quietSeconds: number };
+    /** Shared folder for multi-device sync; empty when off. */
+    sync: { folder: string };
 }
 
 export const DEFAULT_SETTINGS: Settings = {
+    version: 1,
     general: { showWelcome: true },
-    keybindings: { toggleVisor: 'alt+space' },
+    keybindings: { toggleVisor: 'Ctrl+`', quickCapture: 'Ctrl+Shift+Space' },
+    window: {
+        heightRatio: 0.45,
+        maxWidth: null,
+        edge: 'top',
+        monitor: { type: 'follow-cursor' },
+        coverMenuBar: false,
+        insets: { macos: NO_INSETS, linux: NO_INSETS, windows: NO_INSETS },
+        linuxWindowHints: false,
+    },
+    calendars: [],
+    api: { port: 8745 },
+    snapshots: { hourly: 24, daily: 7, weekly: 8 },
+    history: { enabled: false, quietSeconds: 30 },
+    sync: { folder: '' },
 };
 
+// Mirrors SnapshotInfo in src-tauri/src/snapshots.rs
+export interface SnapshotInfo {
+    id: string;
+    createdAt: number;
+    reason: string;
+    size: number;
+}
+
+// Mirrors SnapshotDiff in src-tauri/src/snapshots.rs; `removed` tasks come back on restore
+export interface SnapshotDiff {
+    snapshot: SnapshotInfo;
+    added: Array<{ id: string; content: string }>;
+    removed: Array<{ id: string; content: string }>;
+    changed: Array<{ id: string; content: string; fields?: string[] }>;
+    projects: { added: number; removed: number };
+    logEntries: { added: number; removed: number };
+}
+
+// Mirrors HistoryEntry in src-tauri/src/history.rs (a git commit)
+export interface HistoryEntry {
+    id: string;
+    createdAt: number;
+    summary: string;
+    message: string;
+}
+
+// Mirrors EncryptionStatus in src-tauri/src/encryption.rs
+export interface EncryptionStatus {
+    enabled: boolean;
+    unlocked: boolean;
+    remembered: boolean;
+}
+
 // Default Inbox project
 export const INBOX_PROJECT: Project = {
     id: 'inbox',